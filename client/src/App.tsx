@@ -2,6 +2,7 @@ import { Switch, Route, Redirect, useLocation } from "wouter";
 import { queryClient } from "./lib/queryClient";
 import { QueryClientProvider } from "@tanstack/react-query";
 import { Toaster } from "@/components/ui/toaster";
+import { TraySync } from "@/components/TraySync";
 import { DriftProvider } from "@/contexts/DriftContext";
 import { AuthProvider, useAuth } from "@/contexts/AuthContext";
 import Dashboard from "@/pages/Dashboard";
@@ -148,6 +149,7 @@ function App() {
         <DriftProvider>
           <Router />
           <Toaster />
+          <TraySync />
         </DriftProvider>
       </AuthProvider>
     </QueryClientProvider>
@@ -0,0 +1,41 @@
+import { useEffect } from "react";
+import { useAuth } from "@/contexts/AuthContext";
+import { useTodayMissions, useTodaySchedule } from "@/lib/api";
+import { invokeShell } from "@/lib/shell";
+
+function localDateString(): string {
+  const now = new Date();
+  return new Date(now.getTime() - now.getTimezoneOffset() * 60000).toISOString().split("T")[0];
+}
+
+// Keeps the desktop tray menu's schedule and mission count current
+function TraySummary() {
+  const { user } = useAuth();
+  const { data: schedule } = useTodaySchedule();
+  const { data: missions } = useTodayMissions();
+
+  useEffect(() => {
+    if (!schedule && !missions) return;
+    invokeShell("set_tray_summary", {
+      summary: {
+        userId: user?.id,
+        date: localDateString(),
+        blocks: (schedule?.timeBlocks ?? []).map((block) => ({
+          title: block.title,
+          startTime: block.startTime,
+          endTime: block.endTime,
+        })),
+        missionsTotal: missions?.length ?? 0,
+        missionsCompleted: missions?.filter((m) => m.status === "completed").length ?? 0,
+      },
+    }).catch((error) => console.error("Failed to update tray:", error));
+  }, [user?.id, schedule, missions]);
+
+  return null;
+}
+
+export function TraySync() {
+  const { isAuthenticated } = useAuth();
+  if (!window.__TAURI_INTERNALS__ || !isAuthenticated) return null;
+  return <TraySummary />;
+}
@@ -3,11 +3,13 @@ import { QueryClient, QueryFunction } from "@tanstack/react-query";
 declare global {
   interface Window {
     __TAURI_INTERNALS__?: unknown;
+    __FORGE_BACKEND_URL__?: string;
   }
 }
 
 const isTauri = typeof window !== "undefined" && !!window.__TAURI_INTERNALS__;
-const API_BASE_URL = isTauri ? "http://localhost:5000" : "";
+// The desktop shell picks a free port for the sidecar and injects its URL
+const API_BASE_URL = isTauri ? (window.__FORGE_BACKEND_URL__ ?? "http://localhost:5000") : "";
 
 export function getApiUrl(path: string): string {
   if (path.startsWith("http")) return path;
@@ -0,0 +1,9 @@
+// Commands of the desktop shell (src-tauri). Outside the desktop app these
+// resolve to null so callers need no checks of their own.
+type Invoke = (cmd: string, args?: Record<string, unknown>) => Promise<unknown>;
+
+export async function invokeShell<T>(cmd: string, args?: Record<string, unknown>): Promise<T | null> {
+  const internals = window.__TAURI_INTERNALS__ as { invoke?: Invoke } | undefined;
+  if (!internals?.invoke) return null;
+  return (await internals.invoke(cmd, args)) as T;
+}
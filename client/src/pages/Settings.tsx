@@ -45,6 +45,7 @@ import {
   type Activity as ActivityType,
 } from "@/lib/api";
 import Footer from "@/components/Footer";
+import { invokeShell } from "@/lib/shell";
 import {
   Dialog,
   DialogContent,
@@ -104,6 +105,24 @@ export default function SettingsPage() {
   // Notification preferences
   const [activityNotifications, setActivityNotifications] = useState(true);
   const [notificationSound, setNotificationSound] = useState(true);
+  // Desktop only: null until the shell answers, and outside the desktop app
+  const [closeToTray, setCloseToTray] = useState<boolean | null>(null);
+
+  useEffect(() => {
+    invokeShell<{ closeToTray: boolean }>("get_tray_settings")
+      .then((settings) => setCloseToTray(settings?.closeToTray ?? null))
+      .catch(() => setCloseToTray(null));
+  }, []);
+
+  const handleToggleCloseToTray = async (checked: boolean) => {
+    setCloseToTray(checked);
+    try {
+      await invokeShell("save_tray_settings", { settings: { closeToTray: checked } });
+    } catch (error) {
+      setCloseToTray(!checked);
+      toast({ title: "Error", description: "Failed to save tray setting", variant: "destructive" });
+    }
+  };
 
   // Activity dialog
   const [activityDialog, setActivityDialog] = useState(false);
@@ -799,6 +818,24 @@ export default function SettingsPage() {
                       />
                     </div>
 
+                    {closeToTray !== null && (
+                      <div className="flex items-center justify-between p-3 bg-background/50 border border-border hover:border-primary/50 transition-colors">
+                        <div className="flex items-center gap-3">
+                          <div>
+                            <p className="font-mono text-sm">Keep Running in Tray</p>
+                            <p className="text-xs font-mono text-muted-foreground">
+                              Closing the window keeps Forge running for reminders
+                            </p>
+                          </div>
+                        </div>
+                        <Switch
+                          data-testid="switch-close-to-tray"
+                          checked={closeToTray}
+                          onCheckedChange={handleToggleCloseToTray}
+                        />
+                      </div>
+                    )}
+
                     {notificationSound && (
                       <>
                         <div className="flex items-center justify-between p-3 bg-background/50 border border-border hover:border-primary/50 transition-colors">
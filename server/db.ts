@@ -8,3 +8,27 @@ if (!connectionString) {
 
 const client = postgres(connectionString);
 export const db = drizzle(client);
+
+// The desktop shell's embedded PostgreSQL starts out empty, so bring its
+// schema in line with shared/schema.ts. Changes that would drop data are
+// never applied automatically. drizzle-kit is a runtime dependency for this
+// reason, and the sidecar bundle includes it.
+export async function ensureEmbeddedSchema(): Promise<void> {
+  if (process.env.FORGE_DB_MODE !== "embedded") return;
+
+  const { pushSchema } = await import("drizzle-kit/api");
+  const schema = await import("@shared/schema");
+  const { hasDataLoss, warnings, statementsToExecute, apply } = await pushSchema(schema, db as any);
+  if (statementsToExecute.length === 0) return;
+  if (hasDataLoss) {
+    console.warn("Skipping embedded schema sync, it would lose data:", warnings);
+    return;
+  }
+  await apply();
+  console.log(`Embedded database schema synced (${statementsToExecute.length} statements)`);
+}
+
+// Waits for in-flight queries to settle before closing the pool
+export async function closeDb(): Promise<void> {
+  await client.end({ timeout: 5 });
+}
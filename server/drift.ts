@@ -0,0 +1,69 @@
+import { insertScheduleDriftEventSchema } from "@shared/schema";
+import { storage } from "./storage";
+
+export interface BlockTiming {
+  scheduleDate: string;
+  blockStartTime: string;
+  blockTitle: string;
+  plannedDuration: number;
+  actualDuration: number;
+}
+
+// Records a drift event when a block overran by more than 10 minutes. Used
+// by the drift check route and by the desktop shell's focus timer, which
+// checks once while a block overruns and again with its final duration; a
+// later check for the same block updates its event rather than adding one.
+export async function checkBlockDrift(userId: number, timing: BlockTiming) {
+  const { scheduleDate, blockStartTime, plannedDuration, actualDuration } = timing;
+
+  // Calculate drift
+  const driftMinutes = actualDuration - plannedDuration;
+
+  // Only create drift event if significant (> 10 minutes overrun)
+  if (driftMinutes <= 10) {
+    return { drift: false, driftMinutes };
+  }
+
+  // Get the schedule for this date to count remaining blocks
+  const schedule = await storage.getScheduleForDate(scheduleDate, userId);
+  let affectedBlocksCount = 0;
+
+  if (schedule) {
+    const blocks = JSON.parse(schedule.scheduleData);
+    // Count blocks that start after this block
+    affectedBlocksCount = blocks.filter((b: any) => b.startTime > blockStartTime).length;
+  }
+
+  // Get existing drift events to calculate cumulative drift
+  const existingDriftEvents = await storage.getDriftEventsForDate(scheduleDate, userId);
+  const existing = existingDriftEvents.find((e) => e.blockStartTime === blockStartTime);
+  const previousDrift = existingDriftEvents
+    .filter((e) => e !== existing)
+    .reduce((sum, e) => sum + e.driftMinutes, 0);
+  const cumulativeDrift = previousDrift + driftMinutes;
+
+  // One drift event per block
+  const eventData = insertScheduleDriftEventSchema.parse({
+    ...timing,
+    userId,
+    driftMinutes,
+    cumulativeDrift,
+    affectedBlocksCount,
+  });
+
+  const event = existing
+    ? await storage.updateDriftEvent(existing.id, {
+        actualDuration: eventData.actualDuration,
+        driftMinutes,
+        cumulativeDrift,
+        affectedBlocksCount,
+      })
+    : await storage.createDriftEvent(eventData);
+
+  return {
+    drift: true,
+    event,
+    // A block the user already rescheduled around is not raised again
+    requiresReschedule: cumulativeDrift >= 15 && affectedBlocksCount > 0 && !existing?.resolved,
+  };
+}
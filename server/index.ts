@@ -6,10 +6,14 @@ import connectPgSimple from "connect-pg-simple";
 import { registerRoutes } from "./routes";
 import { serveStatic } from "./static";
 import { createServer } from "http";
-import { startScheduler } from "./scheduler";
+import { startScheduler, stopScheduler } from "./scheduler";
+import { closeDb, ensureEmbeddedSchema } from "./db";
+import { registerInternalRoutes, requireShellToken } from "./internal";
 
 // Load .env file from multiple locations for packaged apps
 const envPaths = [
+  // The desktop shell tells us where its config directory is
+  ...(process.env.FORGE_CONFIG_DIR ? [path.join(process.env.FORGE_CONFIG_DIR, ".env")] : []),
   path.join(process.cwd(), ".env"),
   path.join(process.execPath, "..", ".env"), // Same folder as executable
 ];
@@ -60,6 +64,8 @@ app.use(
   })
 );
 
+registerInternalRoutes(app);
+
 app.use(
   express.json({
     verify: (req, _res, buf) => {
@@ -81,6 +87,32 @@ export function log(message: string, source = "express") {
   console.log(`${formattedTime} [${source}] ${message}`);
 }
 
+let shuttingDown = false;
+
+async function gracefulShutdown(reason: string) {
+  if (shuttingDown) return;
+  shuttingDown = true;
+  log(`Shutting down (${reason})`);
+
+  stopScheduler();
+  httpServer.close();
+  try {
+    await closeDb();
+  } catch (error) {
+    console.error("Error closing database:", error);
+  }
+  process.exit(0);
+}
+
+process.on("SIGTERM", () => gracefulShutdown("SIGTERM"));
+process.on("SIGINT", () => gracefulShutdown("SIGINT"));
+
+// Used by the desktop shell, which cannot signal processes on Windows
+app.post("/api/internal/shutdown", requireShellToken, (_req, res) => {
+  res.status(202).json({ ok: true });
+  gracefulShutdown("shell request");
+});
+
 app.use((req, res, next) => {
   const start = Date.now();
   const path = req.path;
@@ -108,6 +140,7 @@ app.use((req, res, next) => {
 });
 
 (async () => {
+  await ensureEmbeddedSchema();
   await registerRoutes(httpServer, app);
 
   app.use((err: any, _req: Request, res: Response, _next: NextFunction) => {
@@ -126,15 +159,20 @@ app.use((req, res, next) => {
   }
 
   const port = parseInt(process.env.PORT || "5000", 10);
+  const host = process.env.HOST || "0.0.0.0";
   httpServer.listen(
     {
       port,
-      host: "0.0.0.0",
+      host,
       reusePort: true,
     },
     () => {
       log(`serving on port ${port}`);
-      startScheduler();
+      // Under the desktop shell the daily trigger lives in the shell, which
+      // keeps running across backend restarts and machine sleep
+      if (!process.env.FORGE_SHELL_TOKEN) {
+        startScheduler();
+      }
     },
   );
 })();
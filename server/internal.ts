@@ -0,0 +1,263 @@
+import express, { type Express, type Request, Response, NextFunction } from "express";
+import { is, sql, eq, getTableColumns } from "drizzle-orm";
+import { PgTable, getTableConfig } from "drizzle-orm/pg-core";
+import * as schema from "@shared/schema";
+import { db } from "./db";
+import { storage } from "./storage";
+import { generateScheduleForUser } from "./scheduler";
+import { checkBlockDrift } from "./drift";
+
+// Routes under /api/internal are only for the desktop shell, which hands
+// the sidecar a per-launch token. Without one they do not exist.
+export function requireShellToken(req: Request, res: Response, next: NextFunction) {
+  const token = process.env.FORGE_SHELL_TOKEN;
+  if (!token || req.get("x-forge-shell-token") !== token) {
+    return res.status(404).end();
+  }
+  next();
+}
+
+type Row = Record<string, unknown>;
+
+// References that are not declared as foreign keys in the schema but still
+// point at another table's id
+const LOOSE_REFERENCES: Record<string, Record<string, string>> = {
+  notifications: { missionId: "missions" },
+};
+
+interface TableInfo {
+  name: string;
+  table: PgTable;
+  // Property name -> referenced table name
+  references: Record<string, string>;
+}
+
+// Every table in shared/schema.ts, parents before children
+function schemaTables(): TableInfo[] {
+  const tables = Object.values(schema).filter((value): value is PgTable => is(value, PgTable));
+  const infos = new Map<string, TableInfo>();
+
+  for (const table of tables) {
+    const config = getTableConfig(table);
+    const propertyOf = new Map(
+      Object.entries(getTableColumns(table)).map(([key, column]) => [column.name, key]),
+    );
+    const references: Record<string, string> = { ...LOOSE_REFERENCES[config.name] };
+    for (const fk of config.foreignKeys) {
+      const reference = fk.reference();
+      const property = propertyOf.get(reference.columns[0].name);
+      if (property) references[property] = getTableConfig(reference.foreignTable).name;
+    }
+    infos.set(config.name, { name: config.name, table, references });
+  }
+
+  const ordered: TableInfo[] = [];
+  const visiting = new Set<string>();
+  const visit = (info: TableInfo) => {
+    if (ordered.includes(info) || visiting.has(info.name)) return;
+    visiting.add(info.name);
+    for (const parent of Object.values(info.references)) {
+      const parentInfo = infos.get(parent);
+      if (parentInfo && parent !== info.name) visit(parentInfo);
+    }
+    ordered.push(info);
+  };
+  infos.forEach(visit);
+  return ordered;
+}
+
+const INSERT_BATCH = 500;
+
+async function insertRows(tx: any, table: PgTable, rows: Row[], returning: boolean) {
+  const inserted: Row[] = [];
+  for (let i = 0; i < rows.length; i += INSERT_BATCH) {
+    const query = tx.insert(table).values(rows.slice(i, i + INSERT_BATCH)).onConflictDoNothing();
+    if (returning) {
+      inserted.push(...(await query.returning({ id: (table as any).id })));
+    } else {
+      await query;
+    }
+  }
+  return inserted;
+}
+
+// Replace: wipe every table and load the rows with their original ids
+async function replaceAll(tables: TableInfo[], data: Record<string, Row[]>) {
+  const imported: Record<string, number> = {};
+  await db.transaction(async (tx) => {
+    for (const info of [...tables].reverse()) {
+      await tx.delete(info.table);
+    }
+    for (const info of tables) {
+      const rows = data[info.name] ?? [];
+      await insertRows(tx, info.table, rows, false);
+      imported[info.name] = rows.length;
+      // Keep serial ids ahead of the imported ones
+      await tx.execute(sql.raw(
+        `SELECT setval(pg_get_serial_sequence('"${info.name}"', 'id'), COALESCE(MAX(id), 1), MAX(id) IS NOT NULL) FROM "${info.name}"`,
+      ));
+    }
+  });
+  // Sessions would otherwise log people in as whichever user now has their id
+  await db.execute(sql`DELETE FROM user_sessions`).catch(() => {});
+  return { imported, userIds: {} as Record<string, number> };
+}
+
+// Merge: add the rows alongside existing data under fresh ids, rewriting
+// references as we go. Users are matched by email rather than duplicated.
+async function mergeAll(tables: TableInfo[], data: Record<string, Row[]>) {
+  const imported: Record<string, number> = {};
+  const idMaps = new Map<string, Map<number, number>>();
+
+  await db.transaction(async (tx) => {
+    for (const info of tables) {
+      const ids = new Map<number, number>();
+      idMaps.set(info.name, ids);
+      let count = 0;
+
+      for (const row of data[info.name] ?? []) {
+        const { id, ...values } = row as Row & { id: number };
+        let orphaned = false;
+        for (const [property, parent] of Object.entries(info.references)) {
+          const old = values[property];
+          if (old === null || old === undefined) continue;
+          const mapped = idMaps.get(parent)?.get(old as number);
+          if (mapped === undefined) {
+            orphaned = true;
+            break;
+          }
+          values[property] = mapped;
+        }
+        if (orphaned) continue;
+
+        if (info.table === (schema.users as PgTable)) {
+          const [existing] = await tx
+            .select({ id: schema.users.id })
+            .from(schema.users)
+            .where(eq(schema.users.email, values.email as string));
+          if (existing) {
+            ids.set(id, existing.id);
+            continue;
+          }
+        }
+
+        const [inserted] = await insertRows(tx, info.table, [values], true);
+        if (inserted) {
+          ids.set(id, inserted.id as number);
+          count++;
+        }
+      }
+      imported[info.name] = count;
+    }
+  });
+
+  return { imported, userIds: Object.fromEntries(idMaps.get("users") ?? []) };
+}
+
+export function registerInternalRoutes(app: Express) {
+  // Every row of every table, keyed by table name, with property names as
+  // in shared/schema.ts
+  app.get("/api/internal/export", requireShellToken, async (_req, res) => {
+    try {
+      const tables: Record<string, Row[]> = {};
+      for (const info of schemaTables()) {
+        tables[info.name] = await db.select().from(info.table).orderBy((info.table as any).id);
+      }
+      res.json({ tables });
+    } catch (error: any) {
+      console.error("Export failed:", error);
+      res.status(500).json({ message: error.message || "Export failed" });
+    }
+  });
+
+  // Users with a daily schedule generation time, for the shell's trigger
+  app.get("/api/internal/schedule-generation", requireShellToken, async (_req, res) => {
+    try {
+      const users = await storage.getUsersWithScheduleGenerationTime();
+      res.json(users.map(({ userId, scheduleGenerationTime }) => ({
+        userId,
+        generationTime: scheduleGenerationTime,
+      })));
+    } catch (error: any) {
+      console.error("Failed to list schedule generation times:", error);
+      res.status(500).json({ message: error.message || "Failed to list users" });
+    }
+  });
+
+  // Generates one user's schedule for `date` (YYYY-MM-DD, the user's local
+  // day). Reports why nothing was generated rather than failing.
+  app.post(
+    "/api/internal/schedule-generation/:userId",
+    requireShellToken,
+    express.json(),
+    async (req, res) => {
+      const userId = parseInt(req.params.userId, 10);
+      const date = req.body?.date;
+      if (Number.isNaN(userId)) {
+        return res.status(400).json({ message: "Invalid user id" });
+      }
+      if (typeof date !== "string" || !/^\d{4}-\d{2}-\d{2}$/.test(date)) {
+        return res.status(400).json({ message: "date must be YYYY-MM-DD" });
+      }
+      res.json(await generateScheduleForUser(userId, date));
+    },
+  );
+
+  // Drift check for a block the shell's focus timer measured. Same input as
+  // /api/schedule-drift/check plus the user it belongs to.
+  app.post(
+    "/api/internal/schedule-drift/check",
+    requireShellToken,
+    express.json(),
+    async (req, res) => {
+      const { userId, scheduleDate, blockStartTime, blockTitle, plannedDuration, actualDuration } =
+        req.body ?? {};
+      if (!Number.isInteger(userId)) {
+        return res.status(400).json({ message: "userId is required" });
+      }
+      try {
+        res.json(await checkBlockDrift(userId, {
+          scheduleDate,
+          blockStartTime,
+          blockTitle,
+          plannedDuration,
+          actualDuration,
+        }));
+      } catch (error: any) {
+        console.error("Error checking drift:", error);
+        res.status(500).json({ message: error.message || "Failed to check drift" });
+      }
+    },
+  );
+
+  // Registered ahead of the app-wide JSON parser, whose default limit is far
+  // too small for a full export
+  app.post(
+    "/api/internal/import",
+    requireShellToken,
+    express.json({ limit: "1gb" }),
+    async (req, res) => {
+      const { mode, tables } = req.body ?? {};
+      if (mode !== "merge" && mode !== "replace") {
+        return res.status(400).json({ message: "mode must be merge or replace" });
+      }
+      if (!tables || typeof tables !== "object") {
+        return res.status(400).json({ message: "tables is required" });
+      }
+
+      const known = schemaTables();
+      const unknown = Object.keys(tables).filter((name) => !known.some((t) => t.name === name));
+      if (unknown.length > 0) {
+        return res.status(400).json({ message: `Unknown tables: ${unknown.join(", ")}` });
+      }
+
+      try {
+        const result = mode === "replace" ? await replaceAll(known, tables) : await mergeAll(known, tables);
+        res.json({ mode, ...result });
+      } catch (error: any) {
+        console.error("Import failed:", error);
+        res.status(500).json({ message: error.message || "Import failed" });
+      }
+    },
+  );
+}
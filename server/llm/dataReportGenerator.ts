@@ -98,7 +98,7 @@ function summarizeDailyFeedback(feedbacks: DailyFeedbackItem[]): {
 }
 
 async function scanKnowledgeBase(courseCodes: string[]): Promise<Record<string, string[]>> {
-  const kbPath = path.join(process.cwd(), "forge_kb");
+  const kbPath = process.env.FORGE_KB_PATH || path.join(process.cwd(), "forge_kb");
   const result: Record<string, string[]> = {};
   
   try {
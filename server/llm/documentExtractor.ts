@@ -7,6 +7,7 @@
 
 import fs from "fs";
 import path from "path";
+import { spawn } from "child_process";
 import mammoth from "mammoth";
 import * as XLSX from "xlsx";
 import AdmZip from "adm-zip";
@@ -24,11 +25,18 @@ async function getPdfParse() {
   return pdfParse;
 }
 
+export interface PageText {
+  page: number;
+  text: string;
+}
+
 export interface ExtractionResult {
   text: string;
   imageTexts: string[];
   method: "local" | "gemini" | "groq" | "openai";
   pageCount?: number;
+  // `text` split by page, when the extractor knows the page boundaries
+  pages?: PageText[];
 }
 
 /**
@@ -177,9 +185,22 @@ async function extractPptx(filePath: string): Promise<ExtractionResult> {
 
 /**
  * Extracts text from PDF files
- * Uses pdf-parse for regular PDFs, OpenAI GPT-4o mini for scanned/image PDFs
+ * Uses the desktop shell's native extractor when there is one, pdf-parse otherwise,
+ * and OpenAI GPT-4o mini for scanned/image PDFs
  */
 async function extractPdf(filePath: string): Promise<ExtractionResult> {
+  const nativeExtractor = process.env.FORGE_EXTRACTOR;
+  if (nativeExtractor) {
+    try {
+      const result = await extractPdfNative(nativeExtractor, filePath);
+      if (result.text.trim().length >= 100) {
+        return result;
+      }
+    } catch (err) {
+      console.warn(`Native PDF extraction failed, falling back to pdf-parse: ${err}`);
+    }
+  }
+
   try {
     const parse = await getPdfParse();
     const pdfBuffer = fs.readFileSync(filePath);
@@ -219,6 +240,52 @@ async function extractPdf(filePath: string): Promise<ExtractionResult> {
   }
 }
 
+/**
+ * Extracts text from a PDF with the desktop shell's native extractor, which
+ * keeps page boundaries. Same line protocol as the course builder route.
+ */
+function extractPdfNative(extractor: string, filePath: string): Promise<ExtractionResult> {
+  return new Promise((resolve, reject) => {
+    const child = spawn(extractor, ["--extract-text"]);
+    let output = "";
+    child.stdout.on("data", (data) => {
+      output += data.toString();
+    });
+    child.stderr.on("data", (data) => {
+      console.warn(`[DocumentExtractor] Native extractor: ${data.toString().trim()}`);
+    });
+    child.on("error", reject);
+    child.on("close", (code) => {
+      let update: any;
+      try {
+        update = output
+          .split("\n")
+          .filter((line) => line.trim())
+          .map((line) => JSON.parse(line))
+          .find((line) => line.type === "progress");
+      } catch (err) {
+        reject(err);
+        return;
+      }
+      if (!update) {
+        reject(new Error(`Native extractor exited with code ${code}`));
+      } else if (update.status !== "success") {
+        reject(new Error(update.error));
+      } else {
+        resolve({
+          text: update.text,
+          imageTexts: [],
+          method: "local",
+          pageCount: update.metadata?.pageCount ?? undefined,
+          pages: update.pages,
+        });
+      }
+    });
+    child.stdin.write(JSON.stringify({ files: [{ path: filePath, name: path.basename(filePath) }] }));
+    child.stdin.end();
+  });
+}
+
 /**
  * Extracts text from image files using Gemini vision
  */
@@ -13,7 +13,7 @@
 import { db } from "../db";
 import { uploadedFiles, courseContexts } from "@shared/schema";
 import { eq, and } from "drizzle-orm";
-import { extractDocumentFromBuffer } from "./documentExtractor";
+import { extractDocumentFromBuffer, PageText } from "./documentExtractor";
 import { RecursiveCharacterTextSplitter } from "@langchain/textsplitters";
 import { addChunksToCollection, ChunkMetadata } from "./retriever";
 import { extractConcepts, isGeminiConfigured } from "./gemini";
@@ -60,6 +60,49 @@ const textSplitter = new RecursiveCharacterTextSplitter({
   separators: ["\n\n", "\n", ". ", " ", ""],
 });
 
+// The desktop shell chunks natively, breaking between sentences and at
+// headings (src-tauri/src/chunker.rs). Plain server installs, or a shell that
+// cannot be reached, fall back to the LangChain splitter.
+const CHUNKER_URL = process.env.FORGE_VECTOR_URL;
+
+interface NativeChunk {
+  text: string;
+  metadata: ChunkMetadata;
+}
+
+// Pages, when given, hold the same text split by page so chunks can record
+// where they came from
+async function chunkText(
+  file: string,
+  text: string,
+  pages?: PageText[],
+): Promise<{ chunks: string[]; metadata: ChunkMetadata[] }> {
+  if (CHUNKER_URL) {
+    const source = pages?.length ? { pages } : { text };
+    try {
+      const res = await fetch(`${CHUNKER_URL}/chunks`, {
+        method: "POST",
+        headers: {
+          "Content-Type": "application/json",
+          "x-forge-shell-token": process.env.FORGE_SHELL_TOKEN ?? "",
+        },
+        body: JSON.stringify({ file, ...source, options: { chunkSize: CHUNK_SIZE, chunkOverlap: CHUNK_OVERLAP } }),
+      });
+      if (!res.ok) {
+        throw new Error(`${res.status}: ${await res.text()}`);
+      }
+      const native = (await res.json()) as NativeChunk[];
+      return { chunks: native.map((c) => c.text), metadata: native.map((c) => c.metadata) };
+    } catch (error) {
+      console.warn(`[Pipeline] Native chunker unavailable, using the text splitter:`, error);
+    }
+  }
+
+  const docs = await textSplitter.createDocuments([text]);
+  const chunks = docs.map((doc) => doc.pageContent);
+  return { chunks, metadata: chunks.map((_, i) => ({ file, chunkIndex: i })) };
+}
+
 async function updateFileStage(
   fileId: number, 
   stage: PipelineStage, 
@@ -223,9 +266,14 @@ export async function runPipeline(
     await updateFileStage(fileId, "chunking", 0);
     emitProgress("chunking", 0);
 
-    const docs = await textSplitter.createDocuments([cleanedText]);
-    const chunks = docs.map((doc) => doc.pageContent);
-    
+    // Image texts have no page, so pages only stand in for the text without them
+    const pages = extractResult.imageTexts.length === 0
+      ? extractResult.pages
+          ?.map((page) => ({ page: page.page, text: cleanText(page.text) }))
+          .filter((page) => page.text)
+      : undefined;
+    const { chunks, metadata: chunkMetadata } = await chunkText(file.originalName, cleanedText, pages);
+
     await updateFileStage(fileId, "chunking", 100, { extractedChunks: chunks.length });
     emitProgress("chunking", 100);
     console.log(`[Pipeline] Created ${chunks.length} chunks`);
@@ -235,11 +283,6 @@ export async function runPipeline(
     await updateFileStage(fileId, "embedding", 0);
     emitProgress("embedding", 0);
 
-    const chunkMetadata: ChunkMetadata[] = chunks.map((_, i) => ({
-      file: file.originalName,
-      chunkIndex: i,
-    }));
-
     const embeddedCount = await addChunksToCollection(
       userId,
       file.courseCode,
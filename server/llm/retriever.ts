@@ -12,6 +12,11 @@ import { eq, and, sql } from "drizzle-orm";
 
 let embeddingPipeline: FeatureExtractionPipeline | null = null;
 
+// The desktop shell serves a native vector index. Without it (plain server
+// installs) similarity search scans the embeddings stored in PostgreSQL.
+const VECTOR_URL = process.env.FORGE_VECTOR_URL;
+const INDEX_BATCH = 500;
+
 /**
  * Chunk metadata stored alongside embeddings in PostgreSQL
  */
@@ -30,6 +35,112 @@ export interface RetrievedChunk {
   distance: number;
 }
 
+interface IndexItem {
+  id: string;
+  vector: number[];
+  text: string;
+  metadata: { fileId: number; chunkIndex: number; page?: number };
+}
+
+interface IndexQueryResult {
+  matches: { id: string; text: string; metadata: IndexItem["metadata"]; score: number }[];
+  total: number;
+}
+
+/**
+ * Calls the shell's vector index
+ * @param op - Index operation
+ * @param body - Request body, as documented in src-tauri/src/vectors.rs
+ * @returns Promise resolving to the parsed response
+ */
+async function vectorIndex<T>(op: "insert" | "query" | "delete", body: unknown): Promise<T> {
+  const res = await fetch(`${VECTOR_URL}/vectors/${op}`, {
+    method: "POST",
+    headers: {
+      "Content-Type": "application/json",
+      "x-forge-shell-token": process.env.FORGE_SHELL_TOKEN ?? "",
+    },
+    body: JSON.stringify(body),
+  });
+  if (!res.ok) {
+    throw new Error(`Vector index ${op} failed (${res.status}): ${await res.text()}`);
+  }
+  return res.json() as Promise<T>;
+}
+
+/**
+ * Adds stored chunk rows to the vector index, keyed by row id
+ * @param userId - User ID for isolation
+ * @param courseCode - Sanitized course code
+ * @param rows - Chunk rows with their embeddings
+ */
+async function indexChunks(
+  userId: number,
+  courseCode: string,
+  rows: { id: number; fileId: number; chunkIndex: number; text: string; page: number | null; vector: number[] }[]
+): Promise<void> {
+  for (let i = 0; i < rows.length; i += INDEX_BATCH) {
+    const items: IndexItem[] = rows.slice(i, i + INDEX_BATCH).map((row) => ({
+      id: String(row.id),
+      vector: row.vector,
+      text: row.text,
+      metadata: { fileId: row.fileId, chunkIndex: row.chunkIndex, page: row.page ?? undefined },
+    }));
+    await vectorIndex("insert", { userId, course: courseCode, items });
+  }
+}
+
+/**
+ * Rebuilds a course's partition of the vector index from PostgreSQL, for
+ * chunks stored before the index existed or while it was unreachable
+ * @param userId - User ID for isolation
+ * @param courseCode - Sanitized course code
+ */
+async function syncIndex(userId: number, courseCode: string): Promise<void> {
+  const rows = await db.select().from(documentChunks)
+    .where(and(
+      eq(documentChunks.userId, userId),
+      eq(documentChunks.courseCode, courseCode)
+    ));
+  await vectorIndex("delete", { userId, course: courseCode });
+  await indexChunks(userId, courseCode, rows.map((row) => ({
+    ...row,
+    vector: JSON.parse(row.embedding) as number[],
+  })));
+  console.log(`[Retriever] Rebuilt vector index for ${courseCode} (user: ${userId}): ${rows.length} chunks`);
+}
+
+/**
+ * Top-k search through the shell's vector index
+ * @param userId - User ID for isolation
+ * @param courseCode - Sanitized course code
+ * @param queryVector - Query embedding
+ * @param topK - Number of results to return
+ * @returns Promise resolving to retrieved chunks
+ */
+async function retrieveFromIndex(
+  userId: number,
+  courseCode: string,
+  queryVector: number[],
+  topK: number
+): Promise<RetrievedChunk[]> {
+  const query = { userId, course: courseCode, vector: queryVector, topK };
+  let result = await vectorIndex<IndexQueryResult>("query", query);
+  if (result.total !== await getChunkCount(userId, courseCode)) {
+    await syncIndex(userId, courseCode);
+    result = await vectorIndex<IndexQueryResult>("query", query);
+  }
+  return result.matches.map((match) => ({
+    text: match.text,
+    metadata: {
+      file: `file_${match.metadata.fileId}`,
+      chunkIndex: match.metadata.chunkIndex,
+      page: match.metadata.page ?? undefined,
+    },
+    distance: 1 - match.score,
+  }));
+}
+
 /**
  * Sanitizes course code to prevent directory traversal attacks
  * @param courseCode - Raw course code from user input
@@ -143,8 +254,21 @@ export async function addChunksToCollection(
     }));
     
     // Batch insert
+    const inserted: { id: number }[] = [];
     for (const record of chunkRecords) {
-      await db.insert(documentChunks).values(record);
+      const [row] = await db.insert(documentChunks).values(record).returning({ id: documentChunks.id });
+      inserted.push(row);
+    }
+
+    if (VECTOR_URL) {
+      // PostgreSQL stays the source of truth; a missed insert is repaired on
+      // the next search of this course
+      await indexChunks(userId, chunkRecords[0].courseCode, chunkRecords.map((record, i) => ({
+        ...record,
+        id: inserted[i].id,
+        page: record.page ?? null,
+        vector: embeddings[i],
+      }))).catch((error) => console.error("[Retriever] Error indexing chunks:", error));
     }
     
     console.log(`[Retriever] Added ${chunks.length} chunks to PostgreSQL for ${courseCode} (user: ${userId})`);
@@ -175,6 +299,14 @@ export async function retrieveChunks(
     // Generate query embedding
     const queryEmbedding = await generateEmbeddings([query]);
     const queryVector = queryEmbedding[0];
+
+    if (VECTOR_URL) {
+      try {
+        return await retrieveFromIndex(userId, sanitized, queryVector, topK);
+      } catch (error) {
+        console.error("[Retriever] Vector index unavailable, searching PostgreSQL:", error);
+      }
+    }
     
     // Fetch all chunks for this user and course
     const allChunks = await db.select().from(documentChunks)
@@ -250,9 +382,30 @@ export async function getChunkCount(userId: number, courseCode: string): Promise
  */
 export async function deleteChunksForFile(fileId: number): Promise<void> {
   try {
-    await db.delete(documentChunks).where(eq(documentChunks.fileId, fileId));
+    const deleted = await db.delete(documentChunks)
+      .where(eq(documentChunks.fileId, fileId))
+      .returning({ userId: documentChunks.userId });
+    if (VECTOR_URL) {
+      // The index only deletes within one user's partitions
+      for (const userId of new Set(deleted.map((row) => row.userId))) {
+        await vectorIndex("delete", { userId, filter: { fileId } });
+      }
+    }
     console.log(`[Retriever] Deleted chunks for file ${fileId}`);
   } catch (error) {
     console.error("[Retriever] Error deleting chunks:", error);
   }
 }
+
+/**
+ * Deletes all of a user's chunks from PostgreSQL and the vector index
+ * @param userId - User ID to remove chunks for
+ */
+export async function deleteChunksForUser(userId: number): Promise<void> {
+  await db.delete(documentChunks).where(eq(documentChunks.userId, userId));
+  if (VECTOR_URL) {
+    await vectorIndex("delete", { userId }).catch((error) =>
+      console.error("[Retriever] Error removing user from vector index:", error)
+    );
+  }
+}
@@ -2,7 +2,7 @@ import type { Express, Request, Response, NextFunction } from "express";
 import { createServer, type Server } from "http";
 import { storage } from "./storage";
 import { db } from "./db";
-import { insertMissionSchema, insertProofSchema, insertAcademicCommitmentSchema, insertScheduleBlockFeedbackSchema, insertBookSchema, insertCourseSchema, insertMissionFeedbackSchema, missionFeedback, missions, deadlines, scheduleBlockFeedback, dailySchedules, draftSchedules, academicCommitments, courses, books, userPreferences, notifications, users, proofs, userPatterns, settings, conceptTracking, scheduleDriftEvents, dailyFeedback, activityLibrary, insertUploadedFileSchema, uploadedFiles, insertLearnerProfileSchema, insertKnowledgeChatSchema, courseRoadmaps, roadmapChatHistory, planningChatSessions, planningChatMessages, learnerProfiles, knowledgeChatHistory, documentChunks, readingLogs, planningPreferences } from "@shared/schema";
+import { insertMissionSchema, insertProofSchema, insertAcademicCommitmentSchema, insertScheduleBlockFeedbackSchema, insertBookSchema, insertCourseSchema, insertMissionFeedbackSchema, missionFeedback, missions, deadlines, scheduleBlockFeedback, dailySchedules, draftSchedules, academicCommitments, courses, books, userPreferences, notifications, users, proofs, userPatterns, settings, conceptTracking, scheduleDriftEvents, dailyFeedback, activityLibrary, insertUploadedFileSchema, uploadedFiles, insertLearnerProfileSchema, insertKnowledgeChatSchema, courseRoadmaps, roadmapChatHistory, planningChatSessions, planningChatMessages, learnerProfiles, knowledgeChatHistory, readingLogs, planningPreferences } from "@shared/schema";
 import { eq, and, desc } from "drizzle-orm";
 import { z } from "zod";
 import multer from "multer";
@@ -21,9 +21,10 @@ import { analyzeActivityDetails } from "./llm/detailsAnalyzer";
 import { ingestNotes } from "./llm/missionIntelligence";
 import { ingestFiles, ingestSingleFile, getIngestJobStatus, validateFiles } from "./llm/ingestRag";
 import { generateSingleRagMission } from "./llm/missionRag";
-import { getChunkCount, sanitizeCourseCode } from "./llm/retriever";
+import { deleteChunksForUser, getChunkCount, sanitizeCourseCode } from "./llm/retriever";
 import { updateMasteryFromFeedback, startMasteryDecayScheduler } from "./llm/mastery";
 import { exportMetrics } from "./metrics/counters";
+import { checkBlockDrift } from "./drift";
 import type { UserPreferences } from "@shared/schema";
 import { createUser, getUserByEmail, getUserById, authenticateUser } from "./auth";
 
@@ -54,6 +55,9 @@ function requireAuth(req: Request, res: Response, next: NextFunction) {
 import { loadContextForCourse as loadContextFromDB } from "./llm/ingestPipeline";
 
 const FORGE_KB_PATH = process.env.FORGE_KB_PATH || "./forge_kb";
+// Content-addressed proof files written by the desktop shell
+const FORGE_PROOFS_DIR = process.env.FORGE_PROOFS_DIR;
+const PROOF_STORAGE_KEY = /^sha256:([0-9a-f]{64})$/;
 
 // Helper function to get time-of-day label
 function getTimeOfDayLabel(timeStr: string): string {
@@ -206,6 +210,13 @@ async function loadContextForCourse(courseCode: string, userId?: number): Promis
   return await loadContextFromDB(courseCode, userId);
 }
 
+// Where the desktop shell's proof store keeps a file, if the proof is there
+function proofStorePath(storageKey: string | null): string | null {
+  const match = storageKey ? PROOF_STORAGE_KEY.exec(storageKey) : null;
+  if (!match || !FORGE_PROOFS_DIR) return null;
+  return path.join(FORGE_PROOFS_DIR, match[1].slice(0, 2), match[1]);
+}
+
 function getMimeType(filePath: string): string {
   const ext = path.extname(filePath).toLowerCase();
   const mimeTypes: Record<string, string> = {
@@ -336,7 +347,7 @@ export async function registerRoutes(
       // Delete learner profiles and chat history before deleting courses (foreign key constraint)
       await db.delete(learnerProfiles).where(eq(learnerProfiles.userId, userId));
       await db.delete(knowledgeChatHistory).where(eq(knowledgeChatHistory.userId, userId));
-      await db.delete(documentChunks).where(eq(documentChunks.userId, userId));
+      await deleteChunksForUser(userId);
       await db.delete(planningChatSessions).where(eq(planningChatSessions.userId, userId));
       await db.delete(courseRoadmaps).where(eq(courseRoadmaps.userId, userId));
       await db.delete(courses).where(eq(courses.userId, userId));
@@ -594,7 +605,9 @@ export async function registerRoutes(
   }, async (req, res) => {
     try {
       const missionId = parseInt(req.params.id);
-      if (isNaN(missionId) || !req.file) {
+      // The desktop app saves the file itself and only sends its handle
+      const storageKey = typeof req.body?.storageKey === "string" ? req.body.storageKey : null;
+      if (isNaN(missionId) || (!req.file && !storageKey)) {
         res.status(400).json({ error: "Invalid request" });
         return;
       }
@@ -605,6 +618,24 @@ export async function registerRoutes(
         return;
       }
 
+      if (!req.file) {
+        if (!PROOF_STORAGE_KEY.test(storageKey!)) {
+          res.status(400).json({ error: "Invalid storage key" });
+          return;
+        }
+        const originalName = String(req.body.fileName || "");
+        const timestamp = new Date().toISOString().split("T")[0];
+        const proof = await storage.createProof(insertProofSchema.parse({
+          missionId,
+          fileName: `mission_${missionId}_${timestamp}${path.extname(originalName)}`,
+          fileSize: parseInt(req.body.fileSize) || null,
+          storageKey,
+        }));
+        await storage.updateMissionStatus(missionId, "proof_uploaded");
+        res.json({ proof, message: "Proof uploaded successfully" });
+        return;
+      }
+
       // Generate unique filename for database storage
       const timestamp = new Date().toISOString().split("T")[0];
       const ext = path.extname(req.file.originalname);
@@ -689,13 +720,14 @@ export async function registerRoutes(
             if (proofs && proofs.length > 0) {
               const proof = proofs[0];
               const normalizedCourseCode = normalizeCourseCode(course.code);
-              const filePath = path.join(FORGE_KB_PATH, normalizedCourseCode, proof.fileName);
+              const storedPath = proofStorePath(proof.storageKey);
+              const filePath = storedPath ?? path.join(FORGE_KB_PATH, normalizedCourseCode, proof.fileName);
 
               console.log(`[AI Validation] Looking for proof at: ${filePath}`);
 
               if (fs.existsSync(filePath)) {
                 try {
-                  const proofContent = await analyzeDocumentText(filePath, getMimeType(filePath));
+                  const proofContent = await analyzeDocumentText(filePath, getMimeType(proof.fileName));
                   console.log(`[AI Validation] Extracted proof content length: ${proofContent.length}`);
 
                   // Use new comprehensive validation function
@@ -907,7 +939,7 @@ export async function registerRoutes(
       }
 
       const proof = proofs[0];
-      const filePath = path.join(
+      const filePath = proofStorePath(proof.storageKey) ?? path.join(
         FORGE_KB_PATH,
         proof.fileName.split("_")[0],
         proof.fileName
@@ -918,7 +950,7 @@ export async function registerRoutes(
         return;
       }
 
-      const mimeType = getMimeType(filePath);
+      const mimeType = getMimeType(proof.fileName);
       res.setHeader("Content-Type", mimeType);
       res.sendFile(filePath);
     } catch (error) {
@@ -2046,51 +2078,13 @@ export async function registerRoutes(
   app.post("/api/schedule-drift/check", requireAuth, async (req, res) => {
     try {
       const { scheduleDate, blockStartTime, blockTitle, plannedDuration, actualDuration } = req.body;
-
-      // Calculate drift
-      const driftMinutes = actualDuration - plannedDuration;
-
-      // Only create drift event if significant (> 10 minutes overrun)
-      if (driftMinutes <= 10) {
-        res.json({ drift: false, driftMinutes });
-        return;
-      }
-
-      // Get the schedule for this date to count remaining blocks
-      const schedule = await storage.getScheduleForDate(scheduleDate, req.session.userId!);
-      let affectedBlocksCount = 0;
-
-      if (schedule) {
-        const blocks = JSON.parse(schedule.scheduleData);
-        // Count blocks that start after this block
-        affectedBlocksCount = blocks.filter((b: any) => b.startTime > blockStartTime).length;
-      }
-
-      // Get existing drift events to calculate cumulative drift
-      const existingDriftEvents = await storage.getDriftEventsForDate(scheduleDate, req.session.userId!);
-      const previousDrift = existingDriftEvents.reduce((sum, e) => sum + e.driftMinutes, 0);
-      const cumulativeDrift = previousDrift + driftMinutes;
-
-      // Create drift event
-      const { insertScheduleDriftEventSchema } = await import("@shared/schema");
-      const eventData = insertScheduleDriftEventSchema.parse({
+      res.json(await checkBlockDrift(req.session.userId!, {
         scheduleDate,
         blockStartTime,
         blockTitle,
         plannedDuration,
         actualDuration,
-        driftMinutes,
-        cumulativeDrift,
-        affectedBlocksCount,
-      });
-
-      const event = await storage.createDriftEvent(eventData);
-
-      res.json({
-        drift: true,
-        event,
-        requiresReschedule: cumulativeDrift >= 15 && affectedBlocksCount > 0,
-      });
+      }));
     } catch (error) {
       console.error("Error checking drift:", error);
       res.status(500).json({ error: "Failed to check drift" });
@@ -3559,17 +3553,21 @@ export async function registerRoutes(
       }
 
       const { spawn } = await import("child_process");
-      const pythonScriptPath = path.join(process.cwd(), "server/services/pdfExtractor.py");
-      const pythonProcess = spawn("python3", [pythonScriptPath]);
+      // The desktop shell ships a native extractor speaking the same protocol
+      // as the Python script; plain server installs still use Python
+      const nativeExtractor = process.env.FORGE_EXTRACTOR;
+      const extractor = nativeExtractor
+        ? spawn(nativeExtractor, ["--extract-text"])
+        : spawn("python3", [path.join(process.cwd(), "server/services/pdfExtractor.py")]);
 
       const fileList = files.map(f => ({ path: f.path, name: f.originalname }));
-      pythonProcess.stdin.write(JSON.stringify({ files: fileList }));
-      pythonProcess.stdin.end();
+      extractor.stdin.write(JSON.stringify({ files: fileList }));
+      extractor.stdin.end();
 
       let allContent = "";
       let buffer = "";
 
-      pythonProcess.stdout.on("data", (data: any) => {
+      extractor.stdout.on("data", (data: any) => {
         buffer += data.toString();
         const lines = buffer.split("\n");
         buffer = lines.pop() || "";
@@ -3589,17 +3587,21 @@ export async function registerRoutes(
         }
       });
 
-      pythonProcess.stderr.on("data", (data) => {
-        console.error("Python error:", data.toString());
-        res.write(`data: ${JSON.stringify({ type: "error", error: data.toString() })}\n\n`);
+      extractor.stderr.on("data", (data) => {
+        console.error("Extractor error:", data.toString());
+        // The native extractor reports per-file failures on stdout; its
+        // stderr is diagnostics only
+        if (!nativeExtractor) {
+          res.write(`data: ${JSON.stringify({ type: "error", error: data.toString() })}\n\n`);
+        }
       });
 
-      pythonProcess.on("close", (code) => {
+      extractor.on("close", (code) => {
         if (code === 0) {
           console.log(`[Course Builder] Extraction complete, content length: ${allContent.length}`);
           res.write(`data: ${JSON.stringify({ type: "complete", extractedContent: allContent })}\n\n`);
         } else {
-          console.error(`[Course Builder] Python process exited with code ${code}`);
+          console.error(`[Course Builder] Extractor exited with code ${code}`);
           res.write(`data: ${JSON.stringify({ type: "error", error: `Extraction failed with code ${code}` })}\n\n`);
         }
         res.end();
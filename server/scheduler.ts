@@ -48,10 +48,13 @@ function resetProcessedUsersIfNewDay(): void {
   }
 }
 
-async function generateScheduleForUser(userId: number): Promise<{ success: boolean; reason: string }> {
+// Also called by the desktop shell, which owns the daily trigger when it
+// runs the backend and passes the user's local date
+export async function generateScheduleForUser(
+  userId: number,
+  today: string = getTodayDateString(),
+): Promise<{ success: boolean; reason: string }> {
   try {
-    const today = getTodayDateString();
-
     const existing = await storage.getFinalizedSchedule(today, userId);
     if (existing) {
       return { success: false, reason: "schedule_exists" };
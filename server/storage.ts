@@ -63,6 +63,7 @@ export interface IStorage {
   updateTimetableEntry(id: number, entry: Partial<InsertAcademicCommitment>): Promise<AcademicCommitment | undefined>;
   deleteTimetableEntry(id: number): Promise<void>;
   createDriftEvent(event: InsertScheduleDriftEvent): Promise<ScheduleDriftEvent>;
+  updateDriftEvent(id: number, event: Partial<InsertScheduleDriftEvent>): Promise<ScheduleDriftEvent | undefined>;
   getUnresolvedDriftEvents(date: string, userId: number): Promise<ScheduleDriftEvent[]>;
   getDriftEventsForDate(date: string, userId: number): Promise<ScheduleDriftEvent[]>;
   storeAISuggestion(id: number, suggestion: string): Promise<ScheduleDriftEvent | undefined>;
@@ -710,6 +711,14 @@ export class DrizzleStorage implements IStorage {
     return result[0];
   }
 
+  async updateDriftEvent(id: number, event: Partial<InsertScheduleDriftEvent>): Promise<ScheduleDriftEvent | undefined> {
+    const result = await db.update(scheduleDriftEvents)
+      .set(event)
+      .where(eq(scheduleDriftEvents.id, id))
+      .returning();
+    return result[0];
+  }
+
   async getUnresolvedDriftEvents(date: string, userId: number): Promise<ScheduleDriftEvent[]> {
     return await db.select().from(scheduleDriftEvents)
       .where(and(eq(scheduleDriftEvents.scheduleDate, date), eq(scheduleDriftEvents.resolved, false), eq(scheduleDriftEvents.userId, userId)))
@@ -167,6 +167,7 @@ export const proofs = pgTable("proofs", {
   fileName: text("file_name").notNull(),
   fileSize: integer("file_size"),
   fileData: text("file_data"), // Base64 encoded file content for database storage
+  storageKey: text("storage_key"), // "sha256:<hex>" handle into the desktop app's proof store
   uploadedAt: text("uploaded_at").default(sql`CURRENT_TIMESTAMP`),
 }, (table) => ({
   missionIdIdx: index("proofs_mission_id_idx").on(table.missionId),
@@ -420,6 +421,7 @@ export const insertProofSchema = createInsertSchema(proofs).pick({
   missionId: true,
   fileName: true,
   fileSize: true,
+  storageKey: true,
 });
 
 export const insertNotificationSchema = createInsertSchema(notifications).pick({
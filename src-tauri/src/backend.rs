@@ -0,0 +1,614 @@
+use std::fs;
+use std::net::{Ipv4Addr, TcpListener};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_shell::process::{CommandChild, CommandEvent};
+use tauri_plugin_shell::ShellExt;
+use tokio::sync::watch;
+
+use crate::config::BackendConfig;
+use crate::local_db::{self, DatabaseMode};
+use crate::logging::BACKEND_TARGET;
+
+// Crash-loop protection: after this many consecutive crashes we give up and
+// leave the backend in `Crashed` until the user starts it again.
+const MAX_RESTARTS: u32 = 5;
+const BASE_BACKOFF: Duration = Duration::from_millis(500);
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+// A sidecar that stayed up this long is considered healthy again, so its
+// next crash starts the backoff from scratch.
+const STABLE_UPTIME: Duration = Duration::from_secs(60);
+
+// Cheap unauthenticated route that only answers once routes are registered
+const HEALTH_PATH: &str = "/api/llm/status";
+const PROBE_INTERVAL: Duration = Duration::from_millis(250);
+const PROBE_TIMEOUT: Duration = Duration::from_secs(2);
+
+// How long the sidecar gets to flush writes and close the database before
+// it is killed
+pub const SHUTDOWN_GRACE: Duration = Duration::from_secs(10);
+const SHUTDOWN_PATH: &str = "/api/internal/shutdown";
+pub const SHELL_TOKEN_HEADER: &str = "x-forge-shell-token";
+
+const PID_FILE_NAME: &str = "backend.pid";
+const SIDECAR_PROCESS_PREFIX: &str = "forge-backend";
+
+// Mirrors the running child's PID outside any lock so the panic hook can
+// still find it when the rest of the state is unusable
+static RUNNING_PID: AtomicU32 = AtomicU32::new(0);
+
+pub const EVENT_READY: &str = "backend://ready";
+pub const EVENT_EXITED: &str = "backend://exited";
+pub const EVENT_LOG: &str = "backend://log";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BackendPhase {
+  Stopped,
+  Starting,
+  Ready,
+  Crashed,
+  Restarting,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackendStatus {
+  pub state: BackendPhase,
+  pub pid: Option<u32>,
+  pub uptime_secs: Option<u64>,
+  pub port: u16,
+  pub url: String,
+  pub restart_count: u32,
+  pub last_exit_code: Option<i32>,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ReadyPayload {
+  pid: Option<u32>,
+  port: u16,
+  url: String,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ExitedPayload {
+  code: Option<i32>,
+  signal: Option<i32>,
+  // False when the exit was requested through `stop_backend`
+  unexpected: bool,
+  will_restart: bool,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum LogStream {
+  Stdout,
+  Stderr,
+}
+
+#[derive(Clone, Serialize)]
+struct LogPayload {
+  stream: LogStream,
+  line: String,
+}
+
+struct Supervisor {
+  phase: BackendPhase,
+  // Bumped on every spawn and stop so events from an old child are ignored
+  generation: u64,
+  crash_count: u32,
+  started_at: Option<Instant>,
+  // Automatic restarts over the whole session, unlike `crash_count`
+  restart_count: u32,
+  last_exit_code: Option<i32>,
+}
+
+pub struct BackendState {
+  child: Mutex<Option<CommandChild>>,
+  supervisor: Mutex<Supervisor>,
+  // Fixed for the lifetime of the shell so the webview's base URL never goes stale
+  port: u16,
+  // Shared secret for the `/api/internal` routes so no other local process
+  // or web page can stop the backend or read out its data
+  shell_token: String,
+  // Bumped on every child exit so a graceful shutdown can wait for it
+  exits: watch::Sender<u64>,
+  shutdown_lock: tokio::sync::Mutex<()>,
+}
+
+impl BackendState {
+  pub fn new(port: u16) -> Self {
+    Self {
+      port,
+      shell_token: uuid::Uuid::new_v4().to_string(),
+      exits: watch::Sender::new(0),
+      shutdown_lock: tokio::sync::Mutex::new(()),
+      child: Mutex::new(None),
+      supervisor: Mutex::new(Supervisor {
+        phase: BackendPhase::Stopped,
+        generation: 0,
+        crash_count: 0,
+        started_at: None,
+        restart_count: 0,
+        last_exit_code: None,
+      }),
+    }
+  }
+
+  pub fn phase(&self) -> BackendPhase {
+    self.supervisor.lock().unwrap().phase
+  }
+
+  pub fn url(&self) -> String {
+    format!("http://127.0.0.1:{}", self.port)
+  }
+
+  pub fn shell_token(&self) -> &str {
+    &self.shell_token
+  }
+
+  pub fn pid(&self) -> Option<u32> {
+    self.child.lock().unwrap().as_ref().map(|child| child.pid())
+  }
+
+  pub fn status(&self) -> BackendStatus {
+    let pid = self.pid();
+    let supervisor = self.supervisor.lock().unwrap();
+    BackendStatus {
+      state: supervisor.phase,
+      pid,
+      uptime_secs: supervisor.started_at.map(|t| t.elapsed().as_secs()),
+      port: self.port,
+      url: self.url(),
+      restart_count: supervisor.restart_count,
+      last_exit_code: supervisor.last_exit_code,
+    }
+  }
+}
+
+/// Asks the OS for a free loopback port. The listener is dropped right away so
+/// the sidecar can bind it; the window in which another process could grab
+/// the port is tiny compared to the old hardcoded 5000.
+pub fn pick_free_port() -> std::io::Result<u16> {
+  let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))?;
+  Ok(listener.local_addr()?.port())
+}
+
+fn backoff_for(attempt: u32) -> Duration {
+  let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+  BASE_BACKOFF.saturating_mul(factor).min(MAX_BACKOFF)
+}
+
+fn spawn_sidecar(app: &AppHandle, generation: u64) -> Result<(), String> {
+  let state = app.state::<BackendState>();
+
+  let config = BackendConfig::resolve(app, state.port, &state.shell_token)?;
+
+  // The working directory only matters for code that still resolves paths
+  // relative to it; everything else comes from the environment
+  let sidecar = app
+    .shell()
+    .sidecar("forge-backend")
+    .map_err(|e| format!("Failed to create sidecar command: {}", e))?
+    .current_dir(&config.data_dir)
+    .envs(config.envs());
+
+  let (mut rx, child) = sidecar
+    .spawn()
+    .map_err(|e| format!("Failed to spawn sidecar: {}", e))?;
+
+  let pid = child.pid();
+  log::info!("Backend spawned (pid {})", pid);
+  *state.child.lock().unwrap() = Some(child);
+  track_pid(app, pid);
+  state.supervisor.lock().unwrap().started_at = Some(Instant::now());
+
+  let probe_app = app.clone();
+  tauri::async_runtime::spawn(async move {
+    probe_until_ready(&probe_app, generation).await;
+  });
+
+  let app = app.clone();
+  tauri::async_runtime::spawn(async move {
+    while let Some(event) = rx.recv().await {
+      match event {
+        CommandEvent::Stdout(line) => {
+          let line = String::from_utf8_lossy(&line).trim_end().to_string();
+          log::info!(target: BACKEND_TARGET, "{}", line);
+          let _ = app.emit(EVENT_LOG, LogPayload { stream: LogStream::Stdout, line });
+        }
+        CommandEvent::Stderr(line) => {
+          let line = String::from_utf8_lossy(&line).trim_end().to_string();
+          // Node writes warnings and deprecation notices here too
+          log::warn!(target: BACKEND_TARGET, "{}", line);
+          let _ = app.emit(EVENT_LOG, LogPayload { stream: LogStream::Stderr, line });
+        }
+        CommandEvent::Error(err) => {
+          log::error!(target: BACKEND_TARGET, "Fatal: {}", err);
+          let _ = app.emit(EVENT_LOG, LogPayload { stream: LogStream::Stderr, line: err });
+        }
+        CommandEvent::Terminated(payload) => {
+          untrack_pid(&app, pid);
+          handle_exit(&app, generation, payload.code, payload.signal);
+          break;
+        }
+        _ => {}
+      }
+    }
+  });
+
+  Ok(())
+}
+
+fn is_current(app: &AppHandle, generation: u64) -> bool {
+  let state = app.state::<BackendState>();
+  let supervisor = state.supervisor.lock().unwrap();
+  supervisor.generation == generation && supervisor.phase == BackendPhase::Starting
+}
+
+/// Polls the sidecar's HTTP endpoint until it answers, then marks this
+/// generation as ready. Gives up silently once the generation is replaced.
+async fn probe_until_ready(app: &AppHandle, generation: u64) {
+  let client = match reqwest::Client::builder().timeout(PROBE_TIMEOUT).build() {
+    Ok(client) => client,
+    Err(e) => {
+      log::error!("Failed to build health probe client: {}", e);
+      return;
+    }
+  };
+  let url = format!("{}{}", app.state::<BackendState>().url(), HEALTH_PATH);
+
+  while is_current(app, generation) {
+    if let Ok(res) = client.get(&url).send().await {
+      if res.status().is_success() {
+        mark_ready(app, generation);
+        return;
+      }
+    }
+    tokio::time::sleep(PROBE_INTERVAL).await;
+  }
+}
+
+/// Waits until the backend reports `Ready`. Returns an error if it crashes
+/// or does not come up within `timeout`.
+pub async fn wait_until_ready(app: &AppHandle, timeout: Duration) -> Result<(), String> {
+  let deadline = Instant::now() + timeout;
+  loop {
+    match app.state::<BackendState>().phase() {
+      BackendPhase::Ready => return Ok(()),
+      BackendPhase::Crashed => return Err("The Forge backend keeps crashing on startup.".to_string()),
+      BackendPhase::Stopped => return Err("The Forge backend is not running.".to_string()),
+      BackendPhase::Starting | BackendPhase::Restarting => {}
+    }
+    if Instant::now() >= deadline {
+      return Err(format!(
+        "The Forge backend did not respond within {} seconds.",
+        timeout.as_secs()
+      ));
+    }
+    tokio::time::sleep(PROBE_INTERVAL).await;
+  }
+}
+
+fn mark_ready(app: &AppHandle, generation: u64) {
+  let state = app.state::<BackendState>();
+  let mut supervisor = state.supervisor.lock().unwrap();
+  if supervisor.generation != generation || supervisor.phase != BackendPhase::Starting {
+    return;
+  }
+  supervisor.phase = BackendPhase::Ready;
+  drop(supervisor);
+
+  log::info!("Backend ready");
+  let _ = app.emit(
+    EVENT_READY,
+    ReadyPayload { pid: state.pid(), port: state.port, url: state.url() },
+  );
+}
+
+fn emit_exited(app: &AppHandle, payload: ExitedPayload) {
+  let _ = app.emit(EVENT_EXITED, payload);
+}
+
+fn handle_exit(app: &AppHandle, generation: u64, code: Option<i32>, signal: Option<i32>) {
+  let state = app.state::<BackendState>();
+  state.exits.send_modify(|exits| *exits += 1);
+  let mut supervisor = state.supervisor.lock().unwrap();
+  supervisor.last_exit_code = code;
+
+  // Either a deliberate stop or a child we already replaced
+  if supervisor.generation != generation || supervisor.phase == BackendPhase::Stopped {
+    drop(supervisor);
+    emit_exited(app, ExitedPayload { code, signal, unexpected: false, will_restart: false });
+    return;
+  }
+
+  state.child.lock().unwrap().take();
+
+  let uptime = supervisor.started_at.take().map(|t| t.elapsed());
+  if uptime.is_some_and(|u| u >= STABLE_UPTIME) {
+    supervisor.crash_count = 0;
+  }
+  supervisor.crash_count += 1;
+
+  log::warn!(
+    "Backend exited unexpectedly (code {:?}, crash {}/{})",
+    code, supervisor.crash_count, MAX_RESTARTS
+  );
+
+  if supervisor.crash_count > MAX_RESTARTS {
+    supervisor.phase = BackendPhase::Crashed;
+    drop(supervisor);
+    log::error!("Backend is crash-looping, giving up");
+    emit_exited(app, ExitedPayload { code, signal, unexpected: true, will_restart: false });
+    return;
+  }
+
+  supervisor.phase = BackendPhase::Restarting;
+  let delay = backoff_for(supervisor.crash_count);
+  drop(supervisor);
+  emit_exited(app, ExitedPayload { code, signal, unexpected: true, will_restart: true });
+
+  log::info!("Restarting backend in {:?}", delay);
+  let app = app.clone();
+  tauri::async_runtime::spawn(async move {
+    tokio::time::sleep(delay).await;
+    restart(&app, generation);
+  });
+}
+
+fn restart(app: &AppHandle, previous_generation: u64) {
+  let state = app.state::<BackendState>();
+  let generation = {
+    let mut supervisor = state.supervisor.lock().unwrap();
+    // Someone stopped or restarted the backend while we were backing off
+    if supervisor.generation != previous_generation
+      || supervisor.phase != BackendPhase::Restarting
+    {
+      return;
+    }
+    supervisor.generation += 1;
+    supervisor.phase = BackendPhase::Starting;
+    supervisor.restart_count += 1;
+    supervisor.generation
+  };
+
+  if let Err(e) = spawn_sidecar(app, generation) {
+    log::error!("{}", e);
+    handle_exit(app, generation, None, None);
+  }
+}
+
+#[tauri::command]
+pub async fn start_backend(app: AppHandle) -> Result<String, String> {
+  let state = app.state::<BackendState>();
+
+  let generation = {
+    let mut supervisor = state.supervisor.lock().unwrap();
+    match supervisor.phase {
+      BackendPhase::Starting | BackendPhase::Ready | BackendPhase::Restarting => {
+        return Ok("Backend already running".to_string());
+      }
+      BackendPhase::Stopped | BackendPhase::Crashed => {}
+    }
+    supervisor.generation += 1;
+    supervisor.phase = BackendPhase::Starting;
+    supervisor.crash_count = 0;
+    supervisor.generation
+  };
+
+  let prepared = match local_db::mode(&app) {
+    DatabaseMode::Embedded => local_db::ensure_started(&app).await.map(|_| ()),
+    DatabaseMode::External => {
+      // Switched to an external database; the embedded one is no longer needed
+      if local_db::is_running(&app) {
+        local_db::stop(&app).await;
+      }
+      Ok(())
+    }
+  };
+
+  if let Err(e) = prepared.and_then(|_| spawn_sidecar(&app, generation)) {
+    let mut supervisor = state.supervisor.lock().unwrap();
+    if supervisor.generation == generation {
+      supervisor.phase = BackendPhase::Stopped;
+    }
+    return Err(e);
+  }
+
+  Ok("Backend started".to_string())
+}
+
+fn pid_file_path(app: &AppHandle) -> Option<PathBuf> {
+  app.path().app_data_dir().ok().map(|dir| dir.join(PID_FILE_NAME))
+}
+
+/// Records the child's PID on disk so a later session can clean it up if
+/// this one dies without stopping it.
+fn track_pid(app: &AppHandle, pid: u32) {
+  RUNNING_PID.store(pid, Ordering::SeqCst);
+  let Some(path) = pid_file_path(app) else {
+    return;
+  };
+  if let Some(dir) = path.parent() {
+    let _ = fs::create_dir_all(dir);
+  }
+  if let Err(e) = fs::write(&path, pid.to_string()) {
+    log::warn!("Failed to write {}: {}", path.display(), e);
+  }
+}
+
+fn untrack_pid(app: &AppHandle, pid: u32) {
+  // Only forget the PID if a newer child has not replaced it already
+  if RUNNING_PID.compare_exchange(pid, 0, Ordering::SeqCst, Ordering::SeqCst).is_ok() {
+    if let Some(path) = pid_file_path(app) {
+      let _ = fs::remove_file(path);
+    }
+  }
+}
+
+/// Kills `pid` if it is still a Forge sidecar. The name check guards against
+/// the OS having recycled the PID for an unrelated process.
+fn kill_sidecar_pid(pid: u32) -> bool {
+  let pid = sysinfo::Pid::from_u32(pid);
+  let mut system = sysinfo::System::new();
+  system.refresh_processes(sysinfo::ProcessesToUpdate::Some(&[pid]), true);
+  match system.process(pid) {
+    Some(process) if process.name().to_string_lossy().starts_with(SIDECAR_PROCESS_PREFIX) => {
+      process.kill()
+    }
+    _ => false,
+  }
+}
+
+/// Kills a sidecar left running by a previous session that crashed or was
+/// killed before it could stop its backend.
+pub fn reap_stale_sidecar(app: &AppHandle) {
+  let Some(path) = pid_file_path(app) else {
+    return;
+  };
+  let Ok(contents) = fs::read_to_string(&path) else {
+    return;
+  };
+  if let Ok(pid) = contents.trim().parse::<u32>() {
+    if kill_sidecar_pid(pid) {
+      log::warn!("Killed stale backend from a previous session (pid {})", pid);
+    }
+  }
+  let _ = fs::remove_file(&path);
+}
+
+/// Kills the running sidecar without touching any locks. Only for paths that
+/// cannot wait, such as a panic or the final exit event; everything else
+/// should use `shutdown_backend`.
+pub fn kill_sidecar_now() {
+  let pid = RUNNING_PID.swap(0, Ordering::SeqCst);
+  if pid != 0 && kill_sidecar_pid(pid) {
+    log::warn!("Backend killed (pid {})", pid);
+  }
+}
+
+pub fn is_running(app: &AppHandle) -> bool {
+  app.state::<BackendState>().pid().is_some()
+}
+
+/// Marks the backend as deliberately stopped so its exit is not treated as
+/// a crash, and hands back the child if one is running.
+fn detach_child(app: &AppHandle) -> Option<CommandChild> {
+  let state = app.state::<BackendState>();
+  {
+    let mut supervisor = state.supervisor.lock().unwrap();
+    supervisor.generation += 1;
+    supervisor.phase = BackendPhase::Stopped;
+    supervisor.started_at = None;
+  }
+  let child = state.child.lock().unwrap().take();
+  child
+}
+
+/// Asks the sidecar to shut down cleanly, first through its shutdown endpoint
+/// and then with SIGTERM where available, and only force kills it once
+/// `grace` has elapsed without an exit.
+pub async fn shutdown_backend(app: &AppHandle, grace: Duration) {
+  let state = app.state::<BackendState>();
+  // A second caller waits for the shutdown already in flight
+  let _shutting_down = state.shutdown_lock.lock().await;
+  let mut exits = state.exits.subscribe();
+  let Some(child) = detach_child(app) else {
+    return;
+  };
+  let pid = child.pid();
+  log::info!("Shutting down backend (pid {})", pid);
+
+  if let Err(e) = request_shutdown(app).await {
+    log::warn!("Shutdown endpoint unavailable ({}), signalling instead", e);
+    send_terminate(pid);
+  }
+
+  match tokio::time::timeout(grace, exits.changed()).await {
+    Ok(_) => log::info!("Backend stopped"),
+    Err(_) => {
+      log::warn!("Backend did not exit within {:?}, killing it", grace);
+      let _ = child.kill();
+    }
+  }
+}
+
+/// A request to one of the backend's shell-only `/api/internal` routes
+pub fn internal_request(
+  app: &AppHandle,
+  client: &reqwest::Client,
+  method: reqwest::Method,
+  path: &str,
+) -> reqwest::RequestBuilder {
+  let state = app.state::<BackendState>();
+  client
+    .request(method, format!("{}{}", state.url(), path))
+    .header(SHELL_TOKEN_HEADER, &state.shell_token)
+}
+
+async fn request_shutdown(app: &AppHandle) -> Result<(), String> {
+  let client = reqwest::Client::builder()
+    .timeout(PROBE_TIMEOUT)
+    .build()
+    .map_err(|e| e.to_string())?;
+  let res = internal_request(app, &client, reqwest::Method::POST, SHUTDOWN_PATH)
+    .send()
+    .await
+    .map_err(|e| e.to_string())?;
+  if !res.status().is_success() {
+    return Err(format!("status {}", res.status()));
+  }
+  Ok(())
+}
+
+#[cfg(unix)]
+fn send_terminate(pid: u32) {
+  unsafe {
+    libc::kill(pid as libc::pid_t, libc::SIGTERM);
+  }
+}
+
+// Windows has no SIGTERM equivalent for console-less processes; the grace
+// period simply runs out and the child is killed.
+#[cfg(not(unix))]
+fn send_terminate(_pid: u32) {}
+
+/// Restarts a running (or crashed) backend so it rereads its configuration.
+/// A backend the user stopped stays stopped.
+pub async fn restart_backend(app: &AppHandle) -> Result<(), String> {
+  if app.state::<BackendState>().phase() == BackendPhase::Stopped {
+    return Ok(());
+  }
+  shutdown_backend(app, SHUTDOWN_GRACE).await;
+  start_backend(app.clone()).await.map(|_| ())
+}
+
+#[tauri::command]
+pub async fn stop_backend(app: AppHandle, grace_ms: Option<u64>) -> Result<String, String> {
+  let grace = grace_ms.map(Duration::from_millis).unwrap_or(SHUTDOWN_GRACE);
+  shutdown_backend(&app, grace).await;
+  Ok("Backend stopped".to_string())
+}
+
+#[tauri::command]
+pub fn backend_state(app: AppHandle) -> BackendPhase {
+  app.state::<BackendState>().phase()
+}
+
+#[tauri::command]
+pub fn backend_status(app: AppHandle) -> BackendStatus {
+  app.state::<BackendState>().status()
+}
+
+#[tauri::command]
+pub fn get_backend_url(app: AppHandle) -> String {
+  app.state::<BackendState>().url()
+}
@@ -0,0 +1,764 @@
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Local, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tauri::{AppHandle, Manager};
+use tokio::sync::Mutex;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+use crate::backend;
+use crate::backup_schedule;
+use crate::config;
+use crate::local_db::{self, DatabaseMode};
+use crate::proofs;
+use crate::vectors;
+
+const BACKUP_DIR_NAME: &str = "backups";
+const BACKUP_PREFIX: &str = "forge-backup-";
+const BACKUP_EXTENSION: &str = "zip";
+const CHECKSUM_EXTENSION: &str = "sha256";
+const MANIFEST_NAME: &str = "manifest.json";
+const BACKUP_FORMAT: u32 = 1;
+
+// Top-level folders inside the archive
+const DATABASE_ENTRY: &str = "database";
+const KB_ENTRY: &str = "forge_kb";
+const PROOFS_ENTRY: &str = "proofs";
+// Written by a running server; a stopped cluster must not carry one
+const SKIPPED_FILES: [&str; 1] = ["postmaster.pid"];
+
+/// Serializes backups and restores. Both stop the backend, so two of them
+/// interleaving would restart it halfway through the other.
+pub struct BackupState {
+  lock: Mutex<()>,
+}
+
+impl BackupState {
+  pub fn new() -> Self {
+    Self { lock: Mutex::new(()) }
+  }
+}
+
+/// What caused a backup. Only automatic ones are subject to retention.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum BackupTrigger {
+  #[default]
+  Manual,
+  Scheduled,
+  PreUpdate,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ManifestEntry {
+  path: String,
+  size: u64,
+  sha256: String,
+}
+
+/// Stored as the last entry of every archive. The per-file hashes let a
+/// restore refuse a damaged backup before touching live data.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Manifest {
+  format: u32,
+  created_at: DateTime<Utc>,
+  app_version: String,
+  database_mode: DatabaseMode,
+  #[serde(default)]
+  trigger: BackupTrigger,
+  files: Vec<ManifestEntry>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupInfo {
+  pub name: String,
+  pub path: String,
+  pub created_at: DateTime<Utc>,
+  pub size_bytes: u64,
+  pub file_count: usize,
+  pub app_version: String,
+  pub trigger: BackupTrigger,
+  // SHA-256 of the whole archive, from its `.sha256` file
+  pub checksum: Option<String>,
+}
+
+pub fn backup_dir(app: &AppHandle) -> Result<PathBuf, String> {
+  app
+    .path()
+    .app_data_dir()
+    .map(|dir| dir.join(BACKUP_DIR_NAME))
+    .map_err(|e| format!("Could not determine the data directory: {}", e))
+}
+
+/// Archive folder and live directory for everything a backup covers
+fn sources(app: &AppHandle) -> Result<[(&'static str, PathBuf); 3], String> {
+  Ok([
+    (DATABASE_ENTRY, local_db::data_dir(app)?),
+    (KB_ENTRY, config::kb_dir(app)?),
+    (PROOFS_ENTRY, proofs::proofs_dir(app)?),
+  ])
+}
+
+pub fn hex(digest: impl AsRef<[u8]>) -> String {
+  digest.as_ref().iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Passes writes through while hashing them, so each file is read once
+struct HashingWriter<W> {
+  inner: W,
+  hasher: Sha256,
+  written: u64,
+}
+
+impl<W: Write> Write for HashingWriter<W> {
+  fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+    let n = self.inner.write(buf)?;
+    self.hasher.update(&buf[..n]);
+    self.written += n as u64;
+    Ok(n)
+  }
+
+  fn flush(&mut self) -> io::Result<()> {
+    self.inner.flush()
+  }
+}
+
+fn hash_reader(reader: &mut impl Read) -> io::Result<(String, u64)> {
+  let mut writer = HashingWriter { inner: io::sink(), hasher: Sha256::new(), written: 0 };
+  io::copy(reader, &mut writer)?;
+  Ok((hex(writer.hasher.finalize()), writer.written))
+}
+
+fn hash_file(path: &Path) -> io::Result<String> {
+  let mut file = BufReader::new(File::open(path)?);
+  hash_reader(&mut file).map(|(hash, _)| hash)
+}
+
+/// Files and directories under `dir`, relative to it, in a stable order
+pub fn walk(dir: &Path) -> io::Result<Vec<(PathBuf, bool)>> {
+  let mut found = Vec::new();
+  let mut pending = vec![PathBuf::new()];
+  while let Some(relative) = pending.pop() {
+    let mut entries: Vec<_> = fs::read_dir(dir.join(&relative))?.collect::<Result<_, _>>()?;
+    entries.sort_by_key(|e| e.file_name());
+    for entry in entries {
+      let path = relative.join(entry.file_name());
+      let file_type = entry.file_type()?;
+      if file_type.is_dir() {
+        found.push((path.clone(), true));
+        pending.push(path);
+      } else if file_type.is_file() {
+        found.push((path, false));
+      }
+    }
+  }
+  Ok(found)
+}
+
+/// Zip entry names always use forward slashes
+pub fn entry_name(root: &str, relative: &Path) -> String {
+  let mut name = root.to_string();
+  for part in relative.components() {
+    name.push('/');
+    name.push_str(&part.as_os_str().to_string_lossy());
+  }
+  name
+}
+
+fn write_archive(
+  dest: &Path,
+  sources: &[(&'static str, PathBuf)],
+  database_mode: DatabaseMode,
+  trigger: BackupTrigger,
+  app_version: &str,
+) -> Result<Manifest, String> {
+  let io_err = |e: io::Error| format!("Failed to write {}: {}", dest.display(), e);
+  let zip_err = |e: zip::result::ZipError| format!("Failed to write {}: {}", dest.display(), e);
+
+  let file = File::create(dest).map_err(io_err)?;
+  let mut zip = ZipWriter::new(BufWriter::new(file));
+  let options = SimpleFileOptions::default()
+    .compression_method(CompressionMethod::Deflated)
+    .large_file(true);
+  let mut files = Vec::new();
+
+  for (root, dir) in sources {
+    zip.add_directory(format!("{}/", root), options).map_err(zip_err)?;
+    if !dir.is_dir() {
+      continue;
+    }
+    for (relative, is_dir) in walk(dir).map_err(io_err)? {
+      let name = entry_name(root, &relative);
+      if is_dir {
+        // PostgreSQL refuses to start if its empty directories are missing
+        zip.add_directory(format!("{}/", name), options).map_err(zip_err)?;
+        continue;
+      }
+      if relative.file_name().is_some_and(|f| SKIPPED_FILES.iter().any(|s| f == *s)) {
+        continue;
+      }
+      zip.start_file(name.as_str(), options).map_err(zip_err)?;
+      let mut source = BufReader::new(
+        File::open(dir.join(&relative))
+          .map_err(|e| format!("Failed to read {}: {}", relative.display(), e))?,
+      );
+      let mut writer = HashingWriter { inner: &mut zip, hasher: Sha256::new(), written: 0 };
+      io::copy(&mut source, &mut writer).map_err(io_err)?;
+      files.push(ManifestEntry {
+        path: name,
+        size: writer.written,
+        sha256: hex(writer.hasher.finalize()),
+      });
+    }
+  }
+
+  let manifest = Manifest {
+    format: BACKUP_FORMAT,
+    created_at: Utc::now(),
+    app_version: app_version.to_string(),
+    database_mode,
+    trigger,
+    files,
+  };
+  zip.start_file(MANIFEST_NAME, options).map_err(zip_err)?;
+  serde_json::to_writer_pretty(&mut zip, &manifest).map_err(|e| e.to_string())?;
+  zip
+    .finish()
+    .map_err(zip_err)?
+    .into_inner()
+    .map_err(|e| io_err(e.into_error()))?
+    .sync_all()
+    .map_err(io_err)?;
+  Ok(manifest)
+}
+
+fn open_archive(path: &Path) -> Result<ZipArchive<BufReader<File>>, String> {
+  let file = File::open(path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+  ZipArchive::new(BufReader::new(file))
+    .map_err(|e| format!("{} is not a valid backup: {}", path.display(), e))
+}
+
+fn read_manifest(archive: &mut ZipArchive<BufReader<File>>) -> Result<Manifest, String> {
+  let entry = archive
+    .by_name(MANIFEST_NAME)
+    .map_err(|_| "Backup has no manifest".to_string())?;
+  let manifest: Manifest =
+    serde_json::from_reader(entry).map_err(|e| format!("Corrupt backup manifest: {}", e))?;
+  if manifest.format != BACKUP_FORMAT {
+    return Err(format!("Unsupported backup format {}", manifest.format));
+  }
+  Ok(manifest)
+}
+
+fn checksum_path(archive: &Path) -> PathBuf {
+  archive.with_extension(format!("{}.{}", BACKUP_EXTENSION, CHECKSUM_EXTENSION))
+}
+
+fn read_checksum(archive: &Path) -> Option<String> {
+  let contents = fs::read_to_string(checksum_path(archive)).ok()?;
+  contents.split_whitespace().next().map(str::to_string)
+}
+
+/// Checks the archive against its `.sha256` file and every entry against the
+/// manifest. Returns the manifest once the whole backup is known to be intact.
+fn verify(path: &Path) -> Result<Manifest, String> {
+  if let Some(expected) = read_checksum(path) {
+    let actual = hash_file(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    if actual != expected {
+      return Err("Backup checksum does not match; the file is damaged".to_string());
+    }
+  }
+
+  let mut archive = open_archive(path)?;
+  let manifest = read_manifest(&mut archive)?;
+  for expected in &manifest.files {
+    let mut entry = archive
+      .by_name(&expected.path)
+      .map_err(|_| format!("Backup is missing {}", expected.path))?;
+    let (hash, size) =
+      hash_reader(&mut entry).map_err(|e| format!("Failed to read {}: {}", expected.path, e))?;
+    if hash != expected.sha256 || size != expected.size {
+      return Err(format!("Backup entry {} is damaged", expected.path));
+    }
+  }
+  Ok(manifest)
+}
+
+/// Unpacks the entries under `root/` into `dest`, which must not exist yet
+fn extract(path: &Path, root: &str, dest: &Path) -> Result<(), String> {
+  let io_err = |e: io::Error| format!("Failed to restore into {}: {}", dest.display(), e);
+  let mut archive = open_archive(path)?;
+  fs::create_dir_all(dest).map_err(io_err)?;
+
+  for i in 0..archive.len() {
+    let mut entry = archive.by_index(i).map_err(|e| format!("Corrupt backup: {}", e))?;
+    // `enclosed_name` rejects absolute paths and `..`
+    let Some(name) = entry.enclosed_name() else {
+      return Err(format!("Backup contains an unsafe path: {}", entry.name()));
+    };
+    let Ok(relative) = name.strip_prefix(root) else {
+      continue;
+    };
+    let target = dest.join(relative);
+    if entry.is_dir() {
+      fs::create_dir_all(&target).map_err(io_err)?;
+      continue;
+    }
+    if let Some(parent) = target.parent() {
+      fs::create_dir_all(parent).map_err(io_err)?;
+    }
+    let mut out = BufWriter::new(File::create(&target).map_err(io_err)?);
+    io::copy(&mut entry, &mut out).map_err(io_err)?;
+    out.flush().map_err(io_err)?;
+  }
+
+  #[cfg(unix)]
+  {
+    // initdb's permissions; PostgreSQL will not start on a group/world
+    // readable data directory
+    use std::os::unix::fs::PermissionsExt;
+    fs::set_permissions(dest, fs::Permissions::from_mode(0o700)).map_err(io_err)?;
+  }
+  Ok(())
+}
+
+fn sibling(dir: &Path, suffix: &str) -> PathBuf {
+  let mut name = dir.file_name().unwrap_or_default().to_os_string();
+  name.push(suffix);
+  dir.with_file_name(name)
+}
+
+/// Unpacks every source next to its live directory first, so a failure
+/// leaves the live data untouched, then swaps them in. A failed swap puts
+/// the previous directories back.
+fn restore_archive(path: &Path, sources: &[(&'static str, PathBuf)]) -> Result<(), String> {
+  let staged: Vec<PathBuf> = sources.iter().map(|(_, dir)| sibling(dir, ".restore")).collect();
+  let previous: Vec<PathBuf> = sources.iter().map(|(_, dir)| sibling(dir, ".pre-restore")).collect();
+  for dir in staged.iter().chain(&previous) {
+    let _ = fs::remove_dir_all(dir);
+  }
+
+  let cleanup = |dirs: &[PathBuf]| {
+    for dir in dirs {
+      let _ = fs::remove_dir_all(dir);
+    }
+  };
+  for ((root, _), stage) in sources.iter().zip(&staged) {
+    if let Err(e) = extract(path, root, stage) {
+      cleanup(&staged);
+      return Err(e);
+    }
+  }
+
+  // Sources whose live directory has been moved aside
+  let mut moved = Vec::new();
+  for (i, (_, live)) in sources.iter().enumerate() {
+    let mut result = Ok(());
+    if live.exists() {
+      result = fs::rename(live, &previous[i]);
+      if result.is_ok() {
+        moved.push(i);
+      }
+    }
+    if let Err(e) = result.and_then(|_| fs::rename(&staged[i], live)) {
+      log::error!("Restore failed while replacing {}: {}", live.display(), e);
+      // Including this one, which may have been moved aside already
+      for (j, (_, live)) in sources.iter().enumerate().take(i + 1) {
+        if moved.contains(&j) {
+          let _ = fs::remove_dir_all(live);
+          let _ = fs::rename(&previous[j], live);
+        } else if j < i {
+          // Did not exist before the restore
+          let _ = fs::remove_dir_all(live);
+        }
+      }
+      cleanup(&staged);
+      return Err(format!("Failed to replace {}: {}", live.display(), e));
+    }
+  }
+
+  cleanup(&previous);
+  Ok(())
+}
+
+fn info(path: &Path) -> Result<BackupInfo, String> {
+  let size_bytes = fs::metadata(path).map_err(|e| e.to_string())?.len();
+  let manifest = read_manifest(&mut open_archive(path)?)?;
+  Ok(BackupInfo {
+    name: path.file_name().unwrap_or_default().to_string_lossy().into_owned(),
+    path: path.to_string_lossy().into_owned(),
+    created_at: manifest.created_at,
+    size_bytes,
+    file_count: manifest.files.len(),
+    app_version: manifest.app_version,
+    trigger: manifest.trigger,
+    checksum: read_checksum(path),
+  })
+}
+
+pub fn list(app: &AppHandle) -> Result<Vec<BackupInfo>, String> {
+  let dir = backup_dir(app)?;
+  let entries = match fs::read_dir(&dir) {
+    Ok(entries) => entries,
+    Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+    Err(e) => return Err(format!("Failed to read {}: {}", dir.display(), e)),
+  };
+
+  let mut backups: Vec<BackupInfo> = entries
+    .filter_map(|entry| entry.ok().map(|e| e.path()))
+    .filter(|path| {
+      path.extension().is_some_and(|ext| ext == BACKUP_EXTENSION)
+        && path
+          .file_name()
+          .is_some_and(|name| name.to_string_lossy().starts_with(BACKUP_PREFIX))
+    })
+    .filter_map(|path| match info(&path) {
+      Ok(info) => Some(info),
+      Err(e) => {
+        log::warn!("Skipping unreadable backup {}: {}", path.display(), e);
+        None
+      }
+    })
+    .collect();
+  backups.sort_by_key(|b| std::cmp::Reverse(b.created_at));
+  Ok(backups)
+}
+
+/// Resolves a name from `list_backups` to a file in the backup directory,
+/// refusing anything that would point elsewhere.
+fn resolve(app: &AppHandle, name: &str) -> Result<PathBuf, String> {
+  let valid = name.starts_with(BACKUP_PREFIX)
+    && name.ends_with(&format!(".{}", BACKUP_EXTENSION))
+    && !name.contains(['/', '\\'])
+    && !name.contains("..");
+  let path = backup_dir(app)?.join(name);
+  if !valid || !path.is_file() {
+    return Err(format!("No backup named {}", name));
+  }
+  Ok(path)
+}
+
+fn ensure_embedded(app: &AppHandle) -> Result<(), String> {
+  match local_db::mode(app) {
+    DatabaseMode::Embedded => Ok(()),
+    DatabaseMode::External => Err(
+      "Backups cover the built-in database only. Back up your own PostgreSQL server with pg_dump."
+        .to_string(),
+    ),
+  }
+}
+
+/// Stops the backend and local database so their files are consistent on
+/// disk, runs `work` on a blocking thread, then brings the backend back if it
+/// was running.
+async fn with_backend_stopped<T: Send + 'static>(
+  app: &AppHandle,
+  work: impl FnOnce() -> Result<T, String> + Send + 'static,
+) -> Result<T, String> {
+  let was_running = backend::is_running(app);
+  backend::shutdown_backend(app, backend::SHUTDOWN_GRACE).await;
+  local_db::stop(app).await;
+
+  let result = tauri::async_runtime::spawn_blocking(work)
+    .await
+    .map_err(|e| format!("Backup task failed: {}", e))
+    .and_then(|r| r);
+
+  if was_running {
+    if let Err(e) = backend::start_backend(app.clone()).await {
+      log::error!("Failed to restart backend: {}", e);
+    }
+  }
+  result
+}
+
+/// Takes a full snapshot of the local database, knowledge base and proofs
+pub async fn create(app: &AppHandle, trigger: BackupTrigger) -> Result<BackupInfo, String> {
+  ensure_embedded(app)?;
+  let state = app.state::<BackupState>();
+  let _guard = state.lock.lock().await;
+
+  let dir = backup_dir(app)?;
+  fs::create_dir_all(&dir).map_err(|e| format!("Could not create {}: {}", dir.display(), e))?;
+  // Milliseconds keep two backups in the same second apart; the lock above
+  // means no two are written within the same millisecond
+  let name = format!(
+    "{}{}.{}",
+    BACKUP_PREFIX,
+    Local::now().format("%Y%m%d-%H%M%S-%3f"),
+    BACKUP_EXTENSION
+  );
+  let path = dir.join(&name);
+  let partial = path.with_extension("partial");
+  let sources = sources(app)?;
+  let app_version = app.package_info().version.to_string();
+
+  log::info!("Creating backup {}", name);
+  let target = path.clone();
+  let written = with_backend_stopped(app, move || {
+    let result = write_archive(&partial, &sources, DatabaseMode::Embedded, trigger, &app_version)
+      .and_then(|_| verify(&partial))
+      .and_then(|_| {
+        let checksum =
+          hash_file(&partial).map_err(|e| format!("Failed to read {}: {}", partial.display(), e))?;
+        fs::write(checksum_path(&target), format!("{}  {}\n", checksum, name))
+          .and_then(|_| fs::rename(&partial, &target))
+          .map_err(|e| format!("Failed to save backup: {}", e))
+      });
+    if result.is_err() {
+      let _ = fs::remove_file(&partial);
+      let _ = fs::remove_file(checksum_path(&target));
+    }
+    result
+  })
+  .await;
+
+  match written {
+    Ok(()) => {
+      let info = info(&path)?;
+      log::info!("Backup {} complete ({} files)", info.name, info.file_count);
+      Ok(info)
+    }
+    Err(e) => {
+      log::error!("Backup failed: {}", e);
+      Err(e)
+    }
+  }
+}
+
+/// Replaces the local database, knowledge base and proofs with a backup's contents
+pub async fn restore(app: &AppHandle, name: &str) -> Result<(), String> {
+  ensure_embedded(app)?;
+  let path = resolve(app, name)?;
+  let state = app.state::<BackupState>();
+  let _guard = state.lock.lock().await;
+
+  // Verify before stopping anything, so a bad backup costs no downtime
+  let checked = path.clone();
+  tauri::async_runtime::spawn_blocking(move || verify(&checked))
+    .await
+    .map_err(|e| format!("Backup task failed: {}", e))??;
+
+  log::info!("Restoring backup {}", name);
+  let sources = sources(app)?;
+  let handle = app.clone();
+  with_backend_stopped(app, move || {
+    restore_archive(&path, &sources)?;
+    // The index describes chunks the restored database may not have. Cleared
+    // before the backend comes back so it never serves stale results.
+    vectors::reset(&handle);
+    Ok(())
+  })
+  .await?;
+  log::info!("Restored backup {}", name);
+  Ok(())
+}
+
+/// Deletes a backup and its checksum file
+pub fn remove(app: &AppHandle, name: &str) -> Result<(), String> {
+  let path = resolve(app, name)?;
+  fs::remove_file(&path).map_err(|e| format!("Failed to delete {}: {}", name, e))?;
+  let _ = fs::remove_file(checksum_path(&path));
+  Ok(())
+}
+
+#[tauri::command]
+pub async fn create_backup(app: AppHandle) -> Result<BackupInfo, String> {
+  let result = create(&app, BackupTrigger::Manual).await;
+  backup_schedule::record(&app, BackupTrigger::Manual, &result);
+  result
+}
+
+#[tauri::command]
+pub async fn restore_backup(app: AppHandle, name: String) -> Result<(), String> {
+  restore(&app, &name).await
+}
+
+#[tauri::command]
+pub fn list_backups(app: AppHandle) -> Result<Vec<BackupInfo>, String> {
+  list(&app)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test_util::TempDir;
+
+  fn backup(dir: &TempDir, sources: &[(&'static str, PathBuf)]) -> PathBuf {
+    let path = dir.path().join("forge-backup-test.zip");
+    write_archive(&path, sources, DatabaseMode::Embedded, BackupTrigger::Manual, "1.0.0").unwrap();
+    path
+  }
+
+  /// An archive holding exactly `entries`, for backups no writer would produce
+  fn raw_archive(dir: &TempDir, entries: &[(&str, &[u8])]) -> PathBuf {
+    let path = dir.path().join("forge-backup-raw.zip");
+    let mut zip = ZipWriter::new(File::create(&path).unwrap());
+    for (name, contents) in entries {
+      zip.start_file(*name, SimpleFileOptions::default()).unwrap();
+      zip.write_all(contents).unwrap();
+    }
+    zip.finish().unwrap();
+    path
+  }
+
+  fn manifest(files: &[(&str, &[u8])]) -> Vec<u8> {
+    let files = files
+      .iter()
+      .map(|(path, contents)| ManifestEntry {
+        path: path.to_string(),
+        size: contents.len() as u64,
+        sha256: hex(Sha256::digest(contents)),
+      })
+      .collect();
+    serde_json::to_vec(&Manifest {
+      format: BACKUP_FORMAT,
+      created_at: Utc::now(),
+      app_version: "1.0.0".to_string(),
+      database_mode: DatabaseMode::Embedded,
+      trigger: BackupTrigger::Manual,
+      files,
+    })
+    .unwrap()
+  }
+
+  fn read(path: &Path) -> String {
+    fs::read_to_string(path).unwrap()
+  }
+
+  #[test]
+  fn archive_round_trips_through_verify_and_extract() {
+    let dir = TempDir::new();
+    dir.file("live/db/PG_VERSION", "16");
+    dir.file("live/db/postmaster.pid", "4242");
+    fs::create_dir_all(dir.path().join("live/db/pg_tblspc")).unwrap();
+    dir.file("live/kb/notes/week1.md", "# Week 1");
+    let sources = [
+      (DATABASE_ENTRY, dir.path().join("live/db")),
+      (KB_ENTRY, dir.path().join("live/kb")),
+      (PROOFS_ENTRY, dir.path().join("live/missing")),
+    ];
+    let path = backup(&dir, &sources);
+
+    let manifest = verify(&path).unwrap();
+    let names: Vec<&str> = manifest.files.iter().map(|f| f.path.as_str()).collect();
+    assert_eq!(names, ["database/PG_VERSION", "forge_kb/notes/week1.md"]);
+
+    let out = dir.path().join("out");
+    extract(&path, DATABASE_ENTRY, &out.join("db")).unwrap();
+    extract(&path, PROOFS_ENTRY, &out.join("proofs")).unwrap();
+    assert_eq!(read(&out.join("db/PG_VERSION")), "16");
+    assert!(out.join("db/pg_tblspc").is_dir());
+    assert!(!out.join("db/postmaster.pid").exists());
+    assert!(!out.join("db/notes").exists());
+    assert!(out.join("proofs").is_dir());
+  }
+
+  #[test]
+  fn verify_rejects_a_checksum_mismatch() {
+    let dir = TempDir::new();
+    dir.file("live/kb/a.md", "a");
+    let path = backup(&dir, &[(KB_ENTRY, dir.path().join("live/kb"))]);
+    let checksum = hash_file(&path).unwrap();
+    fs::write(checksum_path(&path), format!("{}  forge-backup-test.zip\n", checksum)).unwrap();
+    assert!(verify(&path).is_ok());
+
+    fs::write(checksum_path(&path), format!("{}  forge-backup-test.zip\n", "0".repeat(64)))
+      .unwrap();
+    let err = verify(&path).unwrap_err();
+    assert!(err.contains("checksum does not match"), "{}", err);
+  }
+
+  #[test]
+  fn verify_rejects_damaged_and_missing_entries() {
+    let dir = TempDir::new();
+    let damaged = raw_archive(
+      &dir,
+      &[("forge_kb/a.md", b"tampered"), (MANIFEST_NAME, &manifest(&[("forge_kb/a.md", b"a")]))],
+    );
+    let err = verify(&damaged).unwrap_err();
+    assert!(err.contains("forge_kb/a.md is damaged"), "{}", err);
+
+    let missing = raw_archive(&dir, &[(MANIFEST_NAME, &manifest(&[("forge_kb/a.md", b"a")]))]);
+    let err = verify(&missing).unwrap_err();
+    assert!(err.contains("missing forge_kb/a.md"), "{}", err);
+
+    let unlisted = raw_archive(&dir, &[("forge_kb/a.md", b"a")]);
+    assert_eq!(verify(&unlisted).unwrap_err(), "Backup has no manifest");
+  }
+
+  #[test]
+  fn extract_refuses_paths_outside_the_target() {
+    let dir = TempDir::new();
+    for name in ["forge_kb/../../escaped.md", "/forge_kb/absolute.md"] {
+      let path = raw_archive(&dir, &[(name, b"x")]);
+      let out = dir.path().join("out").join("kb");
+      let err = extract(&path, KB_ENTRY, &out).unwrap_err();
+      assert!(err.contains("unsafe path"), "{}", err);
+      assert!(!dir.path().join("escaped.md").exists());
+      assert!(!dir.path().join("out/escaped.md").exists());
+    }
+  }
+
+  #[test]
+  fn restore_replaces_every_source() {
+    let dir = TempDir::new();
+    dir.file("live/db/PG_VERSION", "16");
+    dir.file("live/kb/a.md", "backed up");
+    let sources =
+      [(DATABASE_ENTRY, dir.path().join("live/db")), (KB_ENTRY, dir.path().join("live/kb"))];
+    let path = backup(&dir, &sources);
+
+    dir.file("live/kb/a.md", "edited later");
+    dir.file("live/kb/b.md", "added later");
+    restore_archive(&path, &sources).unwrap();
+    assert_eq!(read(&dir.path().join("live/kb/a.md")), "backed up");
+    assert!(!dir.path().join("live/kb/b.md").exists());
+    assert_eq!(read(&dir.path().join("live/db/PG_VERSION")), "16");
+    assert!(!dir.path().join("live/kb.pre-restore").exists());
+    assert!(!dir.path().join("live/kb.restore").exists());
+  }
+
+  #[test]
+  fn restore_leaves_live_data_alone_when_unpacking_fails() {
+    let dir = TempDir::new();
+    dir.file("live/kb/a.md", "live");
+    let path = raw_archive(&dir, &[("forge_kb/a.md", b"backed up"), ("../escaped.md", b"x")]);
+    let sources = [(KB_ENTRY, dir.path().join("live/kb"))];
+
+    assert!(restore_archive(&path, &sources).is_err());
+    assert_eq!(read(&dir.path().join("live/kb/a.md")), "live");
+    assert!(!dir.path().join("live/kb.restore").exists());
+  }
+
+  #[test]
+  fn restore_rolls_back_when_a_swap_fails_midway() {
+    let dir = TempDir::new();
+    dir.file("live/db/PG_VERSION", "16");
+    dir.file("live/kb/a.md", "backed up");
+    let sources =
+      [(DATABASE_ENTRY, dir.path().join("live/db")), (KB_ENTRY, dir.path().join("live/kb"))];
+    let path = backup(&dir, &sources);
+
+    dir.file("live/db/PG_VERSION", "17");
+    dir.file("live/kb/a.md", "live");
+    // A file where the knowledge base would be moved aside makes its swap
+    // fail after the database has already been replaced
+    dir.file("live/kb.pre-restore", "in the way");
+
+    let err = restore_archive(&path, &sources).unwrap_err();
+    assert!(err.contains("Failed to replace"), "{}", err);
+    assert_eq!(read(&dir.path().join("live/db/PG_VERSION")), "17");
+    assert_eq!(read(&dir.path().join("live/kb/a.md")), "live");
+    assert!(!dir.path().join("live/db.pre-restore").exists());
+    assert!(!dir.path().join("live/db.restore").exists());
+    assert!(!dir.path().join("live/kb.restore").exists());
+  }
+}
@@ -0,0 +1,451 @@
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use chrono::{DateTime, Datelike, Days, Local, NaiveTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+use tauri_plugin_notification::NotificationExt;
+
+use crate::backend::{BackendPhase, BackendState};
+use crate::backup::{self, BackupInfo, BackupTrigger};
+use crate::local_db::{self, DatabaseMode};
+use crate::store::{read_json, write_json};
+
+const SETTINGS_FILE_NAME: &str = "backup-settings.json";
+const STATUS_FILE_NAME: &str = "status.json";
+const TIME_FORMAT: &str = "%H:%M";
+// Wall-clock check rather than one long sleep, so a machine that slept
+// through the backup time catches up shortly after it wakes
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupSettings {
+  pub enabled: bool,
+  // Local time of day, `HH:MM`
+  pub daily_at: String,
+  pub keep_daily: usize,
+  pub keep_weekly: usize,
+  pub keep_monthly: usize,
+}
+
+impl Default for BackupSettings {
+  fn default() -> Self {
+    Self {
+      enabled: true,
+      daily_at: "03:00".to_string(),
+      keep_daily: 7,
+      keep_weekly: 4,
+      keep_monthly: 6,
+    }
+  }
+}
+
+impl BackupSettings {
+  fn time(&self) -> Result<NaiveTime, String> {
+    NaiveTime::parse_from_str(&self.daily_at, TIME_FORMAT)
+      .map_err(|_| format!("Invalid backup time {:?}, expected HH:MM", self.daily_at))
+  }
+
+  fn validate(&self) -> Result<(), String> {
+    self.time()?;
+    if self.keep_daily == 0 {
+      return Err("Keep at least one daily backup".to_string());
+    }
+    Ok(())
+  }
+}
+
+/// Persisted next to the backups so it survives restarts
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StatusFile {
+  last_backup: Option<BackupInfo>,
+  last_attempt_at: Option<DateTime<Utc>>,
+  last_trigger: Option<BackupTrigger>,
+  last_error: Option<String>,
+  last_scheduled_at: Option<DateTime<Utc>>,
+  // Version whose backend last ran against the data; a different version
+  // may migrate the schema on start
+  data_app_version: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupStatus {
+  pub enabled: bool,
+  pub database_mode: DatabaseMode,
+  pub last_backup: Option<BackupInfo>,
+  pub last_attempt_at: Option<DateTime<Utc>>,
+  pub last_trigger: Option<BackupTrigger>,
+  pub last_error: Option<String>,
+  pub next_scheduled_at: Option<DateTime<Utc>>,
+}
+
+fn settings_path(app: &AppHandle) -> Result<PathBuf, String> {
+  app
+    .path()
+    .app_config_dir()
+    .map(|dir| dir.join(SETTINGS_FILE_NAME))
+    .map_err(|e| format!("Failed to resolve config directory: {}", e))
+}
+
+fn status_path(app: &AppHandle) -> Result<PathBuf, String> {
+  Ok(backup::backup_dir(app)?.join(STATUS_FILE_NAME))
+}
+
+pub fn load_settings(app: &AppHandle) -> Result<BackupSettings, String> {
+  read_json(&settings_path(app)?)
+}
+
+fn load_status(app: &AppHandle) -> StatusFile {
+  status_path(app)
+    .and_then(|path| read_json(&path))
+    .unwrap_or_else(|e| {
+      log::warn!("{}", e);
+      StatusFile::default()
+    })
+}
+
+fn update_status(app: &AppHandle, change: impl FnOnce(&mut StatusFile)) {
+  let mut status = load_status(app);
+  change(&mut status);
+  if let Err(e) = status_path(app).and_then(|path| write_json(&path, &status)) {
+    log::warn!("Failed to save backup status: {}", e);
+  }
+}
+
+/// Remembers the outcome of a backup for `backup_status`
+pub fn record(app: &AppHandle, trigger: BackupTrigger, result: &Result<BackupInfo, String>) {
+  update_status(app, |status| {
+    status.last_attempt_at = Some(Utc::now());
+    status.last_trigger = Some(trigger);
+    match result {
+      Ok(info) => {
+        status.last_backup = Some(info.clone());
+        status.last_error = None;
+      }
+      Err(e) => status.last_error = Some(e.clone()),
+    }
+    // A failed scheduled run is not retried until tomorrow, rather than
+    // stopping the backend every minute
+    if trigger == BackupTrigger::Scheduled {
+      status.last_scheduled_at = Some(Utc::now());
+    }
+  });
+}
+
+/// Next time the daily backup should run: today's slot if it has not run
+/// since, otherwise tomorrow's. A slot in the past means it is overdue.
+fn next_run<Tz: TimeZone>(
+  settings: &BackupSettings,
+  last_run: Option<DateTime<Utc>>,
+  now: DateTime<Tz>,
+) -> Option<DateTime<Tz>> {
+  let time = settings.time().ok()?;
+  let zone = now.timezone();
+  let at = |days: u64| {
+    let local = now.date_naive().checked_add_days(Days::new(days))?.and_time(time);
+    // A repeated hour runs on its first pass, a skipped one an hour later
+    zone
+      .from_local_datetime(&local)
+      .earliest()
+      .or_else(|| zone.from_local_datetime(&(local + chrono::Duration::hours(1))).earliest())
+  };
+  let today = at(0)?;
+  match last_run {
+    Some(last) if last >= today => at(1),
+    _ => Some(today),
+  }
+}
+
+// Maps a backup time to the calendar period it falls in
+type Period = fn(&DateTime<Local>) -> (i32, u32);
+
+/// Indices of the backups to keep, given their times newest first: the
+/// newest backup of each of the last `keep_daily` days, `keep_weekly` ISO
+/// weeks and `keep_monthly` months that have one.
+fn retained(times: &[DateTime<Local>], settings: &BackupSettings) -> HashSet<usize> {
+  let periods: [(usize, Period); 3] = [
+    (settings.keep_daily, |t| (t.year(), t.ordinal())),
+    (settings.keep_weekly, |t| (t.iso_week().year(), t.iso_week().week())),
+    (settings.keep_monthly, |t| (t.year(), t.month())),
+  ];
+
+  let mut keep = HashSet::new();
+  for (limit, period) in periods {
+    let mut seen = Vec::new();
+    for (i, time) in times.iter().enumerate() {
+      if seen.len() == limit {
+        break;
+      }
+      let key = period(time);
+      if !seen.contains(&key) {
+        seen.push(key);
+        keep.insert(i);
+      }
+    }
+  }
+  keep
+}
+
+/// Deletes automatic backups that fall outside the retention policy. Manual
+/// backups are never pruned.
+fn prune(app: &AppHandle, settings: &BackupSettings) -> Result<(), String> {
+  let automatic: Vec<BackupInfo> = backup::list(app)?
+    .into_iter()
+    .filter(|b| b.trigger != BackupTrigger::Manual)
+    .collect();
+  let times: Vec<DateTime<Local>> =
+    automatic.iter().map(|b| b.created_at.with_timezone(&Local)).collect();
+  let keep = retained(&times, settings);
+
+  for (i, info) in automatic.iter().enumerate() {
+    if keep.contains(&i) {
+      continue;
+    }
+    match backup::remove(app, &info.name) {
+      Ok(()) => log::info!("Pruned backup {}", info.name),
+      Err(e) => log::warn!("{}", e),
+    }
+  }
+  Ok(())
+}
+
+/// Takes an automatic backup, records it and applies retention
+async fn run(app: &AppHandle, trigger: BackupTrigger) -> Result<BackupInfo, String> {
+  let result = backup::create(app, trigger).await;
+  record(app, trigger, &result);
+  if result.is_ok() {
+    let settings = load_settings(app).unwrap_or_default();
+    if let Err(e) = prune(app, &settings) {
+      log::warn!("Failed to prune backups: {}", e);
+    }
+  }
+  result
+}
+
+/// Called at boot before the backend starts. When this version differs from
+/// the one that last ran, the backend is about to migrate the schema, so the
+/// data is snapshotted first. A failed backup is logged but does not block
+/// startup.
+pub async fn backup_if_updated(app: &AppHandle) {
+  let version = app.package_info().version.to_string();
+  let previous = load_status(app).data_app_version;
+  if previous.as_deref() == Some(version.as_str()) {
+    return;
+  }
+
+  let has_data = local_db::data_dir(app).map(|dir| dir.exists()).unwrap_or(false);
+  if previous.is_some() && has_data && local_db::mode(app) == DatabaseMode::Embedded {
+    log::info!("Updated from {} to {}, backing up first", previous.unwrap_or_default(), version);
+    if let Err(e) = run(app, BackupTrigger::PreUpdate).await {
+      log::error!("Pre-update backup failed: {}", e);
+      return;
+    }
+  }
+  update_status(app, |status| status.data_app_version = Some(version));
+}
+
+fn window_focused(app: &AppHandle) -> bool {
+  app.get_webview_window("main").and_then(|window| window.is_focused().ok()).unwrap_or(false)
+}
+
+/// Warns that the app is about to go offline for a backup
+fn notify_stopping(app: &AppHandle) {
+  let shown = app
+    .notification()
+    .builder()
+    .title("Backing up Forge")
+    .body("Forge is unavailable for a moment while your data is backed up.")
+    .show();
+  if let Err(e) = shown {
+    log::warn!("Failed to show backup notification: {}", e);
+  }
+}
+
+fn is_due(app: &AppHandle) -> bool {
+  let Ok(settings) = load_settings(app) else {
+    return false;
+  };
+  if !settings.enabled || local_db::mode(app) != DatabaseMode::Embedded {
+    return false;
+  }
+  match app.state::<BackendState>().phase() {
+    BackendPhase::Stopped => {}
+    // Backing up stops the backend, so one in use waits until the window is
+    // in the background
+    BackendPhase::Ready if !window_focused(app) => {}
+    // Leave a backend that is still booting or recovering alone
+    _ => return false,
+  }
+  let now = Local::now();
+  next_run(&settings, load_status(app).last_scheduled_at, now).is_some_and(|at| at <= now)
+}
+
+/// Runs the daily backup for as long as the app is open
+pub fn spawn_scheduler(app: AppHandle) {
+  tauri::async_runtime::spawn(async move {
+    let mut interval = tokio::time::interval(CHECK_INTERVAL);
+    loop {
+      interval.tick().await;
+      if is_due(&app) {
+        log::info!("Starting scheduled backup");
+        if app.state::<BackendState>().phase() == BackendPhase::Ready {
+          notify_stopping(&app);
+        }
+        if let Err(e) = run(&app, BackupTrigger::Scheduled).await {
+          log::error!("Scheduled backup failed: {}", e);
+        }
+      }
+    }
+  });
+}
+
+#[tauri::command]
+pub fn get_backup_settings(app: AppHandle) -> Result<BackupSettings, String> {
+  load_settings(&app)
+}
+
+#[tauri::command]
+pub fn save_backup_settings(app: AppHandle, settings: BackupSettings) -> Result<(), String> {
+  settings.validate()?;
+  write_json(&settings_path(&app)?, &settings)
+}
+
+#[tauri::command]
+pub fn backup_status(app: AppHandle) -> BackupStatus {
+  let settings = load_settings(&app).unwrap_or_default();
+  let status = load_status(&app);
+  let database_mode = local_db::mode(&app);
+  let scheduled = settings.enabled && database_mode == DatabaseMode::Embedded;
+  BackupStatus {
+    enabled: settings.enabled,
+    database_mode,
+    last_backup: status.last_backup,
+    last_attempt_at: status.last_attempt_at,
+    last_trigger: status.last_trigger,
+    last_error: status.last_error,
+    next_scheduled_at: scheduled
+      .then(|| next_run(&settings, status.last_scheduled_at, Local::now()))
+      .flatten()
+      .map(|at| at.with_timezone(&Utc)),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test_util::{eastern, offset};
+
+  fn settings(daily_at: &str) -> BackupSettings {
+    BackupSettings { daily_at: daily_at.to_string(), ..BackupSettings::default() }
+  }
+
+  fn local(y: i32, mo: u32, d: u32, h: u32) -> DateTime<Local> {
+    // Daytime hours never fall in a DST change
+    Local.with_ymd_and_hms(y, mo, d, h, 0, 0).earliest().unwrap()
+  }
+
+  fn keep(daily: usize, weekly: usize, monthly: usize) -> BackupSettings {
+    BackupSettings {
+      keep_daily: daily,
+      keep_weekly: weekly,
+      keep_monthly: monthly,
+      ..BackupSettings::default()
+    }
+  }
+
+  fn sorted(set: HashSet<usize>) -> Vec<usize> {
+    let mut v: Vec<usize> = set.into_iter().collect();
+    v.sort();
+    v
+  }
+
+  #[test]
+  fn next_run_is_today_before_the_slot() {
+    let now = eastern(2026, 6, 10, 1, 0);
+    let at = next_run(&settings("03:00"), None, now).unwrap();
+    assert_eq!(at, eastern(2026, 6, 10, 3, 0));
+  }
+
+  #[test]
+  fn next_run_catches_up_an_overdue_slot() {
+    let now = eastern(2026, 6, 10, 14, 0);
+    let yesterday = eastern(2026, 6, 9, 3, 0).with_timezone(&Utc);
+    let at = next_run(&settings("03:00"), Some(yesterday), now).unwrap();
+    assert_eq!(at, eastern(2026, 6, 10, 3, 0));
+    assert!(at <= now);
+  }
+
+  #[test]
+  fn next_run_moves_to_tomorrow_once_done() {
+    let now = eastern(2026, 6, 10, 14, 0);
+    let ran = eastern(2026, 6, 10, 3, 5).with_timezone(&Utc);
+    let at = next_run(&settings("03:00"), Some(ran), now).unwrap();
+    assert_eq!(at, eastern(2026, 6, 11, 3, 0));
+  }
+
+  #[test]
+  fn next_run_rejects_a_bad_time() {
+    assert!(next_run(&settings("3am"), None, eastern(2026, 6, 10, 1, 0)).is_none());
+  }
+
+  #[test]
+  fn next_run_shifts_a_skipped_slot_by_an_hour() {
+    // 02:30 does not exist on 8 March 2026
+    let at = next_run(&settings("02:30"), None, eastern(2026, 3, 8, 0, 0)).unwrap();
+    assert_eq!(at.naive_local().time(), NaiveTime::from_hms_opt(3, 30, 0).unwrap());
+    assert_eq!(*at.offset(), offset(4));
+  }
+
+  #[test]
+  fn next_run_takes_the_first_of_a_repeated_slot() {
+    // 01:30 happens twice on 1 November 2026
+    let at = next_run(&settings("01:30"), None, eastern(2026, 11, 1, 0, 0)).unwrap();
+    assert_eq!(at.naive_local().time(), NaiveTime::from_hms_opt(1, 30, 0).unwrap());
+    assert_eq!(*at.offset(), offset(4));
+  }
+
+  #[test]
+  fn retained_keeps_the_newest_backup_of_each_day() {
+    let times =
+      [local(2026, 6, 3, 18), local(2026, 6, 3, 9), local(2026, 6, 2, 12), local(2026, 6, 1, 12)];
+    assert_eq!(sorted(retained(&times, &keep(2, 0, 0))), vec![0, 2]);
+  }
+
+  #[test]
+  fn retained_groups_weeks_across_the_iso_year_boundary() {
+    // 28 December 2026 to 3 January 2027 is week 53 of 2026
+    let times = [
+      local(2027, 1, 2, 12),
+      local(2026, 12, 31, 12),
+      local(2026, 12, 28, 12),
+      local(2026, 12, 27, 12),
+      local(2026, 12, 21, 12),
+    ];
+    assert_eq!(sorted(retained(&times, &keep(0, 2, 0))), vec![0, 3]);
+  }
+
+  #[test]
+  fn retained_keeps_the_newest_backup_of_each_month() {
+    let times =
+      [local(2026, 3, 1, 12), local(2026, 2, 28, 12), local(2026, 2, 1, 12), local(2026, 1, 31, 12)];
+    assert_eq!(sorted(retained(&times, &keep(0, 0, 2))), vec![0, 1]);
+  }
+
+  #[test]
+  fn retained_combines_the_buckets() {
+    let times = [
+      local(2026, 6, 10, 12),
+      local(2026, 6, 9, 12),
+      local(2026, 6, 2, 12),
+      local(2026, 5, 20, 12),
+      local(2026, 4, 15, 12),
+    ];
+    // Days keep 0 and 1, weeks add 2, months add 3 and 4
+    assert_eq!(sorted(retained(&times, &keep(2, 2, 3))), vec![0, 1, 2, 3, 4]);
+    assert_eq!(sorted(retained(&times, &keep(1, 1, 1))), vec![0]);
+  }
+}
@@ -0,0 +1,503 @@
+use serde::{Deserialize, Serialize};
+
+use crate::extract::PageText;
+
+// Same defaults as the text splitter in server/llm/ingestPipeline.ts
+pub const DEFAULT_CHUNK_SIZE: usize = 900;
+pub const DEFAULT_CHUNK_OVERLAP: usize = 200;
+
+// Words that end in a period without ending the sentence
+const ABBREVIATIONS: &[&str] = &[
+  "e.g", "i.e", "etc", "vs", "cf", "al", "fig", "figs", "eq", "eqs", "no", "vol", "pp", "p",
+  "ch", "sec", "approx", "dr", "mr", "mrs", "ms", "prof", "st",
+];
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ChunkOptions {
+  // Both in characters
+  pub chunk_size: usize,
+  pub chunk_overlap: usize,
+}
+
+impl Default for ChunkOptions {
+  fn default() -> Self {
+    Self { chunk_size: DEFAULT_CHUNK_SIZE, chunk_overlap: DEFAULT_CHUNK_OVERLAP }
+  }
+}
+
+impl ChunkOptions {
+  fn validate(&self) -> Result<(), String> {
+    if self.chunk_size == 0 {
+      return Err("chunkSize must be greater than zero".to_string());
+    }
+    if self.chunk_overlap >= self.chunk_size {
+      return Err("chunkOverlap must be smaller than chunkSize".to_string());
+    }
+    Ok(())
+  }
+}
+
+/// Matches `ChunkMetadata` in server/llm/retriever.ts
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChunkMetadata {
+  pub file: String,
+  pub chunk_index: usize,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub page: Option<u32>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Chunk {
+  pub text: String,
+  pub metadata: ChunkMetadata,
+}
+
+/// A sentence, heading or piece of an overlong sentence: the smallest span
+/// a chunk boundary never falls inside
+struct Unit {
+  text: String,
+  len: usize,
+  page: Option<u32>,
+  heading: bool,
+  // Joined to the previous unit with a blank line rather than a space
+  paragraph_start: bool,
+}
+
+impl Unit {
+  fn separator(&self) -> &'static str {
+    if self.paragraph_start {
+      "\n\n"
+    } else {
+      " "
+    }
+  }
+}
+
+/// Markdown headings and the usual textbook forms: "Chapter 3",
+/// "2.1 Kinematics", "INTRODUCTION"
+fn is_heading(line: &str) -> bool {
+  if line.starts_with('#') {
+    return true;
+  }
+  let words: Vec<&str> = line.split_whitespace().collect();
+  if words.is_empty() || words.len() > 10 || line.ends_with(['.', ',', ';', ':']) {
+    return false;
+  }
+  let first = words[0].to_lowercase();
+  // "2.1" but not the "1." of a numbered list
+  let numbered = words.len() > 1
+    && first.split('.').all(|n| !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit()));
+  let shouted = line.chars().any(char::is_alphabetic)
+    && !line.chars().any(char::is_lowercase)
+    && line.chars().count() <= 60;
+  matches!(first.as_str(), "chapter" | "section" | "part" | "unit") || numbered || shouted
+}
+
+fn ends_sentence(text: &str) -> bool {
+  let last_word = text
+    .rsplit(char::is_whitespace)
+    .next()
+    .unwrap_or_default()
+    .trim_start_matches(['(', '[', '"', '\''])
+    .trim_end_matches('.')
+    .to_lowercase();
+  // Initials ("J. Smith") and abbreviations
+  last_word.chars().count() > 1 && !ABBREVIATIONS.contains(&last_word.as_str())
+}
+
+/// Splits a paragraph after `.`, `!` or `?` (and any closing quotes or
+/// brackets) followed by whitespace, except after abbreviations or before
+/// a lowercase letter
+fn sentences(paragraph: &str) -> Vec<&str> {
+  let chars: Vec<(usize, char)> = paragraph.char_indices().collect();
+  let mut out = Vec::new();
+  let mut start = 0;
+  for (i, &(_, c)) in chars.iter().enumerate() {
+    if !matches!(c, '.' | '!' | '?') {
+      continue;
+    }
+    let mut j = i + 1;
+    while j < chars.len() && matches!(chars[j].1, '"' | '\'' | ')' | ']' | '”' | '’') {
+      j += 1;
+    }
+    if j >= chars.len() || !chars[j].1.is_whitespace() {
+      continue;
+    }
+    let next = chars[j..].iter().map(|&(_, c)| c).find(|c| !c.is_whitespace());
+    if next.is_some_and(char::is_lowercase) {
+      continue;
+    }
+    let end = chars[j].0;
+    let sentence = paragraph[start..end].trim();
+    if c != '.' || ends_sentence(sentence) {
+      if !sentence.is_empty() {
+        out.push(sentence);
+      }
+      start = end;
+    }
+  }
+  let rest = paragraph[start..].trim();
+  if !rest.is_empty() {
+    out.push(rest);
+  }
+  out
+}
+
+/// Breaks text longer than `max` characters at whitespace, or mid-word when
+/// a single word is longer than `max`
+fn split_long(text: &str, max: usize) -> Vec<String> {
+  let mut pieces = Vec::new();
+  let mut current = String::new();
+  let mut len = 0;
+  for word in text.split_whitespace() {
+    let mut word_len = word.chars().count();
+    if len > 0 && len + 1 + word_len > max {
+      pieces.push(std::mem::take(&mut current));
+      len = 0;
+    }
+    let mut word = word;
+    while word_len > max {
+      let split = word.char_indices().nth(max).map_or(word.len(), |(i, _)| i);
+      pieces.push(word[..split].to_string());
+      word = &word[split..];
+      word_len -= max;
+    }
+    if word.is_empty() {
+      continue;
+    }
+    if len > 0 {
+      current.push(' ');
+      len += 1;
+    }
+    current.push_str(word);
+    len += word_len;
+  }
+  if !current.is_empty() {
+    pieces.push(current);
+  }
+  pieces
+}
+
+fn push_unit(
+  units: &mut Vec<Unit>,
+  text: &str,
+  page: Option<u32>,
+  heading: bool,
+  paragraph_start: bool,
+  max: usize,
+) {
+  for (i, piece) in split_long(text, max).into_iter().enumerate() {
+    units.push(Unit {
+      len: piece.chars().count(),
+      text: piece,
+      page,
+      heading: heading && i == 0,
+      paragraph_start: paragraph_start && i == 0,
+    });
+  }
+}
+
+fn push_paragraph(units: &mut Vec<Unit>, lines: &mut Vec<&str>, page: Option<u32>, max: usize) {
+  let paragraph = lines.join("\n");
+  for (i, sentence) in sentences(&paragraph).into_iter().enumerate() {
+    push_unit(units, sentence, page, false, i == 0, max);
+  }
+  lines.clear();
+}
+
+fn units(pages: &[(Option<u32>, &str)], max: usize) -> Vec<Unit> {
+  let mut units = Vec::new();
+  for &(page, text) in pages {
+    let mut lines = Vec::new();
+    for line in text.lines().map(str::trim) {
+      if line.is_empty() {
+        push_paragraph(&mut units, &mut lines, page, max);
+      } else if is_heading(line) {
+        push_paragraph(&mut units, &mut lines, page, max);
+        push_unit(&mut units, line, page, true, true, max);
+      } else {
+        lines.push(line);
+      }
+    }
+    push_paragraph(&mut units, &mut lines, page, max);
+  }
+  units
+}
+
+fn joined_len(units: &[&Unit]) -> usize {
+  units
+    .iter()
+    .enumerate()
+    .map(|(i, u)| u.len + if i == 0 { 0 } else { u.separator().len() })
+    .sum()
+}
+
+/// Splits pages of text into chunks of at most `chunk_size` characters,
+/// breaking only between sentences and starting a fresh chunk at each
+/// heading. Consecutive chunks within a section repeat up to
+/// `chunk_overlap` characters of trailing sentences. Each chunk carries the
+/// page its new content starts on. The same input always gives the same
+/// chunks.
+pub fn chunk_pages(
+  file: &str,
+  pages: &[(Option<u32>, &str)],
+  options: ChunkOptions,
+) -> Vec<Chunk> {
+  let size = options.chunk_size.max(1);
+  let overlap = options.chunk_overlap.min(size - 1);
+  let units = units(pages, size);
+
+  let mut chunks = Vec::new();
+  let mut current: Vec<&Unit> = Vec::new();
+  // Units in `current` that are not carried over from the previous chunk
+  let mut fresh = 0;
+  let mut flush = |current: &[&Unit], fresh: usize| {
+    let text = current
+      .iter()
+      .enumerate()
+      .fold(String::new(), |mut text, (i, unit)| {
+        if i > 0 {
+          text.push_str(unit.separator());
+        }
+        text.push_str(&unit.text);
+        text
+      });
+    chunks.push(Chunk {
+      text,
+      metadata: ChunkMetadata {
+        file: file.to_string(),
+        chunk_index: chunks.len(),
+        page: current[current.len() - fresh].page,
+      },
+    });
+  };
+
+  for unit in &units {
+    let new_section = unit.heading && fresh > 0;
+    let too_long = fresh > 0 && joined_len(&current) + unit.separator().len() + unit.len > size;
+    if new_section || too_long {
+      flush(&current, fresh);
+      let mut carried: Vec<&Unit> = Vec::new();
+      if !new_section {
+        let room = size.saturating_sub(unit.len + unit.separator().len());
+        for &prev in current.iter().rev() {
+          let mut candidate = vec![prev];
+          candidate.extend(&carried);
+          let len = joined_len(&candidate);
+          if len > overlap || len > room {
+            break;
+          }
+          carried = candidate;
+        }
+      }
+      current = carried;
+      fresh = 0;
+    }
+    current.push(unit);
+    fresh += 1;
+  }
+  if fresh > 0 {
+    flush(&current, fresh);
+  }
+  chunks
+}
+
+/// Text to chunk, from `chunk_document` or the loopback server's `/chunks`
+/// route. Pass `pages` from `extract_text` to keep page numbers, or plain
+/// `text` for sources without them.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChunkRequest {
+  pub file: String,
+  pub pages: Option<Vec<PageText>>,
+  pub text: Option<String>,
+  pub options: Option<ChunkOptions>,
+}
+
+/// Runs off the async runtime since textbooks can be large
+pub async fn chunk(request: ChunkRequest) -> Result<Vec<Chunk>, String> {
+  let ChunkRequest { file, pages, text, options } = request;
+  let options = options.unwrap_or_default();
+  options.validate()?;
+  tauri::async_runtime::spawn_blocking(move || {
+    let pages: Vec<(Option<u32>, &str)> = match (&pages, &text) {
+      (Some(pages), _) => pages.iter().map(|p| (Some(p.page), p.text.as_str())).collect(),
+      (None, Some(text)) => vec![(None, text.as_str())],
+      (None, None) => return Err("Either pages or text is required".to_string()),
+    };
+    Ok(chunk_pages(&file, &pages, options))
+  })
+  .await
+  .map_err(|e| format!("Chunking failed: {}", e))?
+}
+
+/// Chunks extracted text for the knowledge base
+#[tauri::command]
+pub async fn chunk_document(
+  file: String,
+  pages: Option<Vec<PageText>>,
+  text: Option<String>,
+  options: Option<ChunkOptions>,
+) -> Result<Vec<Chunk>, String> {
+  chunk(ChunkRequest { file, pages, text, options }).await
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn options(chunk_size: usize, chunk_overlap: usize) -> ChunkOptions {
+    ChunkOptions { chunk_size, chunk_overlap }
+  }
+
+  fn texts(chunks: &[Chunk]) -> Vec<&str> {
+    chunks.iter().map(|c| c.text.as_str()).collect()
+  }
+
+  #[test]
+  fn splits_sentences_on_terminal_punctuation() {
+    assert_eq!(
+      sentences("It moves. Does it stop? It does!  Then it rests."),
+      vec!["It moves.", "Does it stop?", "It does!", "Then it rests."]
+    );
+  }
+
+  #[test]
+  fn keeps_abbreviations_and_initials_inside_sentences() {
+    assert_eq!(
+      sentences("Forces add, e.g. Gravity and drag. Dr. Smith and J. Doe agree. See Fig. 2 now."),
+      vec!["Forces add, e.g. Gravity and drag.", "Dr. Smith and J. Doe agree.", "See Fig. 2 now."]
+    );
+  }
+
+  #[test]
+  fn splits_after_closing_quotes_but_not_before_lowercase() {
+    assert_eq!(
+      sentences("He said \"stop.\" Then he left. The value is 3. and so on."),
+      vec!["He said \"stop.\"", "Then he left.", "The value is 3. and so on."]
+    );
+  }
+
+  #[test]
+  fn recognises_headings() {
+    assert!(is_heading("# Kinematics"));
+    assert!(is_heading("Chapter 3"));
+    assert!(is_heading("2.1 Projectile Motion"));
+    assert!(is_heading("INTRODUCTION"));
+    assert!(!is_heading("1. Measure the angle"));
+    assert!(!is_heading("A plain sentence that ends."));
+  }
+
+  #[test]
+  fn starts_a_new_chunk_at_each_heading_without_overlap() {
+    let text = "Motion is change. It has a cause.\n\n## Forces\nA push or a pull. Newton said so.";
+    let chunks = chunk_pages("notes.md", &[(None, text)], options(900, 200));
+    assert_eq!(
+      texts(&chunks),
+      vec!["Motion is change. It has a cause.", "## Forces\n\nA push or a pull. Newton said so."]
+    );
+    assert_eq!(chunks[1].metadata.chunk_index, 1);
+    assert_eq!(chunks[1].metadata.file, "notes.md");
+  }
+
+  #[test]
+  fn repeats_trailing_sentences_as_overlap() {
+    let text = "First sentence is here. Second sentence is here. Third sentence is here. \
+                Fourth sentence is here.";
+    let chunks = chunk_pages("a.txt", &[(None, text)], options(60, 30));
+    assert_eq!(
+      texts(&chunks),
+      vec![
+        "First sentence is here. Second sentence is here.",
+        "Second sentence is here. Third sentence is here.",
+        "Third sentence is here. Fourth sentence is here.",
+      ]
+    );
+    assert!(chunks.iter().all(|c| c.text.chars().count() <= 60));
+  }
+
+  #[test]
+  fn leaves_out_overlap_that_would_not_fit() {
+    let text = "First sentence is here. Second sentence is here.";
+    let chunks = chunk_pages("a.txt", &[(None, text)], options(30, 10));
+    assert_eq!(texts(&chunks), vec!["First sentence is here.", "Second sentence is here."]);
+  }
+
+  #[test]
+  fn attributes_chunks_to_the_page_their_new_content_starts_on() {
+    let pages = [
+      (Some(1), "Page one opens here. Page one closes here."),
+      (Some(2), "Page two opens here. Page two closes here."),
+    ];
+    let chunks = chunk_pages("book.pdf", &pages, options(50, 25));
+    let pages: Vec<Option<u32>> = chunks.iter().map(|c| c.metadata.page).collect();
+    assert_eq!(
+      texts(&chunks),
+      vec![
+        "Page one opens here. Page one closes here.",
+        "Page one closes here.\n\nPage two opens here.",
+        "Page two opens here. Page two closes here.",
+      ]
+    );
+    // The second chunk carries page one's last sentence but is new from page two
+    assert_eq!(pages, vec![Some(1), Some(2), Some(2)]);
+  }
+
+  #[test]
+  fn splits_long_text_at_whitespace_and_long_words_mid_word() {
+    assert_eq!(split_long("aaaa bbbb cccc", 9), vec!["aaaa bbbb", "cccc"]);
+    assert_eq!(split_long("abcdefghij", 4), vec!["abcd", "efgh", "ij"]);
+    assert_eq!(split_long("ab abcdefgh", 4), vec!["ab", "abcd", "efgh"]);
+    assert_eq!(split_long("é".repeat(5).as_str(), 2), vec!["éé", "éé", "é"]);
+  }
+
+  #[test]
+  fn breaks_an_oversized_sentence_across_chunks() {
+    let sentence = "word ".repeat(50);
+    let chunks = chunk_pages("a.txt", &[(None, sentence.as_str())], options(40, 0));
+    assert!(chunks.len() > 1);
+    assert!(chunks.iter().all(|c| c.text.chars().count() <= 40));
+    let rejoined: Vec<&str> = chunks.iter().flat_map(|c| c.text.split_whitespace()).collect();
+    assert_eq!(rejoined.len(), 50);
+  }
+
+  #[test]
+  fn breaks_between_sentences_and_overlaps_within_the_limit() {
+    let text = "Velocity is the rate of change of position. It has a direction. \
+                Speed is its magnitude. Acceleration changes velocity. \
+                A constant force gives constant acceleration. Mass resists it. \
+                Momentum is mass times velocity. It is conserved in collisions.";
+    let all = sentences(text);
+    let chunks = chunk_pages("a.txt", &[(None, text)], options(100, 45));
+    assert!(chunks.len() > 2);
+
+    // Index into `all` just past the last sentence the previous chunk held
+    let mut next = 0;
+    for (i, chunk) in chunks.iter().enumerate() {
+      assert!(chunk.text.chars().count() <= 100, "{:?}", chunk.text);
+      // Every chunk is a run of whole sentences from the source
+      let held = sentences(&chunk.text);
+      let start = all.iter().position(|s| *s == held[0]).unwrap();
+      assert_eq!(&all[start..start + held.len()], &held[..]);
+      if i > 0 {
+        // Repeats some of the previous chunk, but no more than the overlap
+        assert!(start < next, "chunk {} does not overlap", i);
+        assert!(all[start..next].join(" ").chars().count() <= 45);
+      }
+      assert!(start + held.len() > next, "chunk {} adds nothing", i);
+      next = start + held.len();
+    }
+    assert_eq!(next, all.len());
+  }
+
+  #[test]
+  fn validates_options() {
+    assert!(ChunkOptions::default().validate().is_ok());
+    assert!(options(100, 99).validate().is_ok());
+    assert!(options(100, 100).validate().is_err());
+    assert!(options(100, 150).validate().is_err());
+    assert!(options(0, 0).validate().is_err());
+  }
+}
@@ -0,0 +1,279 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use chrono::{DateTime, Days, Local, NaiveDate, NaiveTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+
+use crate::backend::{self, BackendPhase, BackendState};
+use crate::store::{read_json, write_json};
+
+const RUNS_FILE_NAME: &str = "schedule-runs.json";
+const USERS_PATH: &str = "/api/internal/schedule-generation";
+const DATE_FORMAT: &str = "%Y-%m-%d";
+const TIME_FORMAT: &str = "%H:%M";
+// Wall-clock check rather than one long sleep, so a machine that slept
+// through a generation time catches up shortly after it wakes
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+// Failed generations call the LLM, so they are not retried every minute
+const RETRY_AFTER: chrono::Duration = chrono::Duration::minutes(15);
+// Outcomes after which the day counts as handled, matching the old
+// in-process scheduler
+const DONE_REASONS: &[&str] = &["generated", "schedule_exists", "no_api_key"];
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GenerationUser {
+  user_id: i64,
+  // `HH:MM` local time
+  generation_time: String,
+}
+
+#[derive(Deserialize)]
+struct GenerationResult {
+  reason: String,
+}
+
+/// Persisted per user so a restart never repeats or skips a day
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RunRecord {
+  // Local day that has been handled
+  last_run_date: Option<NaiveDate>,
+  last_attempt_at: Option<DateTime<Utc>>,
+  last_result: Option<String>,
+}
+
+type Runs = BTreeMap<i64, RunRecord>;
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TriggerStatus {
+  pub user_id: i64,
+  pub generation_time: String,
+  pub last_run_date: Option<NaiveDate>,
+  pub last_attempt_at: Option<DateTime<Utc>>,
+  pub last_result: Option<String>,
+  pub next_run_at: Option<DateTime<Local>>,
+}
+
+fn runs_path(app: &AppHandle) -> Result<PathBuf, String> {
+  app
+    .path()
+    .app_data_dir()
+    .map(|dir| dir.join(RUNS_FILE_NAME))
+    .map_err(|e| format!("Could not determine the data directory: {}", e))
+}
+
+fn load_runs(app: &AppHandle) -> Runs {
+  runs_path(app).and_then(|path| read_json(&path)).unwrap_or_else(|e| {
+    log::warn!("{}", e);
+    Runs::new()
+  })
+}
+
+fn save_runs(app: &AppHandle, runs: &Runs) {
+  if let Err(e) = runs_path(app).and_then(|path| write_json(&path, runs)) {
+    log::warn!("Failed to save schedule runs: {}", e);
+  }
+}
+
+/// When the user's schedule should next be generated. A time in the past
+/// means it is due now, including after the machine slept through it.
+fn next_run<Tz: TimeZone>(
+  generation_time: &str,
+  record: Option<&RunRecord>,
+  now: DateTime<Tz>,
+) -> Option<DateTime<Tz>> {
+  let time = NaiveTime::parse_from_str(generation_time.trim(), TIME_FORMAT).ok()?;
+  let zone = now.timezone();
+  let today = now.date_naive();
+  let handled_today = record.and_then(|r| r.last_run_date).is_some_and(|date| date >= today);
+  let date = if handled_today { today.checked_add_days(Days::new(1))? } else { today };
+  // A repeated time runs on its first pass and one skipped by the clocks
+  // going forward an hour later, rather than skipping the day
+  let local = date.and_time(time);
+  let at = zone
+    .from_local_datetime(&local)
+    .earliest()
+    .or_else(|| zone.from_local_datetime(&(local + chrono::Duration::hours(1))).earliest())?;
+  // Back off after a failed attempt today
+  let retry = record
+    .and_then(|r| r.last_attempt_at)
+    .map(|at| at.with_timezone(&zone) + RETRY_AFTER)
+    .filter(|retry| !handled_today && *retry > at);
+  Some(retry.unwrap_or(at))
+}
+
+async fn fetch_users(
+  app: &AppHandle,
+  client: &reqwest::Client,
+) -> Result<Vec<GenerationUser>, String> {
+  let res = backend::internal_request(app, client, reqwest::Method::GET, USERS_PATH)
+    .send()
+    .await
+    .and_then(|res| res.error_for_status())
+    .map_err(|e| format!("Could not list schedule generation times: {}", e))?;
+  res.json().await.map_err(|e| format!("Unexpected response: {}", e))
+}
+
+async fn generate(
+  app: &AppHandle,
+  client: &reqwest::Client,
+  user_id: i64,
+  date: NaiveDate,
+) -> Result<String, String> {
+  let path = format!("{}/{}", USERS_PATH, user_id);
+  let res = backend::internal_request(app, client, reqwest::Method::POST, &path)
+    .json(&serde_json::json!({ "date": date.format(DATE_FORMAT).to_string() }))
+    // Generation waits on the LLM
+    .timeout(Duration::from_secs(300))
+    .send()
+    .await
+    .and_then(|res| res.error_for_status())
+    .map_err(|e| format!("error: {}", e))?;
+  let result: GenerationResult = res.json().await.map_err(|e| format!("error: {}", e))?;
+  Ok(result.reason)
+}
+
+/// Generates today's schedule for every user whose generation time has
+/// passed and who has not been handled today
+async fn run_due(app: &AppHandle, client: &reqwest::Client) -> Result<(), String> {
+  let users = fetch_users(app, client).await?;
+  let mut runs = load_runs(app);
+  for user in users {
+    let now = Local::now();
+    let due = next_run(&user.generation_time, runs.get(&user.user_id), now);
+    if due.is_none_or(|at| at > now) {
+      continue;
+    }
+
+    log::info!("Generating today's schedule for user {}", user.user_id);
+    let reason =
+      generate(app, client, user.user_id, now.date_naive()).await.unwrap_or_else(|e| e);
+    log::info!("Schedule generation for user {}: {}", user.user_id, reason);
+    let record = runs.entry(user.user_id).or_default();
+    record.last_attempt_at = Some(Utc::now());
+    if DONE_REASONS.contains(&reason.as_str()) {
+      record.last_run_date = Some(now.date_naive());
+    }
+    record.last_result = Some(reason);
+    save_runs(app, &runs);
+  }
+  Ok(())
+}
+
+/// Triggers daily schedule generation for as long as the app is open. Only
+/// talks to a backend that is up; one that was down or paused is caught up
+/// as soon as it is ready again.
+pub fn spawn_trigger(app: AppHandle) {
+  tauri::async_runtime::spawn(async move {
+    let client = reqwest::Client::new();
+    loop {
+      if app.state::<BackendState>().phase() == BackendPhase::Ready {
+        if let Err(e) = run_due(&app, &client).await {
+          log::warn!("{}", e);
+        }
+      }
+      tokio::time::sleep(CHECK_INTERVAL).await;
+    }
+  });
+}
+
+#[tauri::command]
+pub async fn schedule_trigger_status(app: AppHandle) -> Result<Vec<TriggerStatus>, String> {
+  let users = fetch_users(&app, &reqwest::Client::new()).await?;
+  let runs = load_runs(&app);
+  let now = Local::now();
+  Ok(
+    users
+      .into_iter()
+      .map(|user| {
+        let record = runs.get(&user.user_id);
+        TriggerStatus {
+          user_id: user.user_id,
+          next_run_at: next_run(&user.generation_time, record, now),
+          generation_time: user.generation_time,
+          last_run_date: record.and_then(|r| r.last_run_date),
+          last_attempt_at: record.and_then(|r| r.last_attempt_at),
+          last_result: record.and_then(|r| r.last_result.clone()),
+        }
+      })
+      .collect(),
+  )
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test_util::{eastern, eastern_utc, offset};
+
+  fn record(last_run_date: Option<NaiveDate>, last_attempt_at: Option<DateTime<Utc>>) -> RunRecord {
+    RunRecord { last_run_date, last_attempt_at, last_result: None }
+  }
+
+  fn date(y: i32, mo: u32, d: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(y, mo, d).unwrap()
+  }
+
+  #[test]
+  fn next_run_is_today_before_the_time() {
+    let now = eastern(2026, 6, 10, 5, 0);
+    assert_eq!(next_run("06:30", None, now), Some(eastern(2026, 6, 10, 6, 30)));
+    // Including for a user who was handled yesterday
+    let yesterday = record(Some(date(2026, 6, 9)), None);
+    assert_eq!(next_run("06:30", Some(&yesterday), now), Some(eastern(2026, 6, 10, 6, 30)));
+  }
+
+  #[test]
+  fn next_run_is_due_once_the_time_has_passed() {
+    let now = eastern(2026, 6, 10, 14, 0);
+    let at = next_run(" 06:30 ", None, now).unwrap();
+    assert_eq!(at, eastern(2026, 6, 10, 6, 30));
+    assert!(at <= now);
+  }
+
+  #[test]
+  fn next_run_moves_to_tomorrow_once_handled() {
+    let now = eastern(2026, 6, 10, 14, 0);
+    let handled = record(Some(date(2026, 6, 10)), Some(eastern_utc(2026, 6, 10, 6, 31)));
+    assert_eq!(next_run("06:30", Some(&handled), now), Some(eastern(2026, 6, 11, 6, 30)));
+  }
+
+  #[test]
+  fn next_run_backs_off_after_a_failure() {
+    let failed = record(None, Some(eastern_utc(2026, 6, 10, 6, 31)));
+    let now = eastern(2026, 6, 10, 6, 40);
+    assert_eq!(next_run("06:30", Some(&failed), now), Some(eastern(2026, 6, 10, 6, 46)));
+
+    // A failure yesterday does not hold up today
+    let yesterday = record(Some(date(2026, 6, 8)), Some(eastern_utc(2026, 6, 9, 23, 0)));
+    let now = eastern(2026, 6, 10, 14, 0);
+    assert_eq!(next_run("06:30", Some(&yesterday), now), Some(eastern(2026, 6, 10, 6, 30)));
+  }
+
+  #[test]
+  fn next_run_shifts_a_skipped_time_by_an_hour() {
+    // 02:30 does not exist on 8 March 2026
+    let at = next_run("02:30", None, eastern(2026, 3, 8, 0, 0)).unwrap();
+    assert_eq!(at.naive_local().time(), NaiveTime::from_hms_opt(3, 30, 0).unwrap());
+    assert_eq!(*at.offset(), offset(4));
+  }
+
+  #[test]
+  fn next_run_takes_the_first_of_a_repeated_time() {
+    // 01:30 happens twice on 1 November 2026
+    let at = next_run("01:30", None, eastern(2026, 11, 1, 0, 0)).unwrap();
+    assert_eq!(at.naive_local().time(), NaiveTime::from_hms_opt(1, 30, 0).unwrap());
+    assert_eq!(*at.offset(), offset(4));
+  }
+
+  #[test]
+  fn next_run_rejects_an_invalid_time() {
+    let now = eastern(2026, 6, 10, 5, 0);
+    for time in ["", "6am", "25:00", "06:30:00"] {
+      assert!(next_run(time, None, now).is_none(), "{:?}", time);
+    }
+  }
+}
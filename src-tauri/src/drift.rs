@@ -0,0 +1,812 @@
+use std::cmp::Reverse;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+const MINUTES_PER_DAY: i64 = 24 * 60;
+// Blocks tied to someone else's timetable, which a reschedule never moves
+const FIXED_TYPES: &[&str] = &["class", "exam"];
+// Kept even when they are the least important thing left in the day
+const UNDROPPABLE_TYPES: &[&str] = &["break"];
+// Priorities run from 1, the most important, to 3, as the planner emits them
+const TOP_PRIORITY: u8 = 1;
+const DEFAULT_PRIORITY: u8 = 2;
+
+/// A time block as stored in a daily schedule's `scheduleData`
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduleBlock {
+  pub start_time: String,
+  pub end_time: String,
+  pub title: String,
+  #[serde(rename = "type", default)]
+  pub kind: String,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub priority: Option<u8>,
+  // Description, course code and the rest, carried through untouched
+  #[serde(flatten)]
+  pub extra: Map<String, Value>,
+}
+
+/// How long a block really took
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockActual {
+  // Planned `HH:MM` start, which identifies the block
+  pub start_time: String,
+  // `HH:MM` it really started, when that was not on time
+  #[serde(default)]
+  pub actual_start: Option<String>,
+  pub actual_duration: i64,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct DriftOptions {
+  // Running later than this is worth telling the user about
+  pub threshold_minutes: i64,
+  // Compression never shortens a block below this
+  pub min_block_minutes: i64,
+  // `HH:MM` nothing may run past; defaults to the end of the last block
+  pub day_end: Option<String>,
+}
+
+impl Default for DriftOptions {
+  fn default() -> Self {
+    Self { threshold_minutes: 10, min_block_minutes: 15, day_end: None }
+  }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DriftInput {
+  pub blocks: Vec<ScheduleBlock>,
+  pub actuals: Vec<BlockActual>,
+  #[serde(default)]
+  pub options: DriftOptions,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockDrift {
+  pub start_time: String,
+  pub title: String,
+  pub planned_duration: i64,
+  pub actual_duration: i64,
+  // Actual minus planned duration
+  pub overrun_minutes: i64,
+  // How much later than planned the block ended, late starts included
+  pub drift_minutes: i64,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockRef {
+  pub start_time: String,
+  pub title: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockChange {
+  pub start_time: String,
+  pub end_time: String,
+  pub title: String,
+  pub new_start_time: String,
+  pub new_end_time: String,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Strategy {
+  // Every later block keeps its length and moves back as far as needed
+  Push,
+  // Later blocks are shortened, least important first
+  Compress,
+  // Low-priority blocks are left out, least important first
+  DropLowPriority,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Reschedule {
+  pub strategy: Strategy,
+  // The whole day with this change applied, ready to save as the schedule
+  pub blocks: Vec<ScheduleBlock>,
+  pub changes: Vec<BlockChange>,
+  pub dropped: Vec<BlockRef>,
+  // Fixed blocks the plan still runs into
+  pub conflicts: Vec<BlockRef>,
+  // How far the day runs past its end
+  pub overflow_minutes: i64,
+  pub feasible: bool,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DriftReport {
+  pub block_drifts: Vec<BlockDrift>,
+  // How far behind plan the day is after the last timed block; negative
+  // when ahead
+  pub drift_minutes: i64,
+  pub significant: bool,
+  // Blocks that move if the rest of the day is simply pushed back
+  pub affected_blocks: Vec<BlockChange>,
+  pub requires_reschedule: bool,
+  // Push first; compress and drop only when pushing does not fit the day
+  pub candidates: Vec<Reschedule>,
+}
+
+/// Minutes since midnight for `HH:MM`
+fn parse_time(value: &str) -> Result<i64, String> {
+  let invalid = || format!("Invalid time \"{}\", expected HH:MM", value);
+  let (hours, minutes) = value.trim().split_once(':').ok_or_else(invalid)?;
+  let hours: i64 = hours.parse().map_err(|_| invalid())?;
+  let minutes: i64 = minutes.parse().map_err(|_| invalid())?;
+  if !(0..=24).contains(&hours) || !(0..60).contains(&minutes) {
+    return Err(invalid());
+  }
+  Ok(hours * 60 + minutes)
+}
+
+fn format_time(minutes: i64) -> String {
+  let minutes = minutes.rem_euclid(MINUTES_PER_DAY);
+  format!("{:02}:{:02}", minutes / 60, minutes % 60)
+}
+
+struct Block<'a> {
+  source: &'a ScheduleBlock,
+  start: i64,
+  end: i64,
+  fixed: bool,
+  priority: u8,
+}
+
+impl Block<'_> {
+  fn duration(&self) -> i64 {
+    self.end - self.start
+  }
+
+  fn droppable(&self) -> bool {
+    !self.fixed
+      && self.priority > TOP_PRIORITY
+      && !UNDROPPABLE_TYPES.contains(&self.source.kind.to_lowercase().as_str())
+  }
+
+  fn reference(&self) -> BlockRef {
+    BlockRef { start_time: self.source.start_time.clone(), title: self.source.title.clone() }
+  }
+}
+
+/// Blocks in time order. One that ends before it starts runs past midnight.
+fn parse_blocks(blocks: &[ScheduleBlock]) -> Result<Vec<Block<'_>>, String> {
+  let mut parsed = blocks
+    .iter()
+    .map(|source| {
+      let start = parse_time(&source.start_time)?;
+      let mut end = parse_time(&source.end_time)?;
+      if end <= start {
+        end += MINUTES_PER_DAY;
+      }
+      Ok(Block {
+        source,
+        start,
+        end,
+        fixed: FIXED_TYPES.contains(&source.kind.to_lowercase().as_str()),
+        priority: source.priority.unwrap_or(DEFAULT_PRIORITY),
+      })
+    })
+    .collect::<Result<Vec<_>, String>>()?;
+  parsed.sort_by_key(|b| b.start);
+  Ok(parsed)
+}
+
+/// Where each block lands when movable ones start at their planned time or
+/// as soon as the one before them ends. `None` durations are dropped.
+struct Layout {
+  slots: Vec<Option<(i64, i64)>>,
+  conflicts: Vec<usize>,
+  end: i64,
+}
+
+fn layout(blocks: &[Block], durations: &[Option<i64>], cursor: i64) -> Layout {
+  let mut slots = Vec::with_capacity(blocks.len());
+  let mut conflicts = Vec::new();
+  let mut cursor = cursor;
+  for (i, (block, duration)) in blocks.iter().zip(durations).enumerate() {
+    if block.fixed {
+      if cursor > block.start {
+        conflicts.push(i);
+      }
+      slots.push(Some((block.start, block.end)));
+      cursor = cursor.max(block.end);
+      continue;
+    }
+    match duration {
+      Some(duration) => {
+        let start = block.start.max(cursor);
+        cursor = start + duration;
+        slots.push(Some((start, cursor)));
+      }
+      None => slots.push(None),
+    }
+  }
+  Layout { slots, conflicts, end: cursor }
+}
+
+/// How far a run of movable blocks overshoots `deadline`
+fn excess(blocks: &[Block], durations: &[Option<i64>], cursor: i64, deadline: i64) -> i64 {
+  (layout(blocks, durations, cursor).end - deadline).max(0)
+}
+
+/// Shortens blocks a minute at a time, always taking the minute from the
+/// least important, latest block where it still helps
+fn compress(
+  blocks: &[Block],
+  durations: &mut [Option<i64>],
+  cursor: i64,
+  deadline: i64,
+  min_block: i64,
+) {
+  let mut over = excess(blocks, durations, cursor, deadline);
+  while over > 0 {
+    let mut order: Vec<usize> = (0..blocks.len())
+      .filter(|&i| durations[i].is_some_and(|d| d > min_block))
+      .collect();
+    order.sort_by_key(|&i| (Reverse(blocks[i].priority), Reverse(i)));
+    let step = order.into_iter().find_map(|i| {
+      durations[i] = durations[i].map(|d| d - 1);
+      let after = excess(blocks, durations, cursor, deadline);
+      if after < over {
+        return Some(after);
+      }
+      durations[i] = durations[i].map(|d| d + 1);
+      None
+    });
+    match step {
+      Some(after) => over = after,
+      None => break,
+    }
+  }
+}
+
+/// Leaves blocks out until the run fits, starting with the least important.
+/// Within a priority the shortest block that clears the overshoot goes,
+/// otherwise the one that clears the most.
+fn drop_low_priority(
+  blocks: &[Block],
+  durations: &mut [Option<i64>],
+  cursor: i64,
+  deadline: i64,
+) {
+  loop {
+    let over = excess(blocks, durations, cursor, deadline);
+    if over == 0 {
+      break;
+    }
+    let droppable: Vec<usize> = (0..blocks.len())
+      .filter(|&i| durations[i].is_some() && blocks[i].droppable())
+      .collect();
+    let choice = droppable
+      .into_iter()
+      .filter_map(|i| {
+        let kept = durations[i].take();
+        let after = excess(blocks, durations, cursor, deadline);
+        durations[i] = kept;
+        let duration = blocks[i].duration();
+        (after < over).then_some((Reverse(blocks[i].priority), after > 0, after, duration, i))
+      })
+      .min();
+    match choice {
+      Some((.., i)) => durations[i] = None,
+      None => break,
+    }
+  }
+}
+
+/// Lays out the blocks after the last timed one with `strategy`, a stretch
+/// between fixed blocks at a time
+fn plan(
+  remaining: &[Block],
+  cursor: i64,
+  day_end: i64,
+  strategy: Strategy,
+  min_block: i64,
+) -> Layout {
+  let mut durations: Vec<Option<i64>> = remaining.iter().map(|b| Some(b.duration())).collect();
+  let mut run_cursor = cursor;
+  let mut run_start = 0;
+  for i in 0..=remaining.len() {
+    let deadline = match remaining.get(i) {
+      Some(block) if block.fixed => block.start,
+      Some(_) => continue,
+      None => day_end,
+    };
+    let run = &remaining[run_start..i];
+    let run_durations = &mut durations[run_start..i];
+    match strategy {
+      Strategy::Push => {}
+      Strategy::Compress => compress(run, run_durations, run_cursor, deadline, min_block),
+      Strategy::DropLowPriority => drop_low_priority(run, run_durations, run_cursor, deadline),
+    }
+    run_cursor = layout(run, run_durations, run_cursor).end;
+    if let Some(fixed) = remaining.get(i) {
+      run_cursor = run_cursor.max(fixed.end);
+    }
+    run_start = i + 1;
+  }
+  layout(remaining, &durations, cursor)
+}
+
+fn reschedule(
+  blocks: &[Block],
+  first_remaining: usize,
+  cursor: i64,
+  day_end: i64,
+  strategy: Strategy,
+  min_block: i64,
+) -> Reschedule {
+  let remaining = &blocks[first_remaining..];
+  let placed = plan(remaining, cursor, day_end, strategy, min_block);
+
+  let mut day: Vec<ScheduleBlock> =
+    blocks[..first_remaining].iter().map(|b| b.source.clone()).collect();
+  let mut changes = Vec::new();
+  let mut dropped = Vec::new();
+  for (block, slot) in remaining.iter().zip(&placed.slots) {
+    let Some((start, end)) = *slot else {
+      dropped.push(block.reference());
+      continue;
+    };
+    let mut moved = block.source.clone();
+    if (start, end) != (block.start, block.end) {
+      moved.start_time = format_time(start);
+      moved.end_time = format_time(end);
+      changes.push(BlockChange {
+        start_time: block.source.start_time.clone(),
+        end_time: block.source.end_time.clone(),
+        title: block.source.title.clone(),
+        new_start_time: moved.start_time.clone(),
+        new_end_time: moved.end_time.clone(),
+      });
+    }
+    day.push(moved);
+  }
+
+  let conflicts: Vec<BlockRef> =
+    placed.conflicts.iter().map(|&i| remaining[i].reference()).collect();
+  let overflow_minutes = (placed.end - day_end).max(0);
+  Reschedule {
+    strategy,
+    blocks: day,
+    changes,
+    dropped,
+    feasible: conflicts.is_empty() && overflow_minutes == 0,
+    conflicts,
+    overflow_minutes,
+  }
+}
+
+/// Works out how far behind the day is from the blocks timed so far and how
+/// the rest of it could be rearranged. Blocks up to the last timed one are
+/// left as planned.
+pub fn analyze(input: &DriftInput) -> Result<DriftReport, String> {
+  let options = &input.options;
+  if options.min_block_minutes < 0 || options.threshold_minutes < 0 {
+    return Err("Drift options must not be negative".to_string());
+  }
+  let blocks = parse_blocks(&input.blocks)?;
+
+  let mut timed = input
+    .actuals
+    .iter()
+    .map(|actual| {
+      let start = parse_time(&actual.start_time)?;
+      let index = blocks
+        .iter()
+        .position(|b| b.start == start)
+        .ok_or_else(|| format!("No block starts at {}", actual.start_time))?;
+      if actual.actual_duration < 0 {
+        return Err(format!("Negative duration for the block at {}", actual.start_time));
+      }
+      Ok((index, actual))
+    })
+    .collect::<Result<Vec<_>, String>>()?;
+  timed.sort_by_key(|(index, _)| *index);
+
+  let mut block_drifts = Vec::new();
+  // Index of the last timed block and when it really ended
+  let mut last: Option<(usize, i64)> = None;
+  for (index, actual) in timed {
+    let block = &blocks[index];
+    let actual_start = match &actual.actual_start {
+      Some(value) => {
+        let mut time = parse_time(value)?;
+        // A block before midnight that really started after it
+        if time + MINUTES_PER_DAY / 2 < block.start {
+          time += MINUTES_PER_DAY;
+        }
+        time
+      }
+      // On time, unless the block before it was still running
+      None => last.map_or(block.start, |(_, end)| block.start.max(end)),
+    };
+    let actual_end = actual_start + actual.actual_duration;
+    block_drifts.push(BlockDrift {
+      start_time: block.source.start_time.clone(),
+      title: block.source.title.clone(),
+      planned_duration: block.duration(),
+      actual_duration: actual.actual_duration,
+      overrun_minutes: actual.actual_duration - block.duration(),
+      drift_minutes: actual_end - block.end,
+    });
+    last = Some((index, actual_end));
+  }
+
+  let Some((last_index, cursor)) = last else {
+    return Ok(DriftReport {
+      block_drifts,
+      drift_minutes: 0,
+      significant: false,
+      affected_blocks: Vec::new(),
+      requires_reschedule: false,
+      candidates: Vec::new(),
+    });
+  };
+  let drift_minutes = cursor - blocks[last_index].end;
+  let significant = drift_minutes > options.threshold_minutes;
+
+  let day_end = match &options.day_end {
+    Some(value) => {
+      let mut end = parse_time(value)?;
+      if blocks.first().is_some_and(|b| end < b.start) {
+        end += MINUTES_PER_DAY;
+      }
+      end
+    }
+    None => blocks.iter().map(|b| b.end).max().unwrap_or(0),
+  };
+  let min_block = options.min_block_minutes;
+
+  let push = reschedule(&blocks, last_index + 1, cursor, day_end, Strategy::Push, min_block);
+  let affected_blocks = push.changes.clone();
+  let mut candidates = Vec::new();
+  if !push.feasible {
+    candidates.push(push);
+    for strategy in [Strategy::Compress, Strategy::DropLowPriority] {
+      candidates.push(reschedule(&blocks, last_index + 1, cursor, day_end, strategy, min_block));
+    }
+  } else if !push.changes.is_empty() {
+    candidates.push(push);
+  }
+
+  Ok(DriftReport {
+    block_drifts,
+    drift_minutes,
+    significant,
+    requires_reschedule: significant && !candidates.is_empty(),
+    affected_blocks,
+    candidates,
+  })
+}
+
+#[tauri::command]
+pub fn compute_drift(input: DriftInput) -> Result<DriftReport, String> {
+  analyze(&input)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use serde_json::json;
+
+  // A study day: morning lecture, a run of flexible blocks, an afternoon
+  // exam and an evening that ends at 18:00
+  fn study_day() -> Vec<ScheduleBlock> {
+    blocks(&[
+      ("08:00", "09:00", "Lecture", "class", 1),
+      ("09:00", "10:00", "Problem set", "study", 1),
+      ("10:00", "10:30", "Reading", "study", 3),
+      ("10:30", "10:45", "Break", "break", 3),
+      ("10:45", "12:00", "Lab report", "assignment", 2),
+      ("13:00", "14:00", "Midterm", "exam", 1),
+      ("14:00", "15:00", "Review notes", "study", 2),
+      ("15:30", "16:30", "Side project", "personal", 3),
+      ("16:30", "18:00", "Flashcards", "study", 2),
+    ])
+  }
+
+  fn blocks(spec: &[(&str, &str, &str, &str, u8)]) -> Vec<ScheduleBlock> {
+    spec
+      .iter()
+      .map(|&(start, end, title, kind, priority)| ScheduleBlock {
+        start_time: start.to_string(),
+        end_time: end.to_string(),
+        title: title.to_string(),
+        kind: kind.to_string(),
+        priority: Some(priority),
+        extra: Map::new(),
+      })
+      .collect()
+  }
+
+  fn actual(start: &str, duration: i64) -> BlockActual {
+    BlockActual { start_time: start.to_string(), actual_start: None, actual_duration: duration }
+  }
+
+  fn input(blocks: Vec<ScheduleBlock>, actuals: Vec<BlockActual>) -> DriftInput {
+    DriftInput { blocks, actuals, options: DriftOptions::default() }
+  }
+
+  fn candidate(report: &DriftReport, strategy: Strategy) -> &Reschedule {
+    report.candidates.iter().find(|c| c.strategy == strategy).expect("candidate")
+  }
+
+  fn times(reschedule: &Reschedule) -> Vec<(String, String, String)> {
+    reschedule
+      .blocks
+      .iter()
+      .map(|b| (b.title.clone(), b.start_time.clone(), b.end_time.clone()))
+      .collect()
+  }
+
+  fn titles(refs: &[BlockRef]) -> Vec<&str> {
+    refs.iter().map(|r| r.title.as_str()).collect()
+  }
+
+  #[test]
+  fn on_time_day_needs_nothing() {
+    let report = analyze(&input(study_day(), vec![actual("09:00", 60)])).unwrap();
+    assert_eq!(report.drift_minutes, 0);
+    assert!(!report.significant);
+    assert!(report.affected_blocks.is_empty());
+    assert!(report.candidates.is_empty());
+    assert!(!report.requires_reschedule);
+  }
+
+  #[test]
+  fn no_actuals_reports_no_drift() {
+    let report = analyze(&input(study_day(), Vec::new())).unwrap();
+    assert!(report.block_drifts.is_empty());
+    assert_eq!(report.drift_minutes, 0);
+    assert!(report.candidates.is_empty());
+  }
+
+  #[test]
+  fn overrun_cascades_until_a_gap_absorbs_it() {
+    // 20 minutes over on the problem set; the lunch gap before the exam
+    // soaks up what is left
+    let report = analyze(&input(study_day(), vec![actual("09:00", 80)])).unwrap();
+    assert_eq!(report.drift_minutes, 20);
+    assert!(report.significant);
+    assert_eq!(
+      report.block_drifts,
+      vec![BlockDrift {
+        start_time: "09:00".to_string(),
+        title: "Problem set".to_string(),
+        planned_duration: 60,
+        actual_duration: 80,
+        overrun_minutes: 20,
+        drift_minutes: 20,
+      }]
+    );
+    let moved: Vec<_> = report
+      .affected_blocks
+      .iter()
+      .map(|c| (c.title.as_str(), c.new_start_time.as_str(), c.new_end_time.as_str()))
+      .collect();
+    assert_eq!(
+      moved,
+      vec![
+        ("Reading", "10:20", "10:50"),
+        ("Break", "10:50", "11:05"),
+        ("Lab report", "11:05", "12:20"),
+      ]
+    );
+
+    assert_eq!(report.candidates.len(), 1);
+    let push = &report.candidates[0];
+    assert_eq!(push.strategy, Strategy::Push);
+    assert!(push.feasible);
+    assert!(report.requires_reschedule);
+  }
+
+  #[test]
+  fn small_drift_is_not_significant() {
+    let report = analyze(&input(study_day(), vec![actual("09:00", 68)])).unwrap();
+    assert_eq!(report.drift_minutes, 8);
+    assert!(!report.significant);
+    assert!(!report.requires_reschedule);
+    // Still reported, so the client can show the shifted times
+    assert_eq!(report.affected_blocks.len(), 3);
+  }
+
+  #[test]
+  fn late_start_counts_toward_drift() {
+    let mut late = actual("09:00", 60);
+    late.actual_start = Some("09:15".to_string());
+    let report = analyze(&input(study_day(), vec![late])).unwrap();
+    assert_eq!(report.block_drifts[0].overrun_minutes, 0);
+    assert_eq!(report.block_drifts[0].drift_minutes, 15);
+    assert_eq!(report.drift_minutes, 15);
+  }
+
+  #[test]
+  fn times_are_compared_as_times_not_strings() {
+    // "9:30" sorts after "10:00" as a string
+    let day = blocks(&[
+      ("10:00", "11:00", "Later", "study", 2),
+      ("9:30", "10:00", "Earlier", "study", 2),
+    ]);
+    let report = analyze(&input(day, vec![actual("09:30", 45)])).unwrap();
+    assert_eq!(report.drift_minutes, 15);
+    assert_eq!(report.affected_blocks[0].title, "Later");
+    assert_eq!(report.affected_blocks[0].new_start_time, "10:15");
+  }
+
+  #[test]
+  fn drift_follows_the_latest_timed_block() {
+    // Behind after the problem set, caught up by a short reading that could
+    // only start once it was done
+    let report =
+      analyze(&input(study_day(), vec![actual("10:00", 10), actual("09:00", 80)])).unwrap();
+    assert_eq!(report.block_drifts[0].drift_minutes, 20);
+    assert_eq!(report.block_drifts[1].overrun_minutes, -20);
+    assert_eq!(report.block_drifts[1].drift_minutes, 0);
+    assert_eq!(report.drift_minutes, 0);
+    assert!(report.candidates.is_empty());
+  }
+
+  #[test]
+  fn push_into_a_fixed_block_offers_compress_and_drop() {
+    // 90 minutes over runs the lab report into the midterm
+    let report = analyze(&input(study_day(), vec![actual("09:00", 150)])).unwrap();
+    assert_eq!(report.drift_minutes, 90);
+    let strategies: Vec<_> = report.candidates.iter().map(|c| c.strategy).collect();
+    assert_eq!(strategies, vec![Strategy::Push, Strategy::Compress, Strategy::DropLowPriority]);
+
+    let push = candidate(&report, Strategy::Push);
+    assert!(!push.feasible);
+    assert_eq!(titles(&push.conflicts), vec!["Midterm"]);
+    // Nothing after the exam moves
+    assert!(push.changes.iter().all(|c| c.start_time.as_str() < "13:00"));
+
+    // Reading, the least important, is cut to the minimum first, then the
+    // lab report gives up the rest
+    let compress = candidate(&report, Strategy::Compress);
+    assert!(compress.feasible);
+    assert!(compress.dropped.is_empty());
+    let day = times(compress);
+    assert_eq!(
+      &day[2..6],
+      &[
+        ("Reading".to_string(), "11:30".to_string(), "11:45".to_string()),
+        ("Break".to_string(), "11:45".to_string(), "12:00".to_string()),
+        ("Lab report".to_string(), "12:00".to_string(), "13:00".to_string()),
+        ("Midterm".to_string(), "13:00".to_string(), "14:00".to_string()),
+      ]
+    );
+
+    // The break stays even though it shares the lowest priority
+    let dropped = candidate(&report, Strategy::DropLowPriority);
+    assert!(dropped.feasible);
+    assert_eq!(titles(&dropped.dropped), vec!["Reading"]);
+    assert!(dropped.conflicts.is_empty());
+  }
+
+  #[test]
+  fn compress_respects_the_minimum_block_length() {
+    let mut day_input = input(study_day(), vec![actual("09:00", 200)]);
+    day_input.options.min_block_minutes = 30;
+    let report = analyze(&day_input).unwrap();
+    let compress = candidate(&report, Strategy::Compress);
+    assert!(!compress.feasible);
+    assert_eq!(titles(&compress.conflicts), vec!["Midterm"]);
+    for block in &compress.blocks[2..5] {
+      let length = parse_time(&block.end_time).unwrap() - parse_time(&block.start_time).unwrap();
+      assert!(length >= 30 || block.title == "Break", "{} is {} minutes", block.title, length);
+    }
+  }
+
+  #[test]
+  fn drop_takes_the_least_important_block_first() {
+    // Past the exam: 40 minutes over at 15:00 pushes past 18:00
+    let report =
+      analyze(&input(study_day(), vec![actual("09:00", 60), actual("14:00", 100)])).unwrap();
+    assert_eq!(report.drift_minutes, 40);
+    let push = candidate(&report, Strategy::Push);
+    assert_eq!(push.overflow_minutes, 10);
+    assert!(push.conflicts.is_empty());
+
+    // The side project is the only priority 3 block left, and without it
+    // the flashcards keep their planned time
+    let dropped = candidate(&report, Strategy::DropLowPriority);
+    assert!(dropped.feasible);
+    assert_eq!(titles(&dropped.dropped), vec!["Side project"]);
+    let day = times(dropped);
+    assert_eq!(
+      day.last().unwrap(),
+      &("Flashcards".to_string(), "16:30".to_string(), "18:00".to_string())
+    );
+  }
+
+  #[test]
+  fn drop_prefers_the_shortest_block_that_fits() {
+    let day = blocks(&[
+      ("09:00", "10:00", "Essay", "study", 2),
+      ("10:00", "11:00", "Long read", "study", 3),
+      ("11:00", "11:20", "Quiz prep", "study", 3),
+      ("11:20", "12:05", "Podcast", "personal", 3),
+    ]);
+    let report = analyze(&input(day, vec![actual("09:00", 75)])).unwrap();
+    let dropped = candidate(&report, Strategy::DropLowPriority);
+    assert!(dropped.feasible);
+    assert_eq!(titles(&dropped.dropped), vec!["Quiz prep"]);
+  }
+
+  #[test]
+  fn top_priority_fixed_and_break_blocks_are_never_dropped() {
+    let day = blocks(&[
+      ("09:00", "10:00", "Essay", "study", 2),
+      ("10:00", "11:00", "Thesis", "study", 1),
+      ("11:00", "11:15", "Break", "break", 3),
+      ("11:15", "12:00", "Seminar", "class", 3),
+    ]);
+    let report = analyze(&input(day, vec![actual("09:00", 120)])).unwrap();
+    let dropped = candidate(&report, Strategy::DropLowPriority);
+    assert!(!dropped.feasible);
+    assert!(dropped.dropped.is_empty());
+    assert_eq!(titles(&dropped.conflicts), vec!["Seminar"]);
+  }
+
+  #[test]
+  fn day_end_option_bounds_the_day() {
+    let mut day_input = input(study_day(), vec![actual("09:00", 60), actual("14:00", 100)]);
+    day_input.options.day_end = Some("19:00".to_string());
+    let report = analyze(&day_input).unwrap();
+    assert_eq!(report.candidates.len(), 1);
+    assert!(report.candidates[0].feasible);
+    assert_eq!(report.candidates[0].overflow_minutes, 0);
+  }
+
+  #[test]
+  fn blocks_keep_their_other_fields() {
+    let day: Vec<ScheduleBlock> = serde_json::from_value(json!([
+      { "startTime": "09:00", "endTime": "10:00", "title": "Problem set", "type": "study",
+        "priority": 1 },
+      { "startTime": "10:00", "endTime": "11:00", "title": "Reading", "type": "study",
+        "description": "Chapter 4", "courseCode": "MC450" },
+    ]))
+    .unwrap();
+    let report = analyze(&input(day, vec![actual("09:00", 75)])).unwrap();
+    let moved = serde_json::to_value(&report.candidates[0].blocks[1]).unwrap();
+    assert_eq!(
+      moved,
+      json!({ "startTime": "10:15", "endTime": "11:15", "title": "Reading", "type": "study",
+        "description": "Chapter 4", "courseCode": "MC450" })
+    );
+  }
+
+  #[test]
+  fn blocks_can_run_past_midnight() {
+    let day = blocks(&[
+      ("22:00", "23:30", "Reading", "study", 2),
+      ("23:30", "00:30", "Wind down", "personal", 3),
+    ]);
+    let report = analyze(&input(day, vec![actual("22:00", 120)])).unwrap();
+    assert_eq!(report.drift_minutes, 30);
+    assert_eq!(report.affected_blocks[0].new_start_time, "00:00");
+    assert_eq!(report.affected_blocks[0].new_end_time, "01:00");
+  }
+
+  #[test]
+  fn invalid_input_is_rejected() {
+    let bad_time = blocks(&[("9am", "10:00", "Reading", "study", 2)]);
+    assert!(analyze(&input(bad_time, Vec::new())).is_err());
+
+    let unknown_block = analyze(&input(study_day(), vec![actual("09:10", 60)]));
+    assert_eq!(unknown_block.unwrap_err(), "No block starts at 09:10");
+
+    assert!(analyze(&input(study_day(), vec![actual("09:00", -5)])).is_err());
+  }
+}
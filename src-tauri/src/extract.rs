@@ -0,0 +1,435 @@
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::Path;
+use std::process::Stdio;
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+/// Running the app binary with this flag turns it into a text extraction
+/// helper speaking the same line protocol as `server/services/pdfExtractor.py`:
+/// `{"files": [{"path", "name"}]}` on stdin, one JSON update per line on
+/// stdout.
+pub const HELPER_FLAG: &str = "--extract-text";
+pub const EVENT_PROGRESS: &str = "extract://progress";
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ExtractFile {
+  pub path: String,
+  pub name: String,
+}
+
+#[derive(Deserialize)]
+struct ExtractRequest {
+  #[serde(default)]
+  files: Vec<ExtractFile>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Status {
+  Success,
+  Error,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PageText {
+  pub page: u32,
+  pub text: String,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DocumentMetadata {
+  pub format: String,
+  pub page_count: Option<u32>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub title: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub author: Option<String>,
+}
+
+/// One file's result. `text` and `error` match the Python script; `pages`
+/// and `metadata` are additions older consumers simply ignore.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Progress {
+  pub file_name: String,
+  pub status: Status,
+  pub current: usize,
+  pub total: usize,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub text: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub error: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub pages: Option<Vec<PageText>>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub metadata: Option<DocumentMetadata>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum Update {
+  Progress(Progress),
+  Complete,
+  Error { error: String },
+}
+
+struct Extracted {
+  text: String,
+  pages: Vec<PageText>,
+  metadata: DocumentMetadata,
+}
+
+fn info_string(doc: &pdf_extract::Document, key: &[u8]) -> Option<String> {
+  let info = doc.trailer.get(b"Info").ok()?;
+  let info = match info {
+    pdf_extract::Object::Reference(id) => doc.get_object(*id).ok()?,
+    other => other,
+  };
+  let value = info.as_dict().ok()?.get(key).ok()?;
+  pdf_extract::decode_text_string(value)
+    .ok()
+    .map(|s| s.trim().to_string())
+    .filter(|s| !s.is_empty())
+}
+
+fn extract_pdf(path: &Path) -> Result<Extracted, String> {
+  let bytes = fs::read(path).map_err(|e| format!("PDF error: {}", e))?;
+  let pages = pdf_extract::extract_text_from_mem_by_pages(&bytes)
+    .map_err(|e| format!("PDF error: {}", e))?;
+  let doc = pdf_extract::Document::load_mem(&bytes).ok();
+
+  let pages: Vec<PageText> = pages
+    .into_iter()
+    .enumerate()
+    .map(|(i, text)| PageText { page: i as u32 + 1, text: text.trim().to_string() })
+    .collect();
+  let text = pages.iter().map(|p| p.text.as_str()).collect::<Vec<_>>().join("\n");
+  let text = text.trim().to_string();
+  let metadata = DocumentMetadata {
+    format: "pdf".to_string(),
+    page_count: Some(pages.len() as u32),
+    title: doc.as_ref().and_then(|d| info_string(d, b"Title")),
+    author: doc.as_ref().and_then(|d| info_string(d, b"Author")),
+  };
+  Ok(Extracted {
+    text: if text.is_empty() { "[PDF extracted but empty]".to_string() } else { text },
+    pages,
+    metadata,
+  })
+}
+
+fn extract_plain(path: &Path, format: &str, label: &str, empty: &str) -> Result<Extracted, String> {
+  let bytes = fs::read(path).map_err(|e| format!("{} error: {}", label, e))?;
+  // Python's `errors='ignore'`: undecodable bytes are dropped
+  let text: String = String::from_utf8_lossy(&bytes).replace('\u{FFFD}', "").trim().to_string();
+  let text = if text.is_empty() { empty.to_string() } else { text };
+  Ok(Extracted {
+    pages: vec![PageText { page: 1, text: text.clone() }],
+    text,
+    metadata: DocumentMetadata { format: format.to_string(), ..Default::default() },
+  })
+}
+
+/// Extracts one file, with the same error messages as the Python script
+fn extract_file(path: &Path) -> Result<Extracted, String> {
+  let ext = path
+    .extension()
+    .map(|ext| format!(".{}", ext.to_string_lossy().to_lowercase()))
+    .unwrap_or_default();
+  match ext.as_str() {
+    ".pdf" => extract_pdf(path),
+    ".txt" => extract_plain(path, "txt", "Text", "[Empty file]"),
+    ".md" => extract_plain(path, "md", "Markdown", "[Empty markdown]"),
+    _ => Err(format!("Unsupported: {}", ext)),
+  }
+}
+
+fn progress(file: &ExtractFile, current: usize, total: usize) -> Progress {
+  // Parsers can panic on malformed PDFs; this runs in the helper process,
+  // so a panic only costs the one file
+  let result = std::panic::catch_unwind(|| extract_file(Path::new(&file.path)))
+    .unwrap_or_else(|_| Err("PDF error: could not parse document".to_string()));
+  let (status, extracted, error) = match result {
+    Ok(extracted) => (Status::Success, Some(extracted), None),
+    Err(e) => (Status::Error, None, Some(e)),
+  };
+  Progress {
+    file_name: file.name.clone(),
+    status,
+    current,
+    total,
+    text: extracted.as_ref().map(|e| e.text.clone()),
+    error,
+    pages: extracted.as_ref().map(|e| e.pages.clone()),
+    metadata: extracted.map(|e| e.metadata),
+  }
+}
+
+fn emit(out: &mut impl Write, update: &Update) -> io::Result<()> {
+  serde_json::to_writer(&mut *out, update)?;
+  out.write_all(b"\n")?;
+  out.flush()
+}
+
+/// Answers the request read from `input` with one update per line on
+/// `out`. Returns the process exit code.
+fn serve(input: &mut impl Read, out: &mut impl Write) -> i32 {
+  let mut body = String::new();
+  let request = input
+    .read_to_string(&mut body)
+    .map_err(|e| e.to_string())
+    .and_then(|_| serde_json::from_str::<ExtractRequest>(&body).map_err(|e| e.to_string()));
+  let request = match request {
+    Ok(request) => request,
+    Err(error) => {
+      let _ = emit(out, &Update::Error { error });
+      return 1;
+    }
+  };
+
+  let total = request.files.len();
+  for (i, file) in request.files.iter().enumerate() {
+    if emit(out, &Update::Progress(progress(file, i + 1, total))).is_err() {
+      return 1;
+    }
+  }
+  match emit(out, &Update::Complete) {
+    Ok(()) => 0,
+    Err(_) => 1,
+  }
+}
+
+/// Entry point for `HELPER_FLAG`. Returns the process exit code.
+pub fn run_helper() -> i32 {
+  // Keep panics from parsers out of stdout, which carries the protocol
+  std::panic::set_hook(Box::new(|info| eprintln!("{}", info)));
+  serve(&mut io::stdin().lock(), &mut io::stdout().lock())
+}
+
+/// Extracts text from files on disk. Runs the helper in a child process so
+/// a parser crash cannot take the shell down, and forwards each file's
+/// result as an `extract://progress` event as it arrives.
+#[tauri::command]
+pub async fn extract_text(
+  app: AppHandle,
+  files: Vec<ExtractFile>,
+) -> Result<Vec<Progress>, String> {
+  let exe =
+    std::env::current_exe().map_err(|e| format!("Could not locate the app binary: {}", e))?;
+  let mut child = tokio::process::Command::new(exe)
+    .arg(HELPER_FLAG)
+    .stdin(Stdio::piped())
+    .stdout(Stdio::piped())
+    .stderr(Stdio::piped())
+    .kill_on_drop(true)
+    .spawn()
+    .map_err(|e| format!("Could not start the extractor: {}", e))?;
+
+  let request = serde_json::json!({ "files": files }).to_string();
+  let mut stdin = child.stdin.take().unwrap();
+  stdin.write_all(request.as_bytes()).await.map_err(|e| e.to_string())?;
+  drop(stdin);
+
+  // Drained alongside stdout so a chatty parser cannot fill the pipe
+  let mut stderr = BufReader::new(child.stderr.take().unwrap()).lines();
+  tauri::async_runtime::spawn(async move {
+    while let Ok(Some(line)) = stderr.next_line().await {
+      log::warn!("extractor: {}", line);
+    }
+  });
+
+  let mut results = Vec::new();
+  let mut lines = BufReader::new(child.stdout.take().unwrap()).lines();
+  while let Some(line) = lines.next_line().await.map_err(|e| e.to_string())? {
+    match serde_json::from_str::<Update>(&line) {
+      Ok(Update::Progress(progress)) => {
+        let _ = app.emit(EVENT_PROGRESS, &progress);
+        results.push(progress);
+      }
+      Ok(Update::Complete) => {}
+      Ok(Update::Error { error }) => return Err(error),
+      Err(e) => log::warn!("Unexpected extractor output: {}", e),
+    }
+  }
+
+  let status = child.wait().await.map_err(|e| e.to_string())?;
+  if !status.success() {
+    return Err(format!("Extraction failed ({})", status));
+  }
+  Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test_util::TempDir;
+
+  /// A one-page PDF showing `text` in Helvetica, with a title and author
+  fn pdf(text: &str) -> Vec<u8> {
+    let content = format!("BT /F1 12 Tf 72 720 Td ({}) Tj ET", text);
+    let objects = [
+      "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+      "<< /Type /Pages /Kids [3 0 R] /Count 1 >>".to_string(),
+      "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 612 792] /Contents 4 0 R \
+       /Resources << /Font << /F1 5 0 R >> >> >>"
+        .to_string(),
+      format!("<< /Length {} >>\nstream\n{}\nendstream", content.len(), content),
+      "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica >>".to_string(),
+      "<< /Title (Lecture Notes) /Author (Ada Lovelace) >>".to_string(),
+    ];
+    let mut out = b"%PDF-1.4\n".to_vec();
+    let mut offsets = Vec::new();
+    for (i, body) in objects.iter().enumerate() {
+      offsets.push(out.len());
+      out.extend(format!("{} 0 obj\n{}\nendobj\n", i + 1, body).bytes());
+    }
+    let xref = out.len();
+    out.extend(format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).bytes());
+    for offset in offsets {
+      out.extend(format!("{:010} 00000 n \n", offset).bytes());
+    }
+    out.extend(
+      format!(
+        "trailer\n<< /Size {} /Root 1 0 R /Info 6 0 R >>\nstartxref\n{}\n%%EOF\n",
+        objects.len() + 1,
+        xref
+      )
+      .bytes(),
+    );
+    out
+  }
+
+  fn extract_error(path: &Path) -> String {
+    match extract_file(path) {
+      Ok(_) => panic!("{} should not extract", path.display()),
+      Err(e) => e,
+    }
+  }
+
+  #[test]
+  fn extracts_plain_text_by_extension() {
+    let dir = TempDir::new();
+    let txt = extract_file(&dir.file("notes.TXT", b"  first line\nsecond  \n")).unwrap();
+    assert_eq!(txt.text, "first line\nsecond");
+    assert_eq!(txt.metadata.format, "txt");
+    assert_eq!(txt.pages.len(), 1);
+    assert_eq!(txt.pages[0].page, 1);
+
+    let md = extract_file(&dir.file("readme.md", b"# Title")).unwrap();
+    assert_eq!(md.text, "# Title");
+    assert_eq!(md.metadata.format, "md");
+    assert_eq!(md.metadata.page_count, None);
+  }
+
+  #[test]
+  fn marks_empty_plain_text() {
+    let dir = TempDir::new();
+    assert_eq!(extract_file(&dir.file("a.txt", b" \n")).unwrap().text, "[Empty file]");
+    assert_eq!(extract_file(&dir.file("a.md", b"")).unwrap().text, "[Empty markdown]");
+  }
+
+  #[test]
+  fn drops_undecodable_bytes() {
+    let dir = TempDir::new();
+    let extracted = extract_file(&dir.file("a.txt", b"caf\xe9 ok")).unwrap();
+    assert_eq!(extracted.text, "caf ok");
+  }
+
+  #[test]
+  fn rejects_unsupported_files() {
+    let dir = TempDir::new();
+    assert_eq!(extract_error(&dir.file("slides.pptx", b"x")), "Unsupported: .pptx");
+    assert_eq!(extract_error(&dir.file("Makefile", b"x")), "Unsupported: ");
+  }
+
+  #[test]
+  fn extracts_pdf_pages_and_metadata() {
+    let dir = TempDir::new();
+    let extracted = extract_file(&dir.file("lecture.pdf", pdf("Hello from page one"))).unwrap();
+    assert!(extracted.text.contains("Hello from page one"), "{:?}", extracted.text);
+    assert_eq!(extracted.pages.len(), 1);
+    assert_eq!(extracted.metadata.format, "pdf");
+    assert_eq!(extracted.metadata.page_count, Some(1));
+    assert_eq!(extracted.metadata.title.as_deref(), Some("Lecture Notes"));
+    assert_eq!(extracted.metadata.author.as_deref(), Some("Ada Lovelace"));
+  }
+
+  #[test]
+  fn reports_broken_pdfs() {
+    let dir = TempDir::new();
+    assert!(extract_error(&dir.file("broken.pdf", b"not a pdf")).starts_with("PDF error: "));
+  }
+
+  fn run(input: &str) -> (i32, Vec<serde_json::Value>) {
+    let mut out = Vec::new();
+    let code = serve(&mut input.as_bytes(), &mut out);
+    let out = String::from_utf8(out).unwrap();
+    assert!(out.ends_with('\n'));
+    let lines = out.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+    (code, lines)
+  }
+
+  #[test]
+  fn helper_sends_one_line_per_file_then_complete() {
+    let dir = TempDir::new();
+    let good = dir.file("a.txt", b"hello");
+    let missing = dir.path().join("missing.txt");
+    let request = serde_json::json!({ "files": [
+      { "path": good, "name": "a.txt" },
+      { "path": missing, "name": "missing.txt" },
+    ] });
+
+    let (code, lines) = run(&request.to_string());
+    assert_eq!(code, 0);
+    assert_eq!(lines.len(), 3);
+    assert_eq!(lines[0]["type"], "progress");
+    assert_eq!(lines[0]["fileName"], "a.txt");
+    assert_eq!(lines[0]["status"], "success");
+    assert_eq!(lines[0]["current"], 1);
+    assert_eq!(lines[0]["total"], 2);
+    assert_eq!(lines[0]["text"], "hello");
+    assert_eq!(lines[0]["pages"][0]["page"], 1);
+    assert!(lines[0].get("error").is_none());
+
+    assert_eq!(lines[1]["status"], "error");
+    assert_eq!(lines[1]["current"], 2);
+    assert!(lines[1]["error"].as_str().unwrap().starts_with("Text error: "));
+    assert!(lines[1].get("text").is_none());
+
+    assert_eq!(lines[2], serde_json::json!({ "type": "complete" }));
+  }
+
+  #[test]
+  fn helper_completes_an_empty_request() {
+    let (code, lines) = run("{}");
+    assert_eq!(code, 0);
+    assert_eq!(lines, vec![serde_json::json!({ "type": "complete" })]);
+  }
+
+  #[test]
+  fn helper_reports_a_malformed_request() {
+    let (code, lines) = run("{\"files\": 3");
+    assert_eq!(code, 1);
+    assert_eq!(lines.len(), 1);
+    assert_eq!(lines[0]["type"], "error");
+    assert!(lines[0]["error"].is_string());
+  }
+
+  #[test]
+  fn progress_lines_round_trip() {
+    let line = r#"{"type":"progress","fileName":"a.pdf","status":"success","current":1,
+      "total":1,"text":"x"}"#;
+    match serde_json::from_str::<Update>(line).unwrap() {
+      Update::Progress(progress) => {
+        assert_eq!(progress.status, Status::Success);
+        assert_eq!(progress.text.as_deref(), Some("x"));
+        assert!(progress.pages.is_none());
+      }
+      _ => panic!("expected a progress update"),
+    }
+  }
+}
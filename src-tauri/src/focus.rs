@@ -0,0 +1,526 @@
+use std::path::PathBuf;
+use std::sync::{Mutex, MutexGuard};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::time::MissedTickBehavior;
+
+use crate::backend::{self, BackendPhase, BackendState};
+use crate::store::{read_json, write_json};
+
+const FOCUS_FILE_NAME: &str = "focus.json";
+const DRIFT_PATH: &str = "/api/internal/schedule-drift/check";
+pub const EVENT_TICK: &str = "focus://tick";
+pub const EVENT_CHANGED: &str = "focus://changed";
+pub const EVENT_ENDED: &str = "focus://ended";
+pub const EVENT_OVERRUN: &str = "focus://overrun";
+const TICK_INTERVAL: Duration = Duration::from_secs(1);
+// Drift checks that could not be posted, because the backend was down or
+// paused, are retried this many ticks apart
+const RETRY_TICKS: u32 = 60;
+// The backend ignores overruns of this many minutes or fewer
+const DRIFT_THRESHOLD_MINUTES: i64 = 10;
+
+/// What a session is timing. Only blocks are checked for drift; missions
+/// have no planned duration to drift from.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum FocusTarget {
+  #[serde(rename_all = "camelCase")]
+  Block {
+    // Local `YYYY-MM-DD` of the schedule the block belongs to
+    schedule_date: String,
+    // `HH:MM`, which identifies the block within its day
+    start_time: String,
+    title: String,
+    planned_minutes: i64,
+  },
+  #[serde(rename_all = "camelCase")]
+  Mission { mission_id: i64, title: String },
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FocusSession {
+  pub id: String,
+  pub user_id: i64,
+  pub target: FocusTarget,
+  pub started_at: DateTime<Utc>,
+  // Time from earlier running stretches, before the latest pause
+  pub accumulated_secs: i64,
+  // Set while running. Wall-clock based, so a session left running keeps
+  // counting while the app is closed or the machine sleeps.
+  pub running_since: Option<DateTime<Utc>>,
+  // Set once a block has overrun far enough for its drift check to be sent
+  // while it is still running. Stopping still sends the final duration,
+  // which the backend records on the same drift event.
+  #[serde(default)]
+  pub drift_reported: bool,
+}
+
+impl FocusSession {
+  fn elapsed_secs(&self, now: DateTime<Utc>) -> i64 {
+    let running = self.running_since.map_or(0, |since| (now - since).num_seconds().max(0));
+    self.accumulated_secs + running
+  }
+
+  fn pause(&mut self, now: DateTime<Utc>) -> Result<(), String> {
+    let since = self.running_since.take().ok_or("The focus session is already paused")?;
+    self.accumulated_secs += (now - since).num_seconds().max(0);
+    Ok(())
+  }
+
+  fn resume(&mut self, now: DateTime<Utc>) -> Result<(), String> {
+    if self.running_since.is_some() {
+      return Err("The focus session is not paused".to_string());
+    }
+    self.running_since = Some(now);
+    Ok(())
+  }
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FocusStatus {
+  #[serde(flatten)]
+  pub session: FocusSession,
+  pub elapsed_secs: i64,
+  pub paused: bool,
+}
+
+/// A finished session, as returned by `focus_stop` and sent with
+/// `focus://ended`
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FocusRecord {
+  pub session: FocusSession,
+  pub ended_at: DateTime<Utc>,
+  pub actual_minutes: i64,
+  // The backend's drift check response, when it could be posted right away
+  pub drift: Option<Value>,
+}
+
+/// Body of the backend's drift check, kept until it has been posted
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DriftCheck {
+  session_id: String,
+  user_id: i64,
+  schedule_date: String,
+  block_start_time: String,
+  block_title: String,
+  planned_duration: i64,
+  actual_duration: i64,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+struct FocusData {
+  session: Option<FocusSession>,
+  pending: Vec<DriftCheck>,
+}
+
+/// The current session and unsent drift checks, mirrored to `focus.json`
+/// in the data directory
+#[derive(Default)]
+pub struct FocusState {
+  data: Mutex<FocusData>,
+}
+
+impl FocusState {
+  fn lock(&self) -> MutexGuard<'_, FocusData> {
+    self.data.lock().unwrap_or_else(|e| e.into_inner())
+  }
+}
+
+fn focus_path(app: &AppHandle) -> Result<PathBuf, String> {
+  app
+    .path()
+    .app_data_dir()
+    .map(|dir| dir.join(FOCUS_FILE_NAME))
+    .map_err(|e| format!("Could not determine the data directory: {}", e))
+}
+
+fn save(app: &AppHandle, data: &FocusData) {
+  if let Err(e) = focus_path(app).and_then(|path| write_json(&path, data)) {
+    log::warn!("Failed to save focus session: {}", e);
+  }
+}
+
+fn status_of(session: &FocusSession, now: DateTime<Utc>) -> FocusStatus {
+  FocusStatus {
+    session: session.clone(),
+    elapsed_secs: session.elapsed_secs(now),
+    paused: session.running_since.is_none(),
+  }
+}
+
+pub fn status(app: &AppHandle) -> Option<FocusStatus> {
+  let state = app.state::<FocusState>();
+  let data = state.lock();
+  data.session.as_ref().map(|session| status_of(session, Utc::now()))
+}
+
+/// Applies `change` to the current session, saves it and tells the webview
+fn update(
+  app: &AppHandle,
+  change: impl FnOnce(&mut FocusSession, DateTime<Utc>) -> Result<(), String>,
+) -> Result<FocusStatus, String> {
+  let state = app.state::<FocusState>();
+  let mut data = state.lock();
+  let session = data.session.as_mut().ok_or("No focus session is running")?;
+  let now = Utc::now();
+  change(session, now)?;
+  let status = status_of(session, now);
+  save(app, &data);
+  drop(data);
+  let _ = app.emit(EVENT_CHANGED, Some(&status));
+  Ok(status)
+}
+
+pub fn start(app: &AppHandle, user_id: i64, target: FocusTarget) -> Result<FocusStatus, String> {
+  let state = app.state::<FocusState>();
+  let mut data = state.lock();
+  if data.session.is_some() {
+    return Err("A focus session is already running. Stop it first.".to_string());
+  }
+  let now = Utc::now();
+  let session = FocusSession {
+    id: uuid::Uuid::new_v4().to_string(),
+    user_id,
+    target,
+    started_at: now,
+    accumulated_secs: 0,
+    running_since: Some(now),
+    drift_reported: false,
+  };
+  let status = status_of(&session, now);
+  log::info!("Focus session {} started", session.id);
+  data.session = Some(session);
+  save(app, &data);
+  drop(data);
+  let _ = app.emit(EVENT_CHANGED, Some(&status));
+  Ok(status)
+}
+
+async fn post_drift(
+  app: &AppHandle,
+  client: &reqwest::Client,
+  check: &DriftCheck,
+) -> Result<Value, reqwest::Error> {
+  backend::internal_request(app, client, reqwest::Method::POST, DRIFT_PATH)
+    .json(check)
+    .send()
+    .await?
+    .error_for_status()?
+    .json()
+    .await
+}
+
+/// Posts a drift check and returns the backend's answer, or null for one
+/// it rejected, which is dropped rather than retried forever. `None` means
+/// try again later.
+async fn deliver(app: &AppHandle, client: &reqwest::Client, check: &DriftCheck) -> Option<Value> {
+  match post_drift(app, client, check).await {
+    Ok(response) => Some(response),
+    Err(e) if e.status().is_some_and(|s| s.is_client_error()) => {
+      log::warn!("Backend rejected the drift check for session {}: {}", check.session_id, e);
+      Some(Value::Null)
+    }
+    Err(e) => {
+      log::warn!("Could not post the drift check for session {}: {}", check.session_id, e);
+      None
+    }
+  }
+}
+
+fn minutes(secs: i64) -> i64 {
+  (secs + 30) / 60
+}
+
+/// The drift check for a block session, none for a mission
+fn drift_check(session: &FocusSession, actual_minutes: i64) -> Option<DriftCheck> {
+  let FocusTarget::Block { schedule_date, start_time, title, planned_minutes } = &session.target
+  else {
+    return None;
+  };
+  Some(DriftCheck {
+    session_id: session.id.clone(),
+    user_id: session.user_id,
+    schedule_date: schedule_date.clone(),
+    block_start_time: start_time.clone(),
+    block_title: title.clone(),
+    planned_duration: *planned_minutes,
+    actual_duration: actual_minutes,
+  })
+}
+
+/// Posts a drift check when the backend is up, otherwise queues it for the
+/// ticker to retry. Returns the backend's answer when there was one.
+async fn submit(app: &AppHandle, client: &reqwest::Client, check: DriftCheck) -> Option<Value> {
+  let mut drift = None;
+  // Behind any queued checks, so a block's final duration never lands
+  // before its overrun
+  let queued = !app.state::<FocusState>().lock().pending.is_empty();
+  if !queued && app.state::<BackendState>().phase() == BackendPhase::Ready {
+    drift = deliver(app, client, &check).await;
+  }
+  if drift.is_none() {
+    let state = app.state::<FocusState>();
+    let mut data = state.lock();
+    data.pending.push(check);
+    save(app, &data);
+  }
+  drift
+}
+
+/// Ends the session and, for a block, posts its actual duration to the
+/// drift check. A check the backend cannot take now is queued and retried.
+pub async fn stop(app: &AppHandle) -> Result<FocusRecord, String> {
+  let now = Utc::now();
+  let session = {
+    let state = app.state::<FocusState>();
+    let mut data = state.lock();
+    let session = data.session.take().ok_or("No focus session is running")?;
+    save(app, &data);
+    session
+  };
+  let elapsed = session.elapsed_secs(now);
+  log::info!("Focus session {} stopped after {}s", session.id, elapsed);
+  let _ = app.emit(EVENT_CHANGED, None::<FocusStatus>);
+
+  let actual_minutes = minutes(elapsed);
+  let mut drift = None;
+  if let Some(check) = drift_check(&session, actual_minutes) {
+    drift = submit(app, &reqwest::Client::new(), check).await;
+  }
+
+  let record = FocusRecord { session, ended_at: now, actual_minutes, drift };
+  let _ = app.emit(EVENT_ENDED, &record);
+  Ok(record)
+}
+
+/// The drift check for a block that has overrun past the backend's
+/// threshold and not been reported yet
+fn overrun_check(session: &FocusSession, now: DateTime<Utc>) -> Option<DriftCheck> {
+  let FocusTarget::Block { planned_minutes, .. } = &session.target else {
+    return None;
+  };
+  let actual_minutes = minutes(session.elapsed_secs(now));
+  if session.drift_reported || actual_minutes <= planned_minutes + DRIFT_THRESHOLD_MINUTES {
+    return None;
+  }
+  drift_check(session, actual_minutes)
+}
+
+/// The drift check for a running block that has just overrun, marking it
+/// as sent
+fn take_overrun(app: &AppHandle) -> Option<DriftCheck> {
+  let state = app.state::<FocusState>();
+  let mut data = state.lock();
+  let session = data.session.as_mut()?;
+  let check = overrun_check(session, Utc::now())?;
+  session.drift_reported = true;
+  save(app, &data);
+  Some(check)
+}
+
+/// Sends the drift check of a block that is still running once it has
+/// overrun, so the schedule can be adjusted before the block ends. The
+/// answer goes out as `focus://overrun`.
+async fn report_overrun(app: &AppHandle, client: &reqwest::Client) {
+  let Some(check) = take_overrun(app) else {
+    return;
+  };
+  log::info!("Focus session {} overran its block", check.session_id);
+  let session_id = check.session_id.clone();
+  if let Some(drift) = submit(app, client, check).await {
+    let _ = app.emit(EVENT_OVERRUN, serde_json::json!({ "sessionId": session_id, "drift": drift }));
+  }
+}
+
+/// Posts queued checks oldest first, stopping at the first that still
+/// cannot be delivered so they stay in order
+async fn retry_pending(app: &AppHandle, client: &reqwest::Client) {
+  let pending = app.state::<FocusState>().lock().pending.clone();
+  let mut delivered = 0;
+  for check in &pending {
+    if deliver(app, client, check).await.is_none() {
+      break;
+    }
+    delivered += 1;
+  }
+  if delivered > 0 {
+    // Checks queued meanwhile were added at the end
+    let state = app.state::<FocusState>();
+    let mut data = state.lock();
+    data.pending.drain(..delivered);
+    save(app, &data);
+  }
+}
+
+/// Restores a saved session, emits `focus://tick` every second while one
+/// runs, reports blocks that overrun and retries drift checks that could
+/// not be posted
+pub fn spawn_ticker(app: AppHandle) {
+  match focus_path(&app).and_then(|path| read_json::<FocusData>(&path)) {
+    Ok(saved) => *app.state::<FocusState>().lock() = saved,
+    Err(e) => log::warn!("Failed to load focus session: {}", e),
+  }
+
+  tauri::async_runtime::spawn(async move {
+    let client = reqwest::Client::new();
+    let mut interval = tokio::time::interval(TICK_INTERVAL);
+    // After sleep, one tick with the caught-up time rather than a burst
+    interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+    let mut ticks = 0u32;
+    loop {
+      interval.tick().await;
+      if let Some(status) = status(&app).filter(|s| !s.paused) {
+        let _ = app.emit(EVENT_TICK, &status);
+        report_overrun(&app, &client).await;
+      }
+
+      ticks = (ticks + 1) % RETRY_TICKS;
+      let retry = ticks == 0
+        && app.state::<BackendState>().phase() == BackendPhase::Ready
+        && !app.state::<FocusState>().lock().pending.is_empty();
+      if retry {
+        retry_pending(&app, &client).await;
+      }
+    }
+  });
+}
+
+/// Starts timing a block or mission for the signed-in user
+#[tauri::command]
+pub fn focus_start(
+  app: AppHandle,
+  user_id: i64,
+  target: FocusTarget,
+) -> Result<FocusStatus, String> {
+  start(&app, user_id, target)
+}
+
+#[tauri::command]
+pub fn focus_pause(app: AppHandle) -> Result<FocusStatus, String> {
+  update(&app, FocusSession::pause)
+}
+
+#[tauri::command]
+pub fn focus_resume(app: AppHandle) -> Result<FocusStatus, String> {
+  update(&app, FocusSession::resume)
+}
+
+#[tauri::command]
+pub async fn focus_stop(app: AppHandle) -> Result<FocusRecord, String> {
+  stop(&app).await
+}
+
+#[tauri::command]
+pub fn focus_status(app: AppHandle) -> Option<FocusStatus> {
+  status(&app)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn block(planned_minutes: i64) -> FocusTarget {
+    FocusTarget::Block {
+      schedule_date: "2026-10-15".to_string(),
+      start_time: "09:00".to_string(),
+      title: "Calculus".to_string(),
+      planned_minutes,
+    }
+  }
+
+  fn session(target: FocusTarget, started_at: DateTime<Utc>) -> FocusSession {
+    FocusSession {
+      id: "session".to_string(),
+      user_id: 4,
+      target,
+      started_at,
+      accumulated_secs: 0,
+      running_since: Some(started_at),
+      drift_reported: false,
+    }
+  }
+
+  fn start() -> DateTime<Utc> {
+    "2026-10-15T13:00:00Z".parse().unwrap()
+  }
+
+  fn after(secs: i64) -> DateTime<Utc> {
+    start() + chrono::Duration::seconds(secs)
+  }
+
+  #[test]
+  fn elapsed_secs_counts_only_running_time() {
+    let mut session = session(block(30), start());
+    assert_eq!(session.elapsed_secs(after(90)), 90);
+
+    session.pause(after(600)).unwrap();
+    assert_eq!(session.elapsed_secs(after(1800)), 600);
+    assert!(session.pause(after(1800)).is_err());
+
+    session.resume(after(1800)).unwrap();
+    assert_eq!(session.elapsed_secs(after(2700)), 1500);
+    assert!(session.resume(after(2700)).is_err());
+
+    // A clock that went backwards adds nothing
+    assert_eq!(session.elapsed_secs(after(1000)), 600);
+  }
+
+  #[test]
+  fn minutes_rounds_to_the_nearest_minute() {
+    assert_eq!(minutes(0), 0);
+    assert_eq!(minutes(29), 0);
+    assert_eq!(minutes(30), 1);
+    assert_eq!(minutes(89), 1);
+    assert_eq!(minutes(90), 2);
+    assert_eq!(minutes(3600), 60);
+  }
+
+  #[test]
+  fn drift_check_is_only_for_blocks() {
+    let check = drift_check(&session(block(30), start()), 45).unwrap();
+    assert_eq!(check.session_id, "session");
+    assert_eq!(check.user_id, 4);
+    assert_eq!(check.schedule_date, "2026-10-15");
+    assert_eq!(check.block_start_time, "09:00");
+    assert_eq!(check.block_title, "Calculus");
+    assert_eq!((check.planned_duration, check.actual_duration), (30, 45));
+
+    let mission = FocusTarget::Mission { mission_id: 2, title: "Essay".to_string() };
+    assert!(drift_check(&session(mission, start()), 45).is_none());
+  }
+
+  #[test]
+  fn overrun_waits_for_the_threshold() {
+    let mut session = session(block(30), start());
+    let limit = (30 + DRIFT_THRESHOLD_MINUTES) * 60;
+    // 40 minutes, as 40:29 rounds, is not yet an overrun
+    assert!(overrun_check(&session, after(limit)).is_none());
+    assert!(overrun_check(&session, after(limit + 29)).is_none());
+    let check = overrun_check(&session, after(limit + 30)).unwrap();
+    assert_eq!(check.actual_duration, 41);
+
+    // Time spent paused does not count
+    session.pause(after(1200)).unwrap();
+    assert!(overrun_check(&session, after(limit + 600)).is_none());
+
+    session.drift_reported = true;
+    session.resume(after(limit + 600)).unwrap();
+    assert!(overrun_check(&session, after(limit * 2)).is_none());
+  }
+
+  #[test]
+  fn missions_never_overrun() {
+    let mission = FocusTarget::Mission { mission_id: 2, title: "Essay".to_string() };
+    assert!(overrun_check(&session(mission, start()), after(24 * 3600)).is_none());
+  }
+}
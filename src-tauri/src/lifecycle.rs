@@ -0,0 +1,99 @@
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager, RunEvent};
+
+use crate::backend;
+use crate::local_db;
+
+pub const EVENT_SECOND_INSTANCE: &str = "app://second-instance";
+
+#[derive(Clone, Serialize)]
+struct SecondInstancePayload {
+  args: Vec<String>,
+  cwd: String,
+}
+
+/// Makes sure a panicking shell takes its sidecar down with it instead of
+/// leaving an orphaned Node process holding the port.
+pub fn install_panic_hook() {
+  let default_hook = std::panic::take_hook();
+  std::panic::set_hook(Box::new(move |info| {
+    log::error!("Shell panicked: {}", info);
+    backend::kill_sidecar_now();
+    default_hook(info);
+  }));
+}
+
+/// Routes Ctrl+C and termination signals through the normal exit path so
+/// the backend gets a graceful shutdown.
+pub fn spawn_signal_listener(app: AppHandle) {
+  tauri::async_runtime::spawn(async move {
+    wait_for_signal().await;
+    log::info!("Received termination signal, exiting");
+    app.exit(0);
+  });
+}
+
+#[cfg(unix)]
+async fn wait_for_signal() {
+  use tokio::signal::unix::{signal, SignalKind};
+
+  let (Ok(mut terminate), Ok(mut hangup)) =
+    (signal(SignalKind::terminate()), signal(SignalKind::hangup()))
+  else {
+    let _ = tokio::signal::ctrl_c().await;
+    return;
+  };
+  tokio::select! {
+    _ = tokio::signal::ctrl_c() => {}
+    _ = terminate.recv() => {}
+    _ = hangup.recv() => {}
+  }
+}
+
+#[cfg(not(unix))]
+async fn wait_for_signal() {
+  let _ = tokio::signal::ctrl_c().await;
+}
+
+/// Surfaces whichever window is current: the splash while the backend is
+/// still booting, the main window after, even if it was hidden to the tray
+pub fn show_current_window(app: &AppHandle) {
+  let window = app.get_webview_window("splash").or_else(|| app.get_webview_window("main"));
+  if let Some(window) = window {
+    let _ = window.unminimize();
+    let _ = window.show();
+    let _ = window.set_focus();
+  }
+}
+
+/// Called in the running instance when Forge is launched again. The new
+/// process exits right away; we surface the current window and pass its
+/// arguments on to the UI.
+pub fn on_second_instance(app: &AppHandle, args: Vec<String>, cwd: String) {
+  log::info!("Second launch forwarded to this instance: {:?}", args);
+  show_current_window(app);
+  let _ = app.emit(EVENT_SECOND_INSTANCE, SecondInstancePayload { args, cwd });
+}
+
+pub fn handle_run_event(app: &AppHandle, event: RunEvent) {
+  match event {
+    // Covers every way out: last window closed, `app.exit`, OS logout.
+    // Hold the exit until the backend is down, then ask again.
+    RunEvent::ExitRequested { api, code, .. } => {
+      if !backend::is_running(app) && !local_db::is_running(app) {
+        return;
+      }
+      api.prevent_exit();
+      let app = app.clone();
+      tauri::async_runtime::spawn(async move {
+        backend::shutdown_backend(&app, backend::SHUTDOWN_GRACE).await;
+        // Only after the backend has closed its connections
+        local_db::stop(&app).await;
+        app.exit(code.unwrap_or(0));
+      });
+    }
+    // Last chance if something skipped `ExitRequested`
+    RunEvent::Exit => backend::kill_sidecar_now(),
+    _ => {}
+  }
+}
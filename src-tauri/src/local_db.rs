@@ -0,0 +1,224 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use postgresql_embedded::{PostgreSQL, SettingsBuilder, Status, Version, VersionReq};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+use tokio::sync::Mutex;
+
+use crate::backend;
+use crate::config;
+use crate::secrets;
+
+const DATABASE_NAME: &str = "forge";
+// Data directories are tied to a major version, so never float past it
+const POSTGRES_VERSION: &str = "=16";
+const PASSWORD_SECRET: &str = "FORGE_LOCAL_DB_PASSWORD";
+const START_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// A PostgreSQL server owned by the shell, used when the user has not
+/// configured a DATABASE_URL of their own. Binaries are downloaded on first
+/// use, which fails with an explanation when offline, and the cluster lives
+/// under the app data directory.
+pub struct LocalDatabase {
+  server: Mutex<Option<PostgreSQL>>,
+  url: std::sync::Mutex<Option<String>>,
+}
+
+impl LocalDatabase {
+  pub fn new() -> Self {
+    Self {
+      server: Mutex::new(None),
+      url: std::sync::Mutex::new(None),
+    }
+  }
+
+  /// Connection string of the running server, if it has been started
+  pub fn url(&self) -> Option<String> {
+    self.url.lock().unwrap().clone()
+  }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DatabaseMode {
+  External,
+  Embedded,
+}
+
+impl DatabaseMode {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      DatabaseMode::External => "external",
+      DatabaseMode::Embedded => "embedded",
+    }
+  }
+}
+
+/// The user's own DATABASE_URL always wins; the embedded server is only a
+/// fallback.
+pub fn mode(app: &AppHandle) -> DatabaseMode {
+  match config::read_env_file(app) {
+    Ok(env) if env.database_url.is_some() => DatabaseMode::External,
+    _ => DatabaseMode::Embedded,
+  }
+}
+
+pub fn root_dir(app: &AppHandle) -> Result<PathBuf, String> {
+  app
+    .path()
+    .app_data_dir()
+    .map(|dir| dir.join("postgres"))
+    .map_err(|e| format!("Could not determine the data directory: {}", e))
+}
+
+pub fn data_dir(app: &AppHandle) -> Result<PathBuf, String> {
+  Ok(root_dir(app)?.join("data"))
+}
+
+fn password(app: &AppHandle) -> Result<String, String> {
+  let stored = secrets::load(app)?;
+  if let Some(password) = stored.get(PASSWORD_SECRET) {
+    return Ok(password.clone());
+  }
+  let password = uuid::Uuid::new_v4().simple().to_string();
+  secrets::update(app, &[(PASSWORD_SECRET, Some(password.clone()))])?;
+  Ok(password)
+}
+
+/// Whether a PostgreSQL build matching `version` was downloaded before.
+/// Each one is unpacked into a directory named after its exact version.
+fn is_installed(installation_dir: &Path, version: &VersionReq) -> bool {
+  let Ok(entries) = fs::read_dir(installation_dir) else {
+    return false;
+  };
+  entries.filter_map(Result::ok).any(|entry| {
+    entry.file_type().is_ok_and(|t| t.is_dir())
+      && Version::parse(&entry.file_name().to_string_lossy()).is_ok_and(|v| version.matches(&v))
+  })
+}
+
+/// Installs, initializes and starts the embedded server as needed and
+/// returns its connection string. Cheap when it is already running.
+pub async fn ensure_started(app: &AppHandle) -> Result<String, String> {
+  let state = app.state::<LocalDatabase>();
+  let mut server = state.server.lock().await;
+  if let (Some(running), Some(url)) = (server.as_ref(), state.url()) {
+    if running.status() == Status::Started {
+      return Ok(url);
+    }
+  }
+
+  let root = root_dir(app)?;
+  let installation_dir = app
+    .path()
+    .app_local_data_dir()
+    .map_err(|e| format!("Could not determine the local data directory: {}", e))?
+    .join("postgresql");
+  let port = backend::pick_free_port()
+    .map_err(|e| format!("Could not allocate a port for the local database: {}", e))?;
+  let version = VersionReq::parse(POSTGRES_VERSION).map_err(|e| e.to_string())?;
+  let installed = is_installed(&installation_dir, &version);
+  let password_file = root.join(".pgpass");
+
+  let settings = SettingsBuilder::new()
+    .version(version)
+    .installation_dir(installation_dir)
+    .data_dir(root.join("data"))
+    .password_file(&password_file)
+    .host("127.0.0.1")
+    .port(port)
+    .password(password(app)?)
+    .temporary(false)
+    .timeout(Some(START_TIMEOUT))
+    .build();
+
+  fs::create_dir_all(&root).map_err(|e| format!("Could not create {}: {}", root.display(), e))?;
+  let mut postgres = PostgreSQL::new(settings);
+
+  if installed {
+    log::info!("Preparing local database");
+  } else {
+    log::info!("Downloading PostgreSQL for the local database");
+  }
+  postgres.setup().await.map_err(|e| {
+    if installed {
+      format!("Could not set up the local database: {}", e)
+    } else {
+      // The binaries are not shipped with the app, so the very first launch
+      // needs a connection
+      format!(
+        "Forge downloads PostgreSQL the first time it starts and the download failed ({}). \
+         Connect to the internet and retry, or set DATABASE_URL in Settings to use your own \
+         database.",
+        e
+      )
+    }
+  })?;
+  // Only needed by initdb; the password itself lives in the vault
+  let _ = fs::remove_file(&password_file);
+
+  // A previous session that died may have left its server running
+  if postgres.status() == Status::Started {
+    let _ = postgres.stop().await;
+  }
+  postgres
+    .start()
+    .await
+    .map_err(|e| format!("Could not start the local database: {}", e))?;
+
+  let exists = postgres
+    .database_exists(DATABASE_NAME)
+    .await
+    .map_err(|e| format!("Could not query the local database: {}", e))?;
+  if !exists {
+    postgres
+      .create_database(DATABASE_NAME)
+      .await
+      .map_err(|e| format!("Could not create the local database: {}", e))?;
+  }
+
+  let url = postgres.settings().url(DATABASE_NAME);
+  log::info!("Local database running on port {}", port);
+  *state.url.lock().unwrap() = Some(url.clone());
+  *server = Some(postgres);
+  Ok(url)
+}
+
+pub fn is_running(app: &AppHandle) -> bool {
+  app.state::<LocalDatabase>().url().is_some()
+}
+
+pub async fn stop(app: &AppHandle) {
+  let state = app.state::<LocalDatabase>();
+  let mut server = state.server.lock().await;
+  state.url.lock().unwrap().take();
+  if let Some(postgres) = server.take() {
+    match postgres.stop().await {
+      Ok(()) => log::info!("Local database stopped"),
+      Err(e) => log::warn!("Failed to stop local database: {}", e),
+    }
+  }
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DatabaseStatus {
+  pub mode: DatabaseMode,
+  pub running: bool,
+  pub data_dir: Option<String>,
+}
+
+#[tauri::command]
+pub fn database_status(app: AppHandle) -> DatabaseStatus {
+  let mode = mode(&app);
+  DatabaseStatus {
+    mode,
+    running: mode == DatabaseMode::External || is_running(&app),
+    data_dir: match mode {
+      DatabaseMode::Embedded => data_dir(&app).ok().map(|d| d.to_string_lossy().into_owned()),
+      DatabaseMode::External => None,
+    },
+  }
+}
@@ -0,0 +1,217 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
+
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use serde::Serialize;
+use tauri::{AppHandle, Manager};
+
+const LOG_FILE_NAME: &str = "forge.log";
+const MAX_FILE_SIZE: u64 = 5 * 1024 * 1024;
+// Rotated files are forge.1.log (newest) up to forge.5.log (oldest)
+const MAX_ROTATED_FILES: usize = 5;
+const MAX_AGE: Duration = Duration::from_secs(14 * 24 * 60 * 60);
+const DEFAULT_TAIL: usize = 500;
+
+pub const BACKEND_TARGET: &str = "backend";
+
+/// Writes every record to the console and to a size-rotated file in the app
+/// log directory. The console alone is useless in Windows release builds,
+/// which have no attached terminal.
+struct FileLogger {
+  dir: PathBuf,
+  file: Mutex<LogFile>,
+}
+
+struct LogFile {
+  file: File,
+  size: u64,
+}
+
+fn rotated_path(dir: &Path, index: usize) -> PathBuf {
+  dir.join(format!("forge.{}.log", index))
+}
+
+fn open_log_file(dir: &Path) -> std::io::Result<LogFile> {
+  let file = OpenOptions::new()
+    .create(true)
+    .append(true)
+    .open(dir.join(LOG_FILE_NAME))?;
+  let size = file.metadata()?.len();
+  Ok(LogFile { file, size })
+}
+
+/// Deletes rotated files that are past `MAX_AGE`. The live file is left
+/// alone; it is rotated away by size instead.
+fn prune_old_files(dir: &Path) {
+  let now = SystemTime::now();
+  for index in 1..=MAX_ROTATED_FILES {
+    let path = rotated_path(dir, index);
+    let expired = fs::metadata(&path)
+      .and_then(|m| m.modified())
+      .ok()
+      .and_then(|modified| now.duration_since(modified).ok())
+      .is_some_and(|age| age > MAX_AGE);
+    if expired {
+      let _ = fs::remove_file(&path);
+    }
+  }
+}
+
+impl FileLogger {
+  fn rotate(&self, current: &mut LogFile) -> std::io::Result<()> {
+    let _ = fs::remove_file(rotated_path(&self.dir, MAX_ROTATED_FILES));
+    for index in (1..MAX_ROTATED_FILES).rev() {
+      let from = rotated_path(&self.dir, index);
+      if from.exists() {
+        fs::rename(&from, rotated_path(&self.dir, index + 1))?;
+      }
+    }
+    fs::rename(self.dir.join(LOG_FILE_NAME), rotated_path(&self.dir, 1))?;
+    *current = open_log_file(&self.dir)?;
+    Ok(())
+  }
+}
+
+impl Log for FileLogger {
+  fn enabled(&self, metadata: &Metadata) -> bool {
+    metadata.level() <= log::max_level()
+  }
+
+  fn log(&self, record: &Record) {
+    if !self.enabled(record.metadata()) {
+      return;
+    }
+
+    let line = format!(
+      "{} {:<5} [{}] {}\n",
+      chrono::Local::now().format("%Y-%m-%d %H:%M:%S%.3f"),
+      record.level(),
+      record.target(),
+      record.args()
+    );
+
+    if record.level() <= Level::Warn {
+      eprint!("{}", line);
+    } else {
+      print!("{}", line);
+    }
+
+    let mut current = self.file.lock().unwrap();
+    if current.size + line.len() as u64 > MAX_FILE_SIZE {
+      if let Err(e) = self.rotate(&mut current) {
+        eprintln!("Failed to rotate log file: {}", e);
+      }
+    }
+    if current.file.write_all(line.as_bytes()).is_ok() {
+      current.size += line.len() as u64;
+    }
+  }
+
+  fn flush(&self) {
+    let _ = self.file.lock().unwrap().file.flush();
+  }
+}
+
+/// Installs the file logger as the global `log` backend. Must run before
+/// anything else logs, otherwise those records are silently dropped.
+pub fn init(app: &AppHandle) -> Result<(), String> {
+  let dir = app
+    .path()
+    .app_log_dir()
+    .map_err(|e| format!("Failed to resolve log directory: {}", e))?;
+  fs::create_dir_all(&dir).map_err(|e| format!("Failed to create log directory: {}", e))?;
+  prune_old_files(&dir);
+
+  let file = open_log_file(&dir).map_err(|e| format!("Failed to open log file: {}", e))?;
+  let logger = FileLogger { dir, file: Mutex::new(file) };
+
+  log::set_boxed_logger(Box::new(logger)).map_err(|e| e.to_string())?;
+  log::set_max_level(if cfg!(debug_assertions) {
+    LevelFilter::Debug
+  } else {
+    LevelFilter::Info
+  });
+  Ok(())
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct LogEntry {
+  pub timestamp: String,
+  pub level: String,
+  pub target: String,
+  pub message: String,
+}
+
+fn parse_line(line: &str) -> Option<LogEntry> {
+  // "<date> <time> <LEVEL> [<target>] <message>"
+  let mut parts = line.splitn(4, ' ');
+  let date = parts.next()?;
+  let time = parts.next()?;
+  let level = parts.next()?;
+  let rest = parts.next()?.trim_start();
+  let rest = rest.strip_prefix('[')?;
+  let (target, message) = rest.split_once("] ")?;
+  Some(LogEntry {
+    timestamp: format!("{} {}", date, time),
+    level: level.to_string(),
+    target: target.to_string(),
+    message: message.to_string(),
+  })
+}
+
+/// The last `limit` entries at or above `min_level` in `dir`, oldest first.
+/// Reads the live file and then older rotations until enough entries match.
+fn tail_entries(dir: &Path, min_level: Level, limit: usize) -> Vec<LogEntry> {
+  let files = std::iter::once(dir.join(LOG_FILE_NAME))
+    .chain((1..=MAX_ROTATED_FILES).map(|index| rotated_path(dir, index)));
+
+  let mut entries = Vec::new();
+  for path in files {
+    let Ok(contents) = fs::read_to_string(&path) else {
+      continue;
+    };
+    for entry in contents.lines().rev().filter_map(parse_line) {
+      let matches = Level::from_str(&entry.level).is_ok_and(|l| l <= min_level);
+      if matches {
+        entries.push(entry);
+        if entries.len() == limit {
+          break;
+        }
+      }
+    }
+    if entries.len() == limit {
+      break;
+    }
+  }
+
+  entries.reverse();
+  entries
+}
+
+/// Returns the last `limit` entries at or above `level`, oldest first.
+/// The rotated files can add up to tens of megabytes, so they are read off
+/// the main thread.
+#[tauri::command]
+pub async fn read_logs(
+  app: AppHandle,
+  level: Option<String>,
+  limit: Option<usize>,
+) -> Result<Vec<LogEntry>, String> {
+  let min_level = match level {
+    Some(level) => Level::from_str(&level).map_err(|_| format!("Unknown log level: {}", level))?,
+    None => Level::Trace,
+  };
+  let limit = limit.unwrap_or(DEFAULT_TAIL);
+  let dir = app
+    .path()
+    .app_log_dir()
+    .map_err(|e| format!("Failed to resolve log directory: {}", e))?;
+
+  tauri::async_runtime::spawn_blocking(move || tail_entries(&dir, min_level, limit))
+    .await
+    .map_err(|e| format!("Reading logs failed: {}", e))
+}
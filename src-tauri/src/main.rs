@@ -3,122 +3,172 @@
   windows_subsystem = "windows"
 )]
 
-use std::sync::Mutex;
+mod backend;
+mod backup;
+mod backup_schedule;
+mod chunker;
+mod config;
+mod daily_schedule;
+mod drift;
+mod extract;
+mod focus;
+mod lifecycle;
+mod local_db;
+mod logging;
+mod notifications;
+mod proofs;
+mod protocol;
+mod secrets;
+mod splash;
+mod store;
+#[cfg(test)]
+mod test_util;
+mod transfer;
+mod tray;
+mod vectors;
+
 use tauri::Manager;
-use tauri_plugin_shell::ShellExt;
 
-struct BackendState {
-  child: Mutex<Option<tauri_plugin_shell::process::CommandChild>>,
-  started: Mutex<bool>,
-}
+use backend::BackendState;
+use backup::BackupState;
+use focus::FocusState;
+use local_db::LocalDatabase;
+use notifications::NotificationState;
+use tray::TrayState;
+use vectors::VectorIndex;
 
-#[tauri::command]
-async fn start_backend(app: tauri::AppHandle) -> Result<String, String> {
-  let state = app.state::<BackendState>();
-  
-  let mut started_guard = state.started.lock().unwrap();
-  if *started_guard {
-    return Ok("Backend already running".to_string());
-  }
-  
-  *started_guard = true;
-  drop(started_guard);
-  
-  let shell = app.shell();
-  
-  // Get the app's config directory for storing .env file
-  let app_dir = app.path().app_config_dir().unwrap_or_default();
-  
-  let mut sidecar = shell
-    .sidecar("forge-backend")
-    .map_err(|e| {
-      let mut started = state.started.lock().unwrap();
-      *started = false;
-      format!("Failed to create sidecar command: {}", e)
-    })?;
-  
-  // Set the working directory to the app's config directory
-  // This ensures the backend can find the .env file
-  sidecar = sidecar.current_dir(&app_dir);
-  
-  let (mut rx, child) = sidecar
-    .spawn()
-    .map_err(|e| {
-      let mut started = state.started.lock().unwrap();
-      *started = false;
-      format!("Failed to spawn sidecar: {}", e)
-    })?;
-  
-  {
-    let mut child_guard = state.child.lock().unwrap();
-    *child_guard = Some(child);
+fn main() {
+  // Helper mode for the sidecar; must not touch the single-instance lock
+  if std::env::args().nth(1).as_deref() == Some(extract::HELPER_FLAG) {
+    std::process::exit(extract::run_helper());
   }
-  
-  tauri::async_runtime::spawn(async move {
-    while let Some(event) = rx.recv().await {
-      match event {
-        tauri_plugin_shell::process::CommandEvent::Stdout(line) => {
-          println!("[Backend] {}", String::from_utf8_lossy(&line));
-        }
-        tauri_plugin_shell::process::CommandEvent::Stderr(line) => {
-          eprintln!("[Backend Error] {}", String::from_utf8_lossy(&line));
-        }
-        tauri_plugin_shell::process::CommandEvent::Error(err) => {
-          eprintln!("[Backend Fatal] {}", err);
-        }
-        _ => {}
-      }
-    }
-  });
-  
-  Ok("Backend started".to_string())
-}
 
-fn stop_backend_sync(app: &tauri::AppHandle) {
-  let state = app.state::<BackendState>();
-  let mut child_guard = state.child.lock().unwrap();
-  
-  if let Some(child) = child_guard.take() {
-    let _ = child.kill();
-    println!("[Forge] Backend stopped");
-  }
-  
-  let mut started = state.started.lock().unwrap();
-  *started = false;
-}
+  lifecycle::install_panic_hook();
 
-#[tauri::command]
-async fn stop_backend(app: tauri::AppHandle) -> Result<String, String> {
-  stop_backend_sync(&app);
-  Ok("Backend stopped".to_string())
-}
+  let port = backend::pick_free_port().expect("failed to allocate a port for the backend");
+  let state = BackendState::new(port);
+
+  // Every webview learns the backend URL before any page script runs, so
+  // the client never has to guess the port
+  let backend_url = tauri::plugin::Builder::<tauri::Wry, ()>::new("backend-url")
+    .js_init_script(format!(
+      "window.__FORGE_BACKEND_URL__ = {};",
+      serde_json::to_string(&state.url()).unwrap()
+    ))
+    .build();
 
-fn main() {
   tauri::Builder::default()
+    // Must come first so a second launch exits before spawning its own backend
+    .plugin(tauri_plugin_single_instance::init(lifecycle::on_second_instance))
     .plugin(tauri_plugin_shell::init())
-    .manage(BackendState {
-      child: Mutex::new(None),
-      started: Mutex::new(false),
-    })
-    .invoke_handler(tauri::generate_handler![start_backend, stop_backend])
+    .plugin(tauri_plugin_notification::init())
+    .plugin(backend_url)
+    .register_asynchronous_uri_scheme_protocol(protocol::SCHEME, protocol::handle)
+    .manage(state)
+    .manage(LocalDatabase::new())
+    .manage(BackupState::new())
+    .manage(VectorIndex::new())
+    .manage(NotificationState::new())
+    .manage(FocusState::default())
+    .manage(TrayState::default())
+    .invoke_handler(tauri::generate_handler![
+      backend::start_backend,
+      backend::stop_backend,
+      backend::backend_state,
+      backend::backend_status,
+      backend::get_backend_url,
+      logging::read_logs,
+      config::get_backend_config,
+      config::validate_backend_config,
+      config::save_backend_config,
+      secrets::list_secrets,
+      secrets::set_secret,
+      secrets::delete_secret,
+      local_db::database_status,
+      backup::create_backup,
+      backup::restore_backup,
+      backup::list_backups,
+      backup_schedule::get_backup_settings,
+      backup_schedule::save_backup_settings,
+      backup_schedule::backup_status,
+      daily_schedule::schedule_trigger_status,
+      transfer::export_data,
+      transfer::import_data,
+      proofs::save_proof,
+      proofs::save_proof_file,
+      proofs::get_proof,
+      extract::extract_text,
+      chunker::chunk_document,
+      vectors::vector_insert,
+      vectors::vector_query,
+      vectors::vector_delete,
+      notifications::schedule_reminder,
+      notifications::cancel_reminder,
+      notifications::cancel_reminders,
+      notifications::list_reminders,
+      focus::focus_start,
+      focus::focus_pause,
+      focus::focus_resume,
+      focus::focus_stop,
+      focus::focus_status,
+      drift::compute_drift,
+      tray::set_tray_summary,
+      tray::get_tray_settings,
+      tray::save_tray_settings,
+      splash::retry_backend
+    ])
     .setup(|app| {
       let handle = app.handle().clone();
-      let window = app.get_webview_window("main").unwrap();
-      tauri::async_runtime::spawn(async move {
-        std::thread::sleep(std::time::Duration::from_millis(500));
-        if let Err(e) = start_backend(handle).await {
-          eprintln!("Failed to start backend: {}", e);
-        }
-        std::thread::sleep(std::time::Duration::from_millis(1000));
-        let _ = window.show();
-      });
+      if let Err(e) = logging::init(&handle) {
+        eprintln!("Failed to initialize logging: {}", e);
+      }
+      backend::reap_stale_sidecar(&handle);
+      if let Err(e) = config::seed_env_file(&handle) {
+        log::warn!("{}", e);
+      }
+      if let Err(e) = secrets::migrate_from_env_file(&handle) {
+        log::error!("{}", e);
+      }
+      lifecycle::spawn_signal_listener(handle.clone());
+      backup_schedule::spawn_scheduler(handle.clone());
+      daily_schedule::spawn_trigger(handle.clone());
+      notifications::spawn_notifier(handle.clone());
+      focus::spawn_ticker(handle.clone());
+      if let Err(e) = vectors::spawn_server(handle.clone()) {
+        log::error!("{}", e);
+      }
+      tray::create(&handle)?;
+      splash::create(&handle)?;
+      tauri::async_runtime::spawn(splash::boot(handle));
       Ok(())
     })
     .on_window_event(|window, event| {
-      if let tauri::WindowEvent::CloseRequested { .. } = event {
-        stop_backend_sync(window.app_handle());
+      if let tauri::WindowEvent::CloseRequested { api, .. } = event {
+        let app = window.app_handle().clone();
+        // The splash closes itself once the main window is shown; closing it
+        // before then means the user gave up on startup
+        let main_visible = app
+          .get_webview_window("main")
+          .and_then(|main| main.is_visible().ok())
+          .unwrap_or(false);
+        if window.label() != "main" && main_visible {
+          return;
+        }
+        if window.label() == "main" && tray::load_settings(&app).close_to_tray {
+          api.prevent_close();
+          let _ = window.hide();
+          return;
+        }
+
+        // Keep the window until the backend has shut down cleanly
+        api.prevent_close();
+        tauri::async_runtime::spawn(async move {
+          backend::shutdown_backend(&app, backend::SHUTDOWN_GRACE).await;
+          app.exit(0);
+        });
       }
     })
-    .run(tauri::generate_context!())
-    .expect("error while running tauri application");
+    .build(tauri::generate_context!())
+    .expect("error while building tauri application")
+    .run(lifecycle::handle_run_event);
 }
@@ -0,0 +1,371 @@
+use std::path::PathBuf;
+use std::sync::{Mutex, MutexGuard};
+use std::time::Duration;
+
+use chrono::{DateTime, Days, Local, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_notification::NotificationExt;
+use tokio::sync::Notify;
+
+use crate::store::{read_json, write_json};
+
+const REMINDERS_FILE_NAME: &str = "reminders.json";
+pub const EVENT_FIRED: &str = "notifications://fired";
+// Re-checked at least this often so a machine waking from sleep fires what
+// came due meanwhile without waiting out a long timer
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+// Reminders missed by more than this, because the app was closed or the
+// machine asleep, are dropped rather than shown late
+const STALE_AFTER: chrono::Duration = chrono::Duration::minutes(15);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ReminderKind {
+  BlockStart,
+  MissionDue,
+  FeedbackPrompt,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Reminder {
+  // Callers pick stable ids such as `block:2025-01-31:4` so rescheduling
+  // replaces the old reminder; one is generated when empty
+  #[serde(default)]
+  pub id: String,
+  pub kind: ReminderKind,
+  pub title: String,
+  #[serde(default)]
+  pub body: String,
+  pub fire_at: DateTime<Utc>,
+  // Fires again at the same local time the next day, as the end-of-day
+  // feedback prompt does
+  #[serde(default)]
+  pub repeat_daily: bool,
+  // Passed back untouched in the `notifications://fired` event
+  #[serde(default)]
+  pub data: Value,
+}
+
+/// Pending reminders, mirrored to `reminders.json` in the data directory
+pub struct NotificationState {
+  reminders: Mutex<Vec<Reminder>>,
+  changed: Notify,
+}
+
+impl NotificationState {
+  pub fn new() -> Self {
+    Self { reminders: Mutex::new(Vec::new()), changed: Notify::new() }
+  }
+
+  fn lock(&self) -> MutexGuard<'_, Vec<Reminder>> {
+    self.reminders.lock().unwrap_or_else(|e| e.into_inner())
+  }
+}
+
+fn reminders_path(app: &AppHandle) -> Result<PathBuf, String> {
+  app
+    .path()
+    .app_data_dir()
+    .map(|dir| dir.join(REMINDERS_FILE_NAME))
+    .map_err(|e| format!("Could not determine the data directory: {}", e))
+}
+
+fn save(app: &AppHandle, reminders: &[Reminder]) -> Result<(), String> {
+  write_json(&reminders_path(app)?, &reminders)
+}
+
+/// Moves a daily reminder to its first occurrence after `now`, keeping the
+/// local wall-clock time across DST changes. A time the clocks skip fires
+/// an hour later that day; a repeated one fires on its first pass.
+fn next_daily<Tz: TimeZone>(
+  fire_at: DateTime<Utc>,
+  now: DateTime<Utc>,
+  zone: &Tz,
+) -> DateTime<Utc> {
+  if fire_at > now {
+    return fire_at;
+  }
+  let local = fire_at.with_timezone(zone).naive_local();
+  for days in 1.. {
+    let Some(day) = local.checked_add_days(Days::new(days)) else {
+      break;
+    };
+    let next = zone
+      .from_local_datetime(&day)
+      .earliest()
+      .or_else(|| zone.from_local_datetime(&(day + chrono::Duration::hours(1))).earliest());
+    if let Some(next) = next.map(|next| next.with_timezone(&Utc)).filter(|next| *next > now) {
+      return next;
+    }
+  }
+  fire_at
+}
+
+fn show(app: &AppHandle, reminder: &Reminder) {
+  let mut notification = app.notification().builder().title(&reminder.title);
+  if !reminder.body.is_empty() {
+    notification = notification.body(&reminder.body);
+  }
+  if let Err(e) = notification.show() {
+    log::warn!("Failed to show notification {}: {}", reminder.id, e);
+  }
+  let _ = app.emit(EVENT_FIRED, reminder);
+}
+
+/// Takes the reminders that have come due by `now` out of `reminders`,
+/// moving daily ones on to their next occurrence. Returns the ones to show,
+/// which leaves out any missed by more than `STALE_AFTER`, or `None` when
+/// nothing came due.
+fn take_due<Tz: TimeZone>(
+  reminders: &mut Vec<Reminder>,
+  now: DateTime<Utc>,
+  zone: &Tz,
+) -> Option<Vec<Reminder>> {
+  let mut due: Option<Vec<Reminder>> = None;
+  reminders.retain_mut(|reminder| {
+    if reminder.fire_at > now {
+      return true;
+    }
+    let due = due.get_or_insert_with(Vec::new);
+    if now - reminder.fire_at > STALE_AFTER {
+      log::info!("Skipping missed reminder {} due at {}", reminder.id, reminder.fire_at);
+    } else {
+      due.push(reminder.clone());
+    }
+    if reminder.repeat_daily {
+      reminder.fire_at = next_daily(reminder.fire_at, now, zone);
+    }
+    reminder.repeat_daily
+  });
+  due
+}
+
+/// Shows every reminder that has come due and returns how long until the
+/// next one
+fn fire_due(app: &AppHandle) -> Option<Duration> {
+  let state = app.state::<NotificationState>();
+  let mut reminders = state.lock();
+  let now = Utc::now();
+
+  if let Some(due) = take_due(&mut reminders, now, &Local) {
+    for reminder in &due {
+      show(app, reminder);
+    }
+    if let Err(e) = save(app, &reminders) {
+      log::warn!("Failed to save reminders: {}", e);
+    }
+  }
+  reminders.iter().map(|r| r.fire_at).min().map(|next| (next - now).to_std().unwrap_or_default())
+}
+
+/// Loads the saved reminders and fires them as they come due, whether or
+/// not any window is visible
+pub fn spawn_notifier(app: AppHandle) {
+  let saved = reminders_path(&app).and_then(|path| read_json::<Vec<Reminder>>(&path));
+  match saved {
+    Ok(saved) => *app.state::<NotificationState>().lock() = saved,
+    Err(e) => log::warn!("Failed to load reminders: {}", e),
+  }
+
+  tauri::async_runtime::spawn(async move {
+    let state = app.state::<NotificationState>();
+    loop {
+      let wait = fire_due(&app).map_or(CHECK_INTERVAL, |wait| wait.min(CHECK_INTERVAL));
+      tokio::select! {
+        _ = tokio::time::sleep(wait) => {}
+        _ = state.changed.notified() => {}
+      }
+    }
+  });
+}
+
+/// Adds a reminder, replacing any with the same id
+#[tauri::command]
+pub fn schedule_reminder(app: AppHandle, mut reminder: Reminder) -> Result<Reminder, String> {
+  if reminder.title.trim().is_empty() {
+    return Err("Reminders need a title".to_string());
+  }
+  let now = Utc::now();
+  if reminder.repeat_daily {
+    reminder.fire_at = next_daily(reminder.fire_at, now, &Local);
+  } else if now - reminder.fire_at > STALE_AFTER {
+    return Err(format!("{} is already in the past", reminder.fire_at));
+  }
+  if reminder.id.is_empty() {
+    reminder.id = uuid::Uuid::new_v4().to_string();
+  }
+
+  let state = app.state::<NotificationState>();
+  {
+    let mut reminders = state.lock();
+    reminders.retain(|r| r.id != reminder.id);
+    reminders.push(reminder.clone());
+    save(&app, &reminders)?;
+  }
+  state.changed.notify_one();
+  Ok(reminder)
+}
+
+/// Returns whether a reminder with that id was pending
+#[tauri::command]
+pub fn cancel_reminder(app: AppHandle, id: String) -> Result<bool, String> {
+  let state = app.state::<NotificationState>();
+  {
+    let mut reminders = state.lock();
+    let before = reminders.len();
+    reminders.retain(|r| r.id != id);
+    if reminders.len() == before {
+      return Ok(false);
+    }
+    save(&app, &reminders)?;
+  }
+  state.changed.notify_one();
+  Ok(true)
+}
+
+/// Cancels every pending reminder of one kind, such as all block starts
+/// when the day's schedule is regenerated. Returns how many were removed.
+#[tauri::command]
+pub fn cancel_reminders(app: AppHandle, kind: ReminderKind) -> Result<usize, String> {
+  let state = app.state::<NotificationState>();
+  let removed = {
+    let mut reminders = state.lock();
+    let before = reminders.len();
+    reminders.retain(|r| r.kind != kind);
+    let removed = before - reminders.len();
+    if removed > 0 {
+      save(&app, &reminders)?;
+    }
+    removed
+  };
+  if removed > 0 {
+    state.changed.notify_one();
+  }
+  Ok(removed)
+}
+
+/// Pending reminders, soonest first
+#[tauri::command]
+pub fn list_reminders(app: AppHandle) -> Vec<Reminder> {
+  let mut reminders = app.state::<NotificationState>().lock().clone();
+  reminders.sort_by_key(|r| r.fire_at);
+  reminders
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test_util::{eastern_utc, Eastern};
+
+  fn reminder(id: &str, fire_at: DateTime<Utc>, repeat_daily: bool) -> Reminder {
+    Reminder {
+      id: id.to_string(),
+      kind: ReminderKind::BlockStart,
+      title: format!("Reminder {}", id),
+      body: String::new(),
+      fire_at,
+      repeat_daily,
+      data: Value::Null,
+    }
+  }
+
+  fn ids(reminders: &[Reminder]) -> Vec<&str> {
+    reminders.iter().map(|r| r.id.as_str()).collect()
+  }
+
+  #[test]
+  fn next_daily_leaves_future_times_alone() {
+    let at = eastern_utc(2026, 6, 10, 21, 0);
+    assert_eq!(next_daily(at, eastern_utc(2026, 6, 10, 9, 0), &Eastern), at);
+  }
+
+  #[test]
+  fn next_daily_moves_to_the_next_day_at_the_same_time() {
+    let at = eastern_utc(2026, 6, 10, 21, 0);
+    assert_eq!(next_daily(at, at, &Eastern), eastern_utc(2026, 6, 11, 21, 0));
+    // Several days missed while the app was closed
+    let now = eastern_utc(2026, 6, 14, 22, 0);
+    assert_eq!(next_daily(at, now, &Eastern), eastern_utc(2026, 6, 15, 21, 0));
+  }
+
+  #[test]
+  fn next_daily_keeps_the_local_time_across_dst() {
+    let at = eastern_utc(2026, 3, 7, 21, 0);
+    let next = next_daily(at, at, &Eastern);
+    assert_eq!(next, eastern_utc(2026, 3, 8, 21, 0));
+    // 23 hours later in UTC, since the clocks went forward overnight
+    assert_eq!(next - at, chrono::Duration::hours(23));
+
+    let at = eastern_utc(2026, 10, 31, 21, 0);
+    assert_eq!(next_daily(at, at, &Eastern) - at, chrono::Duration::hours(25));
+  }
+
+  #[test]
+  fn next_daily_shifts_a_skipped_time_by_an_hour() {
+    // 02:30 does not exist on 8 March 2026
+    let at = eastern_utc(2026, 3, 7, 2, 30);
+    assert_eq!(next_daily(at, at, &Eastern), eastern_utc(2026, 3, 8, 3, 30));
+    // and keeps the later time from then on
+    let next = next_daily(eastern_utc(2026, 3, 8, 3, 30), eastern_utc(2026, 3, 8, 3, 30), &Eastern);
+    assert_eq!(next, eastern_utc(2026, 3, 9, 3, 30));
+  }
+
+  #[test]
+  fn next_daily_takes_the_first_of_a_repeated_time() {
+    // 01:30 happens twice on 1 November 2026
+    let at = eastern_utc(2026, 10, 31, 1, 30);
+    let next = next_daily(at, at, &Eastern);
+    assert_eq!(next, eastern_utc(2026, 11, 1, 1, 30));
+    assert_eq!(next - at, chrono::Duration::hours(24));
+  }
+
+  #[test]
+  fn take_due_is_none_before_anything_is_due() {
+    let now = eastern_utc(2026, 6, 10, 9, 0);
+    let mut reminders = vec![reminder("later", eastern_utc(2026, 6, 10, 9, 1), false)];
+    assert!(take_due(&mut reminders, now, &Eastern).is_none());
+    assert_eq!(ids(&reminders), ["later"]);
+  }
+
+  #[test]
+  fn take_due_shows_and_removes_one_off_reminders() {
+    let now = eastern_utc(2026, 6, 10, 9, 0);
+    let mut reminders = vec![
+      reminder("now", now, false),
+      reminder("late", now - STALE_AFTER, false),
+      reminder("later", eastern_utc(2026, 6, 10, 10, 0), false),
+    ];
+    let due = take_due(&mut reminders, now, &Eastern).unwrap();
+    assert_eq!(ids(&due), ["now", "late"]);
+    assert_eq!(ids(&reminders), ["later"]);
+  }
+
+  #[test]
+  fn take_due_drops_stale_reminders_without_showing_them() {
+    let now = eastern_utc(2026, 6, 10, 9, 0);
+    let stale = now - STALE_AFTER - chrono::Duration::seconds(1);
+    let mut reminders = vec![reminder("stale", stale, false)];
+    // Still reported as due so the removal is saved
+    assert!(take_due(&mut reminders, now, &Eastern).unwrap().is_empty());
+    assert!(reminders.is_empty());
+  }
+
+  #[test]
+  fn take_due_reschedules_daily_reminders() {
+    let now = eastern_utc(2026, 6, 10, 21, 5);
+    let mut reminders = vec![
+      reminder("feedback", eastern_utc(2026, 6, 10, 21, 0), true),
+      reminder("missed", eastern_utc(2026, 6, 8, 20, 0), true),
+    ];
+    let due = take_due(&mut reminders, now, &Eastern).unwrap();
+    assert_eq!(ids(&due), ["feedback"]);
+    // The copy shown carries the time it was due
+    assert_eq!(due[0].fire_at, eastern_utc(2026, 6, 10, 21, 0));
+    assert_eq!(ids(&reminders), ["feedback", "missed"]);
+    assert_eq!(reminders[0].fire_at, eastern_utc(2026, 6, 11, 21, 0));
+    assert_eq!(reminders[1].fire_at, eastern_utc(2026, 6, 11, 20, 0));
+  }
+}
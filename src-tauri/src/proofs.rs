@@ -0,0 +1,274 @@
+use std::fs::{self, File};
+use std::io::{self, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tauri::ipc::{InvokeBody, Request};
+use tauri::{AppHandle, Manager};
+
+use crate::backup;
+use crate::protocol;
+
+const PROOFS_DIR_NAME: &str = "proofs";
+const HANDLE_PREFIX: &str = "sha256:";
+const FILE_NAME_HEADER: &str = "x-file-name";
+const META_EXTENSION: &str = "json";
+
+/// What the backend records for a stored proof. Identical files share one
+/// copy on disk and one handle.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProofHandle {
+  // `sha256:<hex>`, stored in `proofs.storage_key`
+  pub storage_key: String,
+  pub size: u64,
+  pub mime_type: String,
+  pub url: String,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ProofMeta {
+  size: u64,
+  mime_type: String,
+}
+
+pub fn proofs_dir(app: &AppHandle) -> Result<PathBuf, String> {
+  app
+    .path()
+    .app_data_dir()
+    .map(|dir| dir.join(PROOFS_DIR_NAME))
+    .map_err(|e| format!("Could not determine the data directory: {}", e))
+}
+
+fn is_hash(value: &str) -> bool {
+  value.len() == 64 && value.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'))
+}
+
+/// Files are fanned out by the first two hex digits so no directory grows
+/// too large
+fn blob_path(dir: &Path, hash: &str) -> PathBuf {
+  dir.join(&hash[..2]).join(hash)
+}
+
+/// Path and MIME type of a stored proof, given its hash
+pub fn locate(app: &AppHandle, hash: &str) -> Option<(PathBuf, String)> {
+  find(&proofs_dir(app).ok()?, hash)
+}
+
+fn find(dir: &Path, hash: &str) -> Option<(PathBuf, String)> {
+  if !is_hash(hash) {
+    return None;
+  }
+  let path = blob_path(dir, hash);
+  if !path.is_file() {
+    return None;
+  }
+  let mime_type = fs::read(path.with_extension(META_EXTENSION))
+    .ok()
+    .and_then(|meta| serde_json::from_slice::<ProofMeta>(&meta).ok())
+    .map(|meta| meta.mime_type)
+    .unwrap_or_else(|| protocol::mime_type(&path).to_string());
+  Some((path, mime_type))
+}
+
+/// Copies `source` into the store under its SHA-256. Streams through a
+/// temporary file so large proofs are never held in memory and a half
+/// written file never appears under a real hash.
+fn store(dir: &Path, source: &mut impl Read, file_name: &str) -> Result<(String, u64), String> {
+  let io_err = |e: io::Error| format!("Failed to save proof: {}", e);
+  fs::create_dir_all(dir).map_err(io_err)?;
+
+  let tmp = dir.join(format!(".{}.tmp", uuid::Uuid::new_v4().simple()));
+  let mut hasher = Sha256::new();
+  let mut size = 0u64;
+  let written = (|| {
+    let mut out = File::create(&tmp)?;
+    let mut buf = [0u8; 64 * 1024];
+    loop {
+      let n = source.read(&mut buf)?;
+      if n == 0 {
+        break;
+      }
+      hasher.update(&buf[..n]);
+      out.write_all(&buf[..n])?;
+      size += n as u64;
+    }
+    out.sync_all()
+  })();
+  if let Err(e) = written {
+    let _ = fs::remove_file(&tmp);
+    return Err(io_err(e));
+  }
+
+  let hash = backup::hex(hasher.finalize());
+  let path = blob_path(dir, &hash);
+  if path.exists() {
+    let _ = fs::remove_file(&tmp);
+  } else {
+    let moved = path
+      .parent()
+      .map_or(Ok(()), fs::create_dir_all)
+      .and_then(|_| fs::rename(&tmp, &path));
+    if let Err(e) = moved {
+      let _ = fs::remove_file(&tmp);
+      return Err(io_err(e));
+    }
+    let meta = ProofMeta { size, mime_type: protocol::mime_type(Path::new(file_name)).to_string() };
+    let meta = serde_json::to_vec(&meta).map_err(|e| e.to_string())?;
+    fs::write(path.with_extension(META_EXTENSION), meta).map_err(io_err)?;
+  }
+  Ok((hash, size))
+}
+
+fn handle(app: &AppHandle, hash: &str, size: u64) -> ProofHandle {
+  let mime_type = locate(app, hash)
+    .map(|(_, mime)| mime)
+    .unwrap_or_else(|| "application/octet-stream".to_string());
+  ProofHandle {
+    storage_key: format!("{}{}", HANDLE_PREFIX, hash),
+    size,
+    mime_type,
+    url: protocol::url(&format!("{}/{}", PROOFS_DIR_NAME, hash)),
+  }
+}
+
+/// Saves the raw request body as a proof. The webview sends the bytes with
+/// `invoke("save_proof", bytes, { headers: { "x-file-name": name } })`.
+#[tauri::command]
+pub async fn save_proof(app: AppHandle, request: Request<'_>) -> Result<ProofHandle, String> {
+  let InvokeBody::Raw(bytes) = request.body() else {
+    return Err("Expected the file contents as a raw body".to_string());
+  };
+  let file_name = request
+    .headers()
+    .get(FILE_NAME_HEADER)
+    .and_then(|v| v.to_str().ok())
+    .unwrap_or_default()
+    .to_string();
+  let dir = proofs_dir(&app)?;
+  let bytes = bytes.clone();
+  let (hash, size) =
+    tauri::async_runtime::spawn_blocking(move || store(&dir, &mut bytes.as_slice(), &file_name))
+      .await
+      .map_err(|e| format!("Failed to save proof: {}", e))??;
+  Ok(handle(&app, &hash, size))
+}
+
+/// Saves a file that is already on disk, such as one dropped on the window
+#[tauri::command]
+pub async fn save_proof_file(app: AppHandle, path: String) -> Result<ProofHandle, String> {
+  let dir = proofs_dir(&app)?;
+  let (hash, size) = tauri::async_runtime::spawn_blocking(move || {
+    let source = PathBuf::from(&path);
+    let file = File::open(&source).map_err(|e| format!("Failed to open {}: {}", path, e))?;
+    let file_name = source.file_name().unwrap_or_default().to_string_lossy().into_owned();
+    store(&dir, &mut BufReader::new(file), &file_name)
+  })
+  .await
+  .map_err(|e| format!("Failed to save proof: {}", e))??;
+  Ok(handle(&app, &hash, size))
+}
+
+/// Looks up a handle the backend recorded earlier
+#[tauri::command]
+pub fn get_proof(app: AppHandle, storage_key: String) -> Result<ProofHandle, String> {
+  let hash = storage_key
+    .strip_prefix(HANDLE_PREFIX)
+    .filter(|hash| is_hash(hash))
+    .ok_or_else(|| format!("Invalid proof handle: {}", storage_key))?;
+  let (path, _) = locate(&app, hash).ok_or_else(|| "Proof file not found".to_string())?;
+  let size = fs::metadata(&path).map_err(|e| e.to_string())?.len();
+  Ok(handle(&app, hash, size))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test_util::TempDir;
+
+  const PDF: &[u8] = b"%PDF-1.4 reading log";
+
+  fn save(dir: &Path, contents: &[u8], file_name: &str) -> (String, u64) {
+    store(dir, &mut &contents[..], file_name).unwrap()
+  }
+
+  /// Everything in the store, relative to it
+  fn listing(dir: &Path) -> Vec<String> {
+    backup::walk(dir)
+      .unwrap()
+      .into_iter()
+      .filter(|(_, is_dir)| !is_dir)
+      .map(|(path, _)| path.to_string_lossy().replace('\\', "/"))
+      .collect()
+  }
+
+  #[test]
+  fn is_hash_accepts_only_lowercase_sha256_hex() {
+    assert!(is_hash(&"0123456789abcdef".repeat(4)));
+    assert!(!is_hash(&"0123456789ABCDEF".repeat(4)));
+    assert!(!is_hash(&"a".repeat(63)));
+    assert!(!is_hash(&"a".repeat(65)));
+    assert!(!is_hash(&format!("{}g", "a".repeat(63))));
+    assert!(!is_hash(&format!("../{}", "a".repeat(61))));
+    assert!(!is_hash(""));
+  }
+
+  #[test]
+  fn store_names_files_by_content() {
+    let dir = TempDir::new();
+    let (hash, size) = save(dir.path(), PDF, "log.pdf");
+    assert_eq!(hash, backup::hex(Sha256::digest(PDF)));
+    assert_eq!(size, PDF.len() as u64);
+    assert_eq!(fs::read(blob_path(dir.path(), &hash)).unwrap(), PDF);
+    assert_eq!(
+      listing(dir.path()),
+      [format!("{}/{}", &hash[..2], hash), format!("{}/{}.json", &hash[..2], hash)]
+    );
+  }
+
+  #[test]
+  fn store_keeps_one_copy_of_identical_files() {
+    let dir = TempDir::new();
+    let (first, _) = save(dir.path(), PDF, "log.pdf");
+    let (second, _) = save(dir.path(), PDF, "copy of log.png");
+    let (other, _) = save(dir.path(), b"something else", "notes.txt");
+    assert_eq!(first, second);
+    assert_ne!(first, other);
+    // Two blobs and their sidecars, with no temporary files left behind
+    assert_eq!(listing(dir.path()).len(), 4);
+    // The first upload's type sticks
+    assert_eq!(find(dir.path(), &first).unwrap().1, "application/pdf");
+  }
+
+  #[test]
+  fn meta_sidecar_records_size_and_type() {
+    let dir = TempDir::new();
+    let (hash, _) = save(dir.path(), PDF, "Reading Log.PDF");
+    let path = blob_path(dir.path(), &hash).with_extension(META_EXTENSION);
+    let meta: ProofMeta = serde_json::from_slice(&fs::read(path).unwrap()).unwrap();
+    assert_eq!(meta.size, PDF.len() as u64);
+    assert_eq!(meta.mime_type, "application/pdf");
+  }
+
+  #[test]
+  fn find_falls_back_without_a_sidecar() {
+    let dir = TempDir::new();
+    let (hash, _) = save(dir.path(), PDF, "log.pdf");
+    let blob = blob_path(dir.path(), &hash);
+    fs::remove_file(blob.with_extension(META_EXTENSION)).unwrap();
+    let (path, mime_type) = find(dir.path(), &hash).unwrap();
+    assert_eq!(path, blob);
+    assert_eq!(mime_type, protocol::mime_type(&blob));
+  }
+
+  #[test]
+  fn find_rejects_unknown_and_malformed_hashes() {
+    let dir = TempDir::new();
+    let (hash, _) = save(dir.path(), PDF, "log.pdf");
+    assert!(find(dir.path(), &hash.to_uppercase()).is_none());
+    assert!(find(dir.path(), &"0".repeat(64)).is_none());
+    assert!(find(dir.path(), &format!("{}/../{}", &hash[..2], &hash[..58])).is_none());
+  }
+}
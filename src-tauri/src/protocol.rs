@@ -0,0 +1,348 @@
+use std::fs::{self, File};
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+use percent_encoding::percent_decode_str;
+use tauri::http::{header, Method, Request, Response, StatusCode};
+use tauri::{AppHandle, UriSchemeContext, UriSchemeResponder, Url};
+
+use crate::config;
+use crate::proofs;
+
+pub const SCHEME: &str = "forge";
+
+// Open-ended ranges (`bytes=0-`), which media and PDF viewers send first,
+// get at most this much so a large file is never read into memory at once
+const MAX_RANGE_CHUNK: u64 = 4 * 1024 * 1024;
+
+/// URL the webview can load for `path` under the `forge` scheme. Windows
+/// and Android webviews only allow custom schemes as a localhost subdomain.
+pub fn url(path: &str) -> String {
+  if cfg!(any(windows, target_os = "android")) {
+    format!("http://{}.localhost/{}", SCHEME, path)
+  } else {
+    format!("{}://localhost/{}", SCHEME, path)
+  }
+}
+
+pub fn mime_type(path: &Path) -> &'static str {
+  let ext = path
+    .extension()
+    .map(|ext| ext.to_string_lossy().to_lowercase())
+    .unwrap_or_default();
+  match ext.as_str() {
+    "jpg" | "jpeg" => "image/jpeg",
+    "png" => "image/png",
+    "gif" => "image/gif",
+    "webp" => "image/webp",
+    "svg" => "image/svg+xml",
+    "bmp" => "image/bmp",
+    "pdf" => "application/pdf",
+    "txt" | "log" => "text/plain; charset=utf-8",
+    "md" => "text/markdown; charset=utf-8",
+    "csv" => "text/csv; charset=utf-8",
+    "html" | "htm" => "text/html; charset=utf-8",
+    "json" => "application/json",
+    "mp3" => "audio/mpeg",
+    "wav" => "audio/wav",
+    "mp4" => "video/mp4",
+    "webm" => "video/webm",
+    "doc" => "application/msword",
+    "docx" => "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+    "pptx" => "application/vnd.openxmlformats-officedocument.presentationml.presentation",
+    "xlsx" => "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+    "zip" => "application/zip",
+    _ => "application/octet-stream",
+  }
+}
+
+fn error(status: StatusCode) -> Response<Vec<u8>> {
+  Response::builder().status(status).body(Vec::new()).unwrap()
+}
+
+/// Whether `origin` is the app's own webview: the bundled frontend in a
+/// release build, or the dev server in a debug one
+fn is_app_origin(origin: &str, dev_url: Option<&Url>) -> bool {
+  const BUNDLED: [&str; 3] =
+    ["tauri://localhost", "http://tauri.localhost", "https://tauri.localhost"];
+  BUNDLED.contains(&origin)
+    || (cfg!(debug_assertions)
+      && dev_url.is_some_and(|url| url.origin().ascii_serialization() == origin))
+}
+
+/// Decodes the URL path into plain segments, rejecting anything that could
+/// step outside a root: `..`, `.`, empty segments, backslashes and drive
+/// prefixes
+fn segments(path: &str) -> Option<Vec<String>> {
+  path
+    .trim_start_matches('/')
+    .split('/')
+    .map(|raw| {
+      let segment = percent_decode_str(raw).decode_utf8().ok()?;
+      let unsafe_segment = segment.is_empty()
+        || segment == "."
+        || segment == ".."
+        || segment.contains(['/', '\\', ':', '\0']);
+      (!unsafe_segment).then(|| segment.into_owned())
+    })
+    .collect()
+}
+
+/// Maps `forge://localhost/<root>/<path>` to a file on disk:
+///
+/// - `proofs/<hash>` is a file in the content-addressed proof store
+/// - `kb/<path>` is a file in the knowledge base (course uploads, portfolio)
+fn resolve(app: &AppHandle, path: &str) -> Option<(PathBuf, String)> {
+  let segments = segments(path)?;
+  let (root, rest) = segments.split_first()?;
+  match root.as_str() {
+    "proofs" => match rest {
+      [hash] => proofs::locate(app, hash),
+      _ => None,
+    },
+    "kb" => {
+      let base = config::kb_dir(app).ok()?.canonicalize().ok()?;
+      let file = rest.iter().fold(base.clone(), |p, s| p.join(s)).canonicalize().ok()?;
+      // Catches symlinks pointing out of the knowledge base
+      if !file.starts_with(&base) || !file.is_file() {
+        return None;
+      }
+      let mime = mime_type(&file).to_string();
+      Some((file, mime))
+    }
+    _ => None,
+  }
+}
+
+/// Parses a single `bytes=` range against a file of `len` bytes into an
+/// inclusive `(start, end)`. `Err` means the range cannot be satisfied;
+/// multi-range requests are served whole.
+fn parse_range(value: &str, len: u64) -> Option<Result<(u64, u64), ()>> {
+  let spec = value.trim().strip_prefix("bytes=")?;
+  if spec.contains(',') {
+    return None;
+  }
+  let (start, end) = spec.split_once('-')?;
+  let (start, end) = (start.trim(), end.trim());
+  let range = if start.is_empty() {
+    // Suffix range: the last N bytes
+    let suffix: u64 = end.parse().ok()?;
+    if suffix == 0 || len == 0 {
+      return Some(Err(()));
+    }
+    (len.saturating_sub(suffix), len - 1)
+  } else {
+    let start: u64 = start.parse().ok()?;
+    if start >= len {
+      return Some(Err(()));
+    }
+    let end = match end {
+      "" => (start + MAX_RANGE_CHUNK - 1).min(len - 1),
+      end => end.parse::<u64>().ok()?.min(len - 1),
+    };
+    if end < start {
+      return Some(Err(()));
+    }
+    (start, end)
+  };
+  Some(Ok(range))
+}
+
+fn read_range(file: &Path, start: u64, len: u64) -> io::Result<Vec<u8>> {
+  let mut file = File::open(file)?;
+  file.seek(SeekFrom::Start(start))?;
+  let mut body = Vec::with_capacity(len as usize);
+  file.take(len).read_to_end(&mut body)?;
+  Ok(body)
+}
+
+fn serve(app: &AppHandle, request: &Request<Vec<u8>>) -> Response<Vec<u8>> {
+  let method = request.method();
+  if method != Method::GET && method != Method::HEAD {
+    return error(StatusCode::METHOD_NOT_ALLOWED);
+  }
+  let Some((file, mime_type)) = resolve(app, request.uri().path()) else {
+    return error(StatusCode::NOT_FOUND);
+  };
+  let len = match fs::metadata(&file) {
+    Ok(meta) => meta.len(),
+    Err(_) => return error(StatusCode::NOT_FOUND),
+  };
+
+  let range = request
+    .headers()
+    .get(header::RANGE)
+    .and_then(|v| v.to_str().ok())
+    .and_then(|v| parse_range(v, len));
+  let (status, start, end) = match range {
+    Some(Ok((start, end))) => (StatusCode::PARTIAL_CONTENT, start, end),
+    Some(Err(())) => {
+      let mut response = error(StatusCode::RANGE_NOT_SATISFIABLE);
+      response
+        .headers_mut()
+        .insert(header::CONTENT_RANGE, format!("bytes */{}", len).parse().unwrap());
+      return response;
+    }
+    None => (StatusCode::OK, 0, len.saturating_sub(1)),
+  };
+  let body_len = if len == 0 { 0 } else { end - start + 1 };
+
+  let body = if method == Method::HEAD {
+    Vec::new()
+  } else {
+    match read_range(&file, start, body_len) {
+      Ok(body) => body,
+      Err(e) => {
+        log::warn!("Failed to read {}: {}", file.display(), e);
+        return error(StatusCode::INTERNAL_SERVER_ERROR);
+      }
+    }
+  };
+
+  let mut response = Response::builder()
+    .status(status)
+    .header(header::CONTENT_TYPE, mime_type)
+    .header(header::CONTENT_LENGTH, body_len)
+    .header(header::ACCEPT_RANGES, "bytes")
+    .header(header::CACHE_CONTROL, "no-cache");
+  if status == StatusCode::PARTIAL_CONTENT {
+    response = response.header(header::CONTENT_RANGE, format!("bytes {}-{}/{}", start, end, len));
+  }
+  response.body(body).unwrap()
+}
+
+/// Lets pdf.js and other fetch-based viewers in the app read the response,
+/// and nothing else: a page from anywhere else gets no CORS access to files
+/// on disk
+fn respond(app: &AppHandle, request: &Request<Vec<u8>>) -> Response<Vec<u8>> {
+  let mut response = serve(app, request);
+  let dev_url = app.config().build.dev_url.as_ref();
+  let origin = request.headers().get(header::ORIGIN);
+  if let Some(origin) = origin.filter(|o| o.to_str().is_ok_and(|o| is_app_origin(o, dev_url))) {
+    let headers = response.headers_mut();
+    headers.insert(header::ACCESS_CONTROL_ALLOW_ORIGIN, origin.clone());
+    headers.insert(header::VARY, header::HeaderValue::from_static("Origin"));
+  }
+  response
+}
+
+/// Serves files from the app's data directories to the webview, so proofs
+/// and course materials render without a trip through the Node server.
+/// File reads happen off the main thread so large files do not stall the UI.
+pub fn handle(
+  ctx: UriSchemeContext<'_, tauri::Wry>,
+  request: Request<Vec<u8>>,
+  responder: UriSchemeResponder,
+) {
+  let app = ctx.app_handle().clone();
+  tauri::async_runtime::spawn_blocking(move || responder.respond(respond(&app, &request)));
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const MIB: u64 = 1024 * 1024;
+
+  fn strings(parts: &[&str]) -> Option<Vec<String>> {
+    Some(parts.iter().map(|p| p.to_string()).collect())
+  }
+
+  #[test]
+  fn segments_decodes_plain_paths() {
+    assert_eq!(segments("/kb/notes/week%201.md"), strings(&["kb", "notes", "week 1.md"]));
+    assert_eq!(segments("proofs/abc"), strings(&["proofs", "abc"]));
+    assert_eq!(segments("/kb/caf%C3%A9.md"), strings(&["kb", "café.md"]));
+    // Leading slashes only mark the path as rooted at the scheme
+    assert_eq!(segments("//kb/a.md"), strings(&["kb", "a.md"]));
+  }
+
+  #[test]
+  fn segments_rejects_dot_segments() {
+    for path in ["/kb/../secret", "/..", "/kb/.", "/kb/%2e%2e/secret", "/kb/%2E%2E", "/kb/%2e"] {
+      assert_eq!(segments(path), None, "{}", path);
+    }
+  }
+
+  #[test]
+  fn segments_rejects_separators_inside_a_segment() {
+    for path in ["/kb/..%2Fsecret", "/%2Fetc%2Fpasswd", "/kb/a\\b", "/kb/..%5Csecret", "/kb/%00"] {
+      assert_eq!(segments(path), None, "{}", path);
+    }
+  }
+
+  #[test]
+  fn segments_rejects_drive_prefixes() {
+    for path in ["/C:/Windows", "/kb/C%3A%5CWindows", "/kb/c%3a"] {
+      assert_eq!(segments(path), None, "{}", path);
+    }
+  }
+
+  #[test]
+  fn segments_rejects_empty_segments_and_bad_encoding() {
+    for path in ["", "/", "/kb//a.md", "/kb/", "/kb/%ff"] {
+      assert_eq!(segments(path), None, "{}", path);
+    }
+  }
+
+  #[test]
+  fn parse_range_reads_a_closed_range() {
+    assert_eq!(parse_range("bytes=0-99", 1000), Some(Ok((0, 99))));
+    assert_eq!(parse_range(" bytes=10 - 19 ", 1000), Some(Ok((10, 19))));
+    assert_eq!(parse_range("bytes=999-999", 1000), Some(Ok((999, 999))));
+    // An end past the file is clamped to it
+    assert_eq!(parse_range("bytes=900-5000", 1000), Some(Ok((900, 999))));
+  }
+
+  #[test]
+  fn parse_range_reads_a_suffix_range() {
+    assert_eq!(parse_range("bytes=-100", 1000), Some(Ok((900, 999))));
+    assert_eq!(parse_range("bytes=-5000", 1000), Some(Ok((0, 999))));
+    assert_eq!(parse_range("bytes=-0", 1000), Some(Err(())));
+  }
+
+  #[test]
+  fn parse_range_caps_an_open_range() {
+    assert_eq!(parse_range("bytes=100-", 1000), Some(Ok((100, 999))));
+    assert_eq!(parse_range("bytes=0-", 10 * MIB), Some(Ok((0, 4 * MIB - 1))));
+    assert_eq!(parse_range("bytes=1-", 10 * MIB), Some(Ok((1, 4 * MIB))));
+  }
+
+  #[test]
+  fn parse_range_rejects_unsatisfiable_ranges() {
+    assert_eq!(parse_range("bytes=1000-", 1000), Some(Err(())));
+    assert_eq!(parse_range("bytes=1000-2000", 1000), Some(Err(())));
+    assert_eq!(parse_range("bytes=5-4", 1000), Some(Err(())));
+  }
+
+  #[test]
+  fn parse_range_ignores_malformed_headers() {
+    for value in ["bytes=a-b", "bytes=0-b", "bytes=--1", "bytes=5", "items=0-1", "bytes=0-1,5"] {
+      assert_eq!(parse_range(value, 1000), None, "{}", value);
+    }
+  }
+
+  #[test]
+  fn parse_range_on_an_empty_file() {
+    assert_eq!(parse_range("bytes=0-", 0), Some(Err(())));
+    assert_eq!(parse_range("bytes=0-0", 0), Some(Err(())));
+    assert_eq!(parse_range("bytes=-1", 0), Some(Err(())));
+  }
+
+  #[test]
+  fn is_app_origin_allows_only_the_webview() {
+    for origin in ["tauri://localhost", "http://tauri.localhost", "https://tauri.localhost"] {
+      assert!(is_app_origin(origin, None), "{}", origin);
+    }
+    for origin in ["http://localhost:5000", "https://example.com", "null", "tauri://evil"] {
+      assert!(!is_app_origin(origin, None), "{}", origin);
+    }
+  }
+
+  #[test]
+  fn is_app_origin_allows_the_dev_server_in_debug_builds() {
+    let dev_url = Url::parse("http://localhost:5173/app/").unwrap();
+    assert_eq!(is_app_origin("http://localhost:5173", Some(&dev_url)), cfg!(debug_assertions));
+    assert!(!is_app_origin("http://localhost:5174", Some(&dev_url)));
+  }
+}
@@ -0,0 +1,403 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use hkdf::Hkdf;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use tauri::{AppHandle, Manager};
+
+use crate::backend;
+use crate::config::{self, BackendEnv, GEMINI_API_KEY, GROQ_API_KEY, OPENAI_API_KEY};
+
+const VAULT_FILE_NAME: &str = "secrets.vault";
+const VAULT_VERSION: u32 = 1;
+const KEY_INFO: &[u8] = b"com.forge.app secrets vault v1";
+const SALT_LEN: usize = 16;
+
+/// Secrets the vault accepts. They only ever reach the sidecar as process
+/// environment variables.
+pub const SECRET_KEYS: [&str; 3] = [GROQ_API_KEY, GEMINI_API_KEY, OPENAI_API_KEY];
+
+/// On-disk layout. The secrets map is serialized to JSON and sealed with
+/// ChaCha20-Poly1305 under a key derived from this machine's ID, so the file
+/// is useless if copied to another machine.
+#[derive(Serialize, Deserialize)]
+struct VaultFile {
+  version: u32,
+  salt: String,
+  nonce: String,
+  ciphertext: String,
+}
+
+pub type Secrets = BTreeMap<String, String>;
+
+pub fn vault_path(app: &AppHandle) -> Result<PathBuf, String> {
+  app
+    .path()
+    .app_config_dir()
+    .map(|dir| dir.join(VAULT_FILE_NAME))
+    .map_err(|e| format!("Failed to resolve config directory: {}", e))
+}
+
+fn machine_id() -> Result<String, String> {
+  machine_uid::get().map_err(|e| format!("Failed to read machine ID: {}", e))
+}
+
+fn derive_key(machine_id: &str, salt: &[u8]) -> Result<Key, String> {
+  let hkdf = Hkdf::<Sha256>::new(Some(salt), machine_id.trim().as_bytes());
+  let mut key = Key::default();
+  hkdf
+    .expand(KEY_INFO, &mut key)
+    .map_err(|e| format!("Failed to derive vault key: {}", e))?;
+  Ok(key)
+}
+
+fn decode(field: &str, value: &str) -> Result<Vec<u8>, String> {
+  BASE64
+    .decode(value)
+    .map_err(|e| format!("Corrupt vault {}: {}", field, e))
+}
+
+pub fn load(app: &AppHandle) -> Result<Secrets, String> {
+  read_vault(&vault_path(app)?, machine_id)
+}
+
+/// Only asks for the machine ID once there is a vault to open
+fn read_vault(
+  path: &Path,
+  machine_id: impl FnOnce() -> Result<String, String>,
+) -> Result<Secrets, String> {
+  let contents = match fs::read(path) {
+    Ok(contents) => contents,
+    Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Secrets::new()),
+    Err(e) => return Err(format!("Failed to read {}: {}", path.display(), e)),
+  };
+
+  let vault: VaultFile =
+    serde_json::from_slice(&contents).map_err(|e| format!("Corrupt vault file: {}", e))?;
+  if vault.version != VAULT_VERSION {
+    return Err(format!("Unsupported vault version {}", vault.version));
+  }
+
+  let salt = decode("salt", &vault.salt)?;
+  let nonce = decode("nonce", &vault.nonce)?;
+  let ciphertext = decode("ciphertext", &vault.ciphertext)?;
+  if nonce.len() != 12 {
+    return Err("Corrupt vault nonce".to_string());
+  }
+
+  let cipher = ChaCha20Poly1305::new(&derive_key(&machine_id()?, &salt)?);
+  let plaintext = cipher
+    .decrypt(Nonce::from_slice(&nonce), ciphertext.as_ref())
+    .map_err(|_| "Failed to decrypt the secrets vault. Was it copied from another machine?".to_string())?;
+  serde_json::from_slice(&plaintext).map_err(|e| format!("Corrupt vault contents: {}", e))
+}
+
+fn write_vault(path: &Path, machine_id: &str, secrets: &Secrets) -> Result<(), String> {
+  // Fresh salt and nonce on every write
+  let salt: [u8; SALT_LEN] = rand_bytes();
+  let cipher = ChaCha20Poly1305::new(&derive_key(machine_id, &salt)?);
+  let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+  let plaintext = serde_json::to_vec(secrets).map_err(|e| e.to_string())?;
+  let ciphertext = cipher
+    .encrypt(&nonce, plaintext.as_ref())
+    .map_err(|e| format!("Failed to encrypt secrets: {}", e))?;
+
+  let vault = VaultFile {
+    version: VAULT_VERSION,
+    salt: BASE64.encode(salt),
+    nonce: BASE64.encode(nonce),
+    ciphertext: BASE64.encode(ciphertext),
+  };
+  let contents = serde_json::to_vec_pretty(&vault).map_err(|e| e.to_string())?;
+
+  if let Some(dir) = path.parent() {
+    fs::create_dir_all(dir).map_err(|e| format!("Failed to create config directory: {}", e))?;
+  }
+  let tmp = path.with_extension("tmp");
+  write_private(&tmp, &contents)
+    .and_then(|_| fs::rename(&tmp, path))
+    .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+/// Creates the file readable by the owner only, so the sealed secrets are
+/// never briefly world-readable before the rename
+fn write_private(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+  // The mode only applies on creation, so clear out a leftover from a crash
+  match fs::remove_file(path) {
+    Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e),
+    _ => {}
+  }
+  let mut options = fs::OpenOptions::new();
+  options.write(true).create_new(true);
+  #[cfg(unix)]
+  {
+    use std::os::unix::fs::OpenOptionsExt;
+    options.mode(0o600);
+  }
+  options.open(path)?.write_all(contents)
+}
+
+fn rand_bytes<const N: usize>() -> [u8; N] {
+  use chacha20poly1305::aead::rand_core::RngCore;
+  let mut bytes = [0u8; N];
+  OsRng.fill_bytes(&mut bytes);
+  bytes
+}
+
+/// Applies the given changes to the vault. `None` removes a secret.
+pub fn update(app: &AppHandle, changes: &[(&str, Option<String>)]) -> Result<(), String> {
+  update_vault(&vault_path(app)?, &machine_id()?, changes)
+}
+
+fn update_vault(
+  path: &Path,
+  machine_id: &str,
+  changes: &[(&str, Option<String>)],
+) -> Result<(), String> {
+  let mut secrets = read_vault(path, || Ok(machine_id.to_string()))?;
+  for (key, value) in changes {
+    match value {
+      Some(value) => secrets.insert(key.to_string(), value.clone()),
+      None => secrets.remove(*key),
+    };
+  }
+  write_vault(path, machine_id, &secrets)
+}
+
+/// Moves API keys that are still sitting in the plaintext `.env` (from older
+/// versions or hand edits) into the vault and strips them from the file,
+/// along with the placeholders seeded from `.env.example`.
+pub fn migrate_from_env_file(app: &AppHandle) -> Result<(), String> {
+  migrate(&config::env_file_path(app)?, &vault_path(app)?, machine_id)
+}
+
+fn migrate(
+  env_path: &Path,
+  vault_path: &Path,
+  machine_id: impl FnOnce() -> Result<String, String>,
+) -> Result<(), String> {
+  let env = config::read_env(env_path)?;
+  let found: Vec<(&str, Option<String>)> = [
+    (GROQ_API_KEY, env.groq_api_key.clone()),
+    (GEMINI_API_KEY, env.gemini_api_key.clone()),
+    (OPENAI_API_KEY, env.openai_api_key.clone()),
+  ]
+  .into_iter()
+  .filter(|(_, value)| value.is_some())
+  .collect();
+
+  if !found.is_empty() {
+    update_vault(vault_path, &machine_id()?, &found)?;
+    log::info!("Moved {} API key(s) from .env into the secrets vault", found.len());
+  }
+  config::write_env(
+    env_path,
+    &BackendEnv { database_url: env.database_url, ..BackendEnv::default() },
+  )
+}
+
+fn check_key(key: &str) -> Result<(), String> {
+  if SECRET_KEYS.contains(&key) {
+    Ok(())
+  } else {
+    Err(format!("Unknown secret: {}", key))
+  }
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SecretInfo {
+  pub key: &'static str,
+  pub configured: bool,
+  // Last four characters, enough to tell keys apart without exposing them
+  pub hint: Option<String>,
+}
+
+#[tauri::command]
+pub fn list_secrets(app: AppHandle) -> Result<Vec<SecretInfo>, String> {
+  let secrets = load(&app)?;
+  Ok(
+    SECRET_KEYS
+      .iter()
+      .map(|key| {
+        let value = secrets.get(*key);
+        SecretInfo {
+          key,
+          configured: value.is_some(),
+          hint: value.map(|v| {
+            let tail: String = v.chars().skip(v.chars().count().saturating_sub(4)).collect();
+            format!("…{}", tail)
+          }),
+        }
+      })
+      .collect(),
+  )
+}
+
+#[tauri::command]
+pub async fn set_secret(app: AppHandle, key: String, value: String) -> Result<(), String> {
+  check_key(&key)?;
+  let value = value.trim().to_string();
+  if value.is_empty() {
+    return Err("Secret cannot be empty".to_string());
+  }
+  update(&app, &[(key.as_str(), Some(value))])?;
+  backend::restart_backend(&app).await
+}
+
+#[tauri::command]
+pub async fn delete_secret(app: AppHandle, key: String) -> Result<(), String> {
+  check_key(&key)?;
+  update(&app, &[(key.as_str(), None)])?;
+  backend::restart_backend(&app).await
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test_util::TempDir;
+
+  const MACHINE: &str = "0123456789abcdef";
+
+  fn this_machine() -> Result<String, String> {
+    Ok(MACHINE.to_string())
+  }
+
+  fn never() -> Result<String, String> {
+    panic!("machine ID should not be needed")
+  }
+
+  fn secrets(entries: &[(&str, &str)]) -> Secrets {
+    entries.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+  }
+
+  fn read_file(path: &Path) -> VaultFile {
+    serde_json::from_slice(&fs::read(path).unwrap()).unwrap()
+  }
+
+  #[test]
+  fn vault_round_trips() {
+    let dir = TempDir::new();
+    let path = dir.path().join(VAULT_FILE_NAME);
+    let stored = secrets(&[(GROQ_API_KEY, "gsk_one"), (GEMINI_API_KEY, "AIza two")]);
+    write_vault(&path, MACHINE, &stored).unwrap();
+    assert_eq!(read_vault(&path, this_machine).unwrap(), stored);
+    assert!(!fs::read_to_string(&path).unwrap().contains("gsk_one"));
+  }
+
+  #[test]
+  fn missing_vault_is_empty() {
+    let dir = TempDir::new();
+    assert!(read_vault(&dir.path().join(VAULT_FILE_NAME), never).unwrap().is_empty());
+  }
+
+  #[test]
+  fn every_write_uses_a_fresh_salt_and_nonce() {
+    let dir = TempDir::new();
+    let path = dir.path().join(VAULT_FILE_NAME);
+    let stored = secrets(&[(GROQ_API_KEY, "gsk_one")]);
+    write_vault(&path, MACHINE, &stored).unwrap();
+    let first = read_file(&path);
+    write_vault(&path, MACHINE, &stored).unwrap();
+    let second = read_file(&path);
+    assert_ne!(first.salt, second.salt);
+    assert_ne!(first.nonce, second.nonce);
+  }
+
+  #[test]
+  fn vault_from_another_machine_is_rejected() {
+    let dir = TempDir::new();
+    let path = dir.path().join(VAULT_FILE_NAME);
+    write_vault(&path, MACHINE, &secrets(&[(GROQ_API_KEY, "gsk_one")])).unwrap();
+    let err = read_vault(&path, || Ok("fedcba9876543210".to_string())).unwrap_err();
+    assert!(err.contains("another machine"), "{}", err);
+  }
+
+  #[test]
+  fn tampered_vault_is_rejected() {
+    let dir = TempDir::new();
+    let path = dir.path().join(VAULT_FILE_NAME);
+    write_vault(&path, MACHINE, &secrets(&[(GROQ_API_KEY, "gsk_one")])).unwrap();
+
+    let mut vault = read_file(&path);
+    let mut ciphertext = BASE64.decode(&vault.ciphertext).unwrap();
+    ciphertext[0] ^= 1;
+    vault.ciphertext = BASE64.encode(ciphertext);
+    fs::write(&path, serde_json::to_vec(&vault).unwrap()).unwrap();
+    assert!(read_vault(&path, this_machine).is_err());
+
+    vault.version = VAULT_VERSION + 1;
+    fs::write(&path, serde_json::to_vec(&vault).unwrap()).unwrap();
+    let err = read_vault(&path, this_machine).unwrap_err();
+    assert!(err.contains("Unsupported vault version"), "{}", err);
+  }
+
+  #[cfg(unix)]
+  #[test]
+  fn vault_is_private_to_the_owner() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = TempDir::new();
+    let path = dir.path().join(VAULT_FILE_NAME);
+    // A world-readable leftover from an interrupted write
+    let tmp = dir.file("secrets.tmp", "stale");
+    fs::set_permissions(&tmp, fs::Permissions::from_mode(0o644)).unwrap();
+
+    write_vault(&path, MACHINE, &secrets(&[(GROQ_API_KEY, "gsk_one")])).unwrap();
+    let mode = fs::metadata(&path).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o600);
+    assert!(!tmp.exists());
+  }
+
+  #[test]
+  fn update_sets_and_removes_secrets() {
+    let dir = TempDir::new();
+    let path = dir.path().join(VAULT_FILE_NAME);
+    update_vault(&path, MACHINE, &[(GROQ_API_KEY, Some("gsk_one".to_string()))]).unwrap();
+    update_vault(
+      &path,
+      MACHINE,
+      &[(GROQ_API_KEY, None), (OPENAI_API_KEY, Some("sk-two".to_string()))],
+    )
+    .unwrap();
+    assert_eq!(read_vault(&path, this_machine).unwrap(), secrets(&[(OPENAI_API_KEY, "sk-two")]));
+  }
+
+  #[test]
+  fn migrate_moves_keys_out_of_the_env_file() {
+    let dir = TempDir::new();
+    let vault = dir.path().join(VAULT_FILE_NAME);
+    let env = dir.file(
+      ".env",
+      "# Forge settings\nDATABASE_URL=postgres://localhost/forge\nGROQ_API_KEY=gsk_one\n\
+       GEMINI_API_KEY=your_gemini_api_key\nPORT=5000\n",
+    );
+    update_vault(&vault, MACHINE, &[(OPENAI_API_KEY, Some("sk-two".to_string()))]).unwrap();
+
+    migrate(&env, &vault, this_machine).unwrap();
+    assert_eq!(
+      read_vault(&vault, this_machine).unwrap(),
+      secrets(&[(GROQ_API_KEY, "gsk_one"), (OPENAI_API_KEY, "sk-two")])
+    );
+    assert_eq!(
+      fs::read_to_string(&env).unwrap(),
+      "# Forge settings\nDATABASE_URL=postgres://localhost/forge\nPORT=5000\n"
+    );
+  }
+
+  #[test]
+  fn migrate_without_keys_leaves_the_vault_alone() {
+    let dir = TempDir::new();
+    let vault = dir.path().join(VAULT_FILE_NAME);
+    let env = dir.file(".env", "DATABASE_URL=postgres://localhost/forge\n");
+    migrate(&env, &vault, never).unwrap();
+    assert!(!vault.exists());
+  }
+}
@@ -0,0 +1,65 @@
+use std::time::Duration;
+
+use tauri::{AppHandle, Manager, WebviewUrl, WebviewWindowBuilder};
+
+use crate::backend;
+use crate::backup_schedule;
+
+const SPLASH_LABEL: &str = "splash";
+// The packaged sidecar loads the embedding model on boot, so be generous
+const READY_TIMEOUT: Duration = Duration::from_secs(45);
+
+pub fn create(app: &AppHandle) -> tauri::Result<()> {
+  WebviewWindowBuilder::new(app, SPLASH_LABEL, WebviewUrl::App("splash.html".into()))
+    .title("Forge")
+    .inner_size(420.0, 280.0)
+    .resizable(false)
+    .decorations(false)
+    .center()
+    .build()?;
+  Ok(())
+}
+
+/// Starts the backend and keeps the splash up until its HTTP server answers.
+/// On success the splash is replaced by the main window; on failure the
+/// splash switches to its error view so the user can retry.
+pub async fn boot(app: AppHandle) {
+  backup_schedule::backup_if_updated(&app).await;
+  if let Err(e) = backend::start_backend(app.clone()).await {
+    show_error(&app, &e);
+    return;
+  }
+
+  match backend::wait_until_ready(&app, READY_TIMEOUT).await {
+    Ok(()) => {
+      if let Some(window) = app.get_webview_window("main") {
+        let _ = window.show();
+        let _ = window.set_focus();
+      }
+      if let Some(splash) = app.get_webview_window(SPLASH_LABEL) {
+        let _ = splash.close();
+      }
+    }
+    Err(e) => {
+      log::error!("{}", e);
+      show_error(&app, &e);
+    }
+  }
+}
+
+fn show_error(app: &AppHandle, message: &str) {
+  if let Some(splash) = app.get_webview_window(SPLASH_LABEL) {
+    let message = serde_json::to_string(message).unwrap_or_default();
+    let _ = splash.eval(format!("window.forgeSplash && window.forgeSplash.showError({})", message));
+  }
+}
+
+#[tauri::command]
+pub async fn retry_backend(app: AppHandle) -> Result<(), String> {
+  backend::shutdown_backend(&app, backend::SHUTDOWN_GRACE).await;
+  if let Some(splash) = app.get_webview_window(SPLASH_LABEL) {
+    let _ = splash.eval("window.forgeSplash && window.forgeSplash.showLoading()");
+  }
+  tauri::async_runtime::spawn(boot(app));
+  Ok(())
+}
@@ -0,0 +1,28 @@
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+/// Reads a JSON file written by `write_json`. A missing file reads as the
+/// default value.
+pub fn read_json<T: for<'de> Deserialize<'de> + Default>(path: &Path) -> Result<T, String> {
+  match fs::read(path) {
+    Ok(contents) => serde_json::from_slice(&contents)
+      .map_err(|e| format!("Corrupt {}: {}", path.display(), e)),
+    Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(T::default()),
+    Err(e) => Err(format!("Failed to read {}: {}", path.display(), e)),
+  }
+}
+
+/// Writes pretty-printed JSON through a temporary file, so a crash never
+/// leaves a half-written file behind
+pub fn write_json<T: Serialize>(path: &Path, value: &T) -> Result<(), String> {
+  if let Some(dir) = path.parent() {
+    fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+  }
+  let contents = serde_json::to_vec_pretty(value).map_err(|e| e.to_string())?;
+  let tmp = path.with_extension("tmp");
+  fs::write(&tmp, contents)
+    .and_then(|_| fs::rename(&tmp, path))
+    .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
@@ -0,0 +1,93 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, FixedOffset, LocalResult, NaiveDate, NaiveDateTime, TimeZone, Utc};
+
+/// A scratch directory removed when the test ends
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+  pub fn new() -> Self {
+    let dir = std::env::temp_dir().join(format!("forge-test-{}", uuid::Uuid::new_v4()));
+    fs::create_dir_all(&dir).unwrap();
+    Self(dir)
+  }
+
+  pub fn path(&self) -> &Path {
+    &self.0
+  }
+
+  /// Writes `contents` to `name` under the directory, creating parents
+  pub fn file(&self, name: &str, contents: impl AsRef<[u8]>) -> PathBuf {
+    let path = self.0.join(name);
+    if let Some(dir) = path.parent() {
+      fs::create_dir_all(dir).unwrap();
+    }
+    fs::write(&path, contents).unwrap();
+    path
+  }
+}
+
+impl Drop for TempDir {
+  fn drop(&mut self) {
+    let _ = fs::remove_dir_all(&self.0);
+  }
+}
+
+/// The fixed offset `hours` behind UTC
+pub fn offset(hours: i32) -> FixedOffset {
+  FixedOffset::west_opt(hours * 3600).unwrap()
+}
+
+/// US Eastern time for 2026, daylight saving from 8 March to 1 November
+#[derive(Clone, Copy, Debug)]
+pub struct Eastern;
+
+impl TimeZone for Eastern {
+  type Offset = FixedOffset;
+
+  fn from_offset(_: &FixedOffset) -> Self {
+    Eastern
+  }
+
+  fn offset_from_local_date(&self, local: &NaiveDate) -> LocalResult<FixedOffset> {
+    self.offset_from_local_datetime(&local.and_hms_opt(12, 0, 0).unwrap())
+  }
+
+  fn offset_from_local_datetime(&self, local: &NaiveDateTime) -> LocalResult<FixedOffset> {
+    // Daylight time first, as it is the earlier instant for a repeated hour
+    let valid: Vec<FixedOffset> = [offset(4), offset(5)]
+      .into_iter()
+      .filter(|o| self.offset_from_utc_datetime(&(*local - *o)) == *o)
+      .collect();
+    match valid[..] {
+      [only] => LocalResult::Single(only),
+      [earliest, latest] => LocalResult::Ambiguous(earliest, latest),
+      _ => LocalResult::None,
+    }
+  }
+
+  fn offset_from_utc_date(&self, utc: &NaiveDate) -> FixedOffset {
+    self.offset_from_utc_datetime(&utc.and_hms_opt(0, 0, 0).unwrap())
+  }
+
+  fn offset_from_utc_datetime(&self, utc: &NaiveDateTime) -> FixedOffset {
+    let start = NaiveDate::from_ymd_opt(2026, 3, 8).unwrap().and_hms_opt(7, 0, 0).unwrap();
+    let end = NaiveDate::from_ymd_opt(2026, 11, 1).unwrap().and_hms_opt(6, 0, 0).unwrap();
+    if (start..end).contains(utc) {
+      offset(4)
+    } else {
+      offset(5)
+    }
+  }
+}
+
+/// A wall-clock time in [`Eastern`], the earlier one if it happens twice
+pub fn eastern(y: i32, mo: u32, d: u32, h: u32, mi: u32) -> DateTime<Eastern> {
+  Eastern.with_ymd_and_hms(y, mo, d, h, mi, 0).earliest().unwrap()
+}
+
+/// The same, as the UTC instant it is stored as
+pub fn eastern_utc(y: i32, mo: u32, d: u32, h: u32, mi: u32) -> DateTime<Utc> {
+  eastern(y, mo, d, h, mi).with_timezone(&Utc)
+}
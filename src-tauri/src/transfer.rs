@@ -0,0 +1,690 @@
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::{Component, Path, PathBuf};
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+use tauri::{AppHandle, Manager};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+use crate::backend::{self, BackendPhase, BackendState};
+use crate::backup;
+use crate::config;
+use crate::proofs;
+use crate::vectors;
+
+// A `.forge` file is a plain zip: `manifest.json`, one JSON array per table
+// under `tables/` with property names as in shared/schema.ts, file contents
+// that the database keeps as base64 under `attachments/`, and the knowledge
+// base and proof store under `files/`.
+const EXPORT_FORMAT: &str = "forge-export";
+const EXPORT_VERSION: u32 = 1;
+const EXPORT_EXTENSION: &str = "forge";
+const MANIFEST_NAME: &str = "manifest.json";
+const TABLES_DIR: &str = "tables";
+const ATTACHMENTS_DIR: &str = "attachments";
+const KB_FILES_DIR: &str = "files/forge_kb";
+const PROOF_FILES_DIR: &str = "files/proofs";
+
+const EXPORT_PATH: &str = "/api/internal/export";
+const IMPORT_PATH: &str = "/api/internal/import";
+
+// Base64 text columns holding uploaded file contents, stored as real files
+// in the archive so it stays browsable
+const BINARY_COLUMNS: [(&str, &str); 2] = [("proofs", "fileData"), ("uploaded_files", "fileData")];
+
+type Row = Map<String, Value>;
+type Tables = BTreeMap<String, Vec<Row>>;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Attachment {
+  table: String,
+  id: i64,
+  column: String,
+  path: String,
+  size: u64,
+  sha256: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ExportManifest {
+  format: String,
+  version: u32,
+  exported_at: DateTime<Utc>,
+  app_version: String,
+  // Table name -> row count
+  tables: BTreeMap<String, usize>,
+  attachments: Vec<Attachment>,
+  files: usize,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportMode {
+  /// Adds the exported rows next to existing data under new ids. Users with
+  /// the same email are treated as the same account.
+  Merge,
+  /// Wipes existing data first and keeps the exported ids
+  Replace,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportSummary {
+  pub path: String,
+  pub tables: BTreeMap<String, usize>,
+  pub attachments: usize,
+  pub files: usize,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportSummary {
+  pub mode: ImportMode,
+  pub exported_at: DateTime<Utc>,
+  pub imported: BTreeMap<String, usize>,
+  pub files: usize,
+}
+
+#[derive(Deserialize)]
+struct ExportResponse {
+  tables: Tables,
+}
+
+#[derive(Serialize)]
+struct ImportRequest<'a> {
+  mode: ImportMode,
+  tables: &'a Tables,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ImportResponse {
+  imported: BTreeMap<String, usize>,
+  // Old user id -> new user id, only filled in for merges
+  user_ids: BTreeMap<String, i64>,
+}
+
+#[derive(Deserialize)]
+struct ErrorResponse {
+  message: String,
+}
+
+fn ensure_ready(app: &AppHandle) -> Result<(), String> {
+  if app.state::<BackendState>().phase() != BackendPhase::Ready {
+    return Err("The backend must be running to transfer data".to_string());
+  }
+  Ok(())
+}
+
+async fn check(res: reqwest::Response) -> Result<reqwest::Response, String> {
+  if res.status().is_success() {
+    return Ok(res);
+  }
+  let status = res.status();
+  match res.json::<ErrorResponse>().await {
+    Ok(body) => Err(body.message),
+    Err(_) => Err(format!("Backend returned {}", status)),
+  }
+}
+
+/// Archive folder and live directory for every file tree an export carries
+fn file_sources(app: &AppHandle) -> Result<[(&'static str, PathBuf); 2], String> {
+  Ok([(KB_FILES_DIR, config::kb_dir(app)?), (PROOF_FILES_DIR, proofs::proofs_dir(app)?)])
+}
+
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+  let mut name = OsString::from(path.as_os_str());
+  name.push(suffix);
+  PathBuf::from(name)
+}
+
+fn write_export(
+  dest: &Path,
+  mut tables: Tables,
+  sources: &[(&'static str, PathBuf)],
+  app_version: &str,
+) -> Result<ExportManifest, String> {
+  let io_err = |e: io::Error| format!("Failed to write {}: {}", dest.display(), e);
+  let zip_err = |e: zip::result::ZipError| format!("Failed to write {}: {}", dest.display(), e);
+
+  let file = File::create(dest).map_err(io_err)?;
+  let mut zip = ZipWriter::new(BufWriter::new(file));
+  let options = SimpleFileOptions::default()
+    .compression_method(CompressionMethod::Deflated)
+    .large_file(true);
+  // Already compressed more often than not
+  let stored = SimpleFileOptions::default()
+    .compression_method(CompressionMethod::Stored)
+    .large_file(true);
+
+  let mut attachments = Vec::new();
+  for (table, rows) in tables.iter_mut() {
+    for (t, column) in BINARY_COLUMNS {
+      if t != table {
+        continue;
+      }
+      for row in rows.iter_mut() {
+        let (Some(id), Some(Value::String(encoded))) =
+          (row.get("id").and_then(Value::as_i64), row.get(column))
+        else {
+          continue;
+        };
+        // Leave anything that is not valid base64 inline
+        let Ok(bytes) = BASE64.decode(encoded) else {
+          continue;
+        };
+        let path = format!("{}/{}/{}-{}", ATTACHMENTS_DIR, table, id, column);
+        zip.start_file(path.as_str(), stored).map_err(zip_err)?;
+        zip.write_all(&bytes).map_err(io_err)?;
+        attachments.push(Attachment {
+          table: table.clone(),
+          id,
+          column: column.to_string(),
+          path,
+          size: bytes.len() as u64,
+          sha256: backup::hex(Sha256::digest(&bytes)),
+        });
+        row.insert(column.to_string(), Value::Null);
+      }
+    }
+
+    zip
+      .start_file(format!("{}/{}.json", TABLES_DIR, table), options)
+      .map_err(zip_err)?;
+    serde_json::to_writer_pretty(&mut zip, rows).map_err(|e| e.to_string())?;
+  }
+
+  let mut files = 0;
+  for (root, dir) in sources {
+    if !dir.is_dir() {
+      continue;
+    }
+    for (relative, is_dir) in backup::walk(dir).map_err(io_err)? {
+      if is_dir {
+        continue;
+      }
+      zip
+        .start_file(backup::entry_name(root, &relative), options)
+        .map_err(zip_err)?;
+      let mut source = BufReader::new(
+        File::open(dir.join(&relative))
+          .map_err(|e| format!("Failed to read {}: {}", relative.display(), e))?,
+      );
+      io::copy(&mut source, &mut zip).map_err(io_err)?;
+      files += 1;
+    }
+  }
+
+  let manifest = ExportManifest {
+    format: EXPORT_FORMAT.to_string(),
+    version: EXPORT_VERSION,
+    exported_at: Utc::now(),
+    app_version: app_version.to_string(),
+    tables: tables.iter().map(|(name, rows)| (name.clone(), rows.len())).collect(),
+    attachments,
+    files,
+  };
+  zip.start_file(MANIFEST_NAME, options).map_err(zip_err)?;
+  serde_json::to_writer_pretty(&mut zip, &manifest).map_err(|e| e.to_string())?;
+  zip
+    .finish()
+    .map_err(zip_err)?
+    .into_inner()
+    .map_err(|e| io_err(e.into_error()))?
+    .sync_all()
+    .map_err(io_err)?;
+  Ok(manifest)
+}
+
+fn open_export(path: &Path) -> Result<ZipArchive<BufReader<File>>, String> {
+  let file = File::open(path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+  ZipArchive::new(BufReader::new(file))
+    .map_err(|e| format!("{} is not a Forge export: {}", path.display(), e))
+}
+
+/// Reads and validates an export, putting attachments back inline so the
+/// rows match what the backend stores
+fn read_export(path: &Path) -> Result<(ExportManifest, Tables), String> {
+  let mut archive = open_export(path)?;
+  let manifest: ExportManifest = {
+    let entry = archive
+      .by_name(MANIFEST_NAME)
+      .map_err(|_| format!("{} is not a Forge export", path.display()))?;
+    serde_json::from_reader(entry).map_err(|e| format!("Corrupt export manifest: {}", e))?
+  };
+  if manifest.format != EXPORT_FORMAT {
+    return Err(format!("{} is not a Forge export", path.display()));
+  }
+  if manifest.version > EXPORT_VERSION {
+    return Err(format!(
+      "This export was made by a newer version of Forge ({}). Update Forge to import it.",
+      manifest.app_version
+    ));
+  }
+
+  let mut tables = Tables::new();
+  for (name, count) in &manifest.tables {
+    let entry = archive
+      .by_name(&format!("{}/{}.json", TABLES_DIR, name))
+      .map_err(|_| format!("Export is missing table {}", name))?;
+    let rows: Vec<Row> =
+      serde_json::from_reader(entry).map_err(|e| format!("Corrupt table {}: {}", name, e))?;
+    if rows.len() != *count {
+      return Err(format!("Table {} has {} rows, expected {}", name, rows.len(), count));
+    }
+    tables.insert(name.clone(), rows);
+  }
+
+  for attachment in &manifest.attachments {
+    let mut bytes = Vec::new();
+    archive
+      .by_name(&attachment.path)
+      .map_err(|_| format!("Export is missing {}", attachment.path))?
+      .read_to_end(&mut bytes)
+      .map_err(|e| format!("Failed to read {}: {}", attachment.path, e))?;
+    if backup::hex(Sha256::digest(&bytes)) != attachment.sha256 {
+      return Err(format!("Attachment {} is damaged", attachment.path));
+    }
+    let row = tables
+      .get_mut(&attachment.table)
+      .and_then(|rows| {
+        rows
+          .iter_mut()
+          .find(|row| row.get("id").and_then(Value::as_i64) == Some(attachment.id))
+      })
+      .ok_or_else(|| format!("Attachment {} has no matching row", attachment.path))?;
+    row.insert(attachment.column.clone(), Value::String(BASE64.encode(&bytes)));
+  }
+
+  Ok((manifest, tables))
+}
+
+/// Knowledge base folders are named after user ids, which change on a merge
+fn remap_user_dir(relative: &Path, user_ids: &BTreeMap<String, i64>) -> PathBuf {
+  let mut components = relative.components();
+  let remapped = match components.next() {
+    Some(Component::Normal(first)) => first
+      .to_str()
+      .and_then(|first| first.strip_prefix("user_"))
+      .and_then(|old| user_ids.get(old))
+      .map(|new| PathBuf::from(format!("user_{}", new)).join(components.as_path())),
+    _ => None,
+  };
+  remapped.unwrap_or_else(|| relative.to_path_buf())
+}
+
+/// Writes the exported files under `root/` into `dir`. A replace swaps the
+/// whole directory; a merge only adds files that are not there yet.
+fn restore_files(
+  path: &Path,
+  root: &str,
+  dir: &Path,
+  mode: ImportMode,
+  user_ids: &BTreeMap<String, i64>,
+) -> Result<usize, String> {
+  let io_err = |e: io::Error| format!("Failed to restore files into {}: {}", dir.display(), e);
+  let mut archive = open_export(path)?;
+  let target = match mode {
+    ImportMode::Merge => dir.to_path_buf(),
+    ImportMode::Replace => {
+      let staged = with_suffix(dir, ".import");
+      let _ = fs::remove_dir_all(&staged);
+      staged
+    }
+  };
+  fs::create_dir_all(&target).map_err(io_err)?;
+
+  let mut files = 0;
+  for i in 0..archive.len() {
+    let mut entry = archive.by_index(i).map_err(|e| format!("Corrupt export: {}", e))?;
+    if entry.is_dir() {
+      continue;
+    }
+    // `enclosed_name` rejects absolute paths and `..`
+    let Some(name) = entry.enclosed_name() else {
+      return Err(format!("Export contains an unsafe path: {}", entry.name()));
+    };
+    let Ok(relative) = name.strip_prefix(root) else {
+      continue;
+    };
+    let dest = target.join(remap_user_dir(relative, user_ids));
+    if mode == ImportMode::Merge && dest.exists() {
+      continue;
+    }
+    if let Some(parent) = dest.parent() {
+      fs::create_dir_all(parent).map_err(io_err)?;
+    }
+    let mut out = BufWriter::new(File::create(&dest).map_err(io_err)?);
+    io::copy(&mut entry, &mut out).map_err(io_err)?;
+    out.flush().map_err(io_err)?;
+    files += 1;
+  }
+
+  if mode == ImportMode::Replace {
+    let previous = with_suffix(dir, ".pre-import");
+    let _ = fs::remove_dir_all(&previous);
+    if dir.exists() {
+      fs::rename(dir, &previous).map_err(io_err)?;
+    }
+    if let Err(e) = fs::rename(&target, dir) {
+      let _ = fs::rename(&previous, dir);
+      return Err(io_err(e));
+    }
+    let _ = fs::remove_dir_all(&previous);
+  }
+  Ok(files)
+}
+
+/// Writes everything the backend knows about to a `.forge` archive at `dest`
+pub async fn export(app: &AppHandle, dest: PathBuf) -> Result<ExportSummary, String> {
+  ensure_ready(app)?;
+  let dest = if dest.extension().is_some_and(|ext| ext == EXPORT_EXTENSION) {
+    dest
+  } else {
+    with_suffix(&dest, &format!(".{}", EXPORT_EXTENSION))
+  };
+
+  log::info!("Exporting data to {}", dest.display());
+  let client = reqwest::Client::new();
+  let res = backend::internal_request(app, &client, reqwest::Method::GET, EXPORT_PATH)
+    .send()
+    .await
+    .map_err(|e| format!("Could not reach the backend: {}", e))?;
+  let data: ExportResponse = check(res)
+    .await?
+    .json()
+    .await
+    .map_err(|e| format!("Unexpected export response: {}", e))?;
+
+  let sources = file_sources(app)?;
+  let app_version = app.package_info().version.to_string();
+  let target = dest.clone();
+  let manifest = tauri::async_runtime::spawn_blocking(move || {
+    let partial = with_suffix(&target, ".partial");
+    let result = write_export(&partial, data.tables, &sources, &app_version).and_then(|manifest| {
+      fs::rename(&partial, &target)
+        .map(|_| manifest)
+        .map_err(|e| format!("Failed to write {}: {}", target.display(), e))
+    });
+    if result.is_err() {
+      let _ = fs::remove_file(&partial);
+    }
+    result
+  })
+  .await
+  .map_err(|e| format!("Export task failed: {}", e))??;
+
+  log::info!(
+    "Exported {} tables, {} attachments and {} files",
+    manifest.tables.len(),
+    manifest.attachments.len(),
+    manifest.files
+  );
+  Ok(ExportSummary {
+    path: dest.to_string_lossy().into_owned(),
+    tables: manifest.tables,
+    attachments: manifest.attachments.len(),
+    files: manifest.files,
+  })
+}
+
+/// Loads a `.forge` archive into the backend
+pub async fn import(
+  app: &AppHandle,
+  source: PathBuf,
+  mode: ImportMode,
+) -> Result<ImportSummary, String> {
+  ensure_ready(app)?;
+  let path = source.clone();
+  let (manifest, tables) = tauri::async_runtime::spawn_blocking(move || read_export(&path))
+    .await
+    .map_err(|e| format!("Import task failed: {}", e))??;
+
+  log::info!("Importing {} ({:?})", source.display(), mode);
+  let client = reqwest::Client::new();
+  let res = backend::internal_request(app, &client, reqwest::Method::POST, IMPORT_PATH)
+    .json(&ImportRequest { mode, tables: &tables })
+    .send()
+    .await
+    .map_err(|e| format!("Could not reach the backend: {}", e))?;
+  drop(tables);
+  let result: ImportResponse = check(res)
+    .await?
+    .json()
+    .await
+    .map_err(|e| format!("Unexpected import response: {}", e))?;
+  if mode == ImportMode::Replace {
+    vectors::reset(app);
+  }
+
+  let sources = file_sources(app)?;
+  let user_ids = result.user_ids;
+  let files = tauri::async_runtime::spawn_blocking(move || {
+    sources.iter().try_fold(0, |files, (root, dir)| {
+      restore_files(&source, root, dir, mode, &user_ids).map(|n| files + n)
+    })
+  })
+  .await
+  .map_err(|e| format!("Import task failed: {}", e))??;
+
+  log::info!("Import complete ({} files)", files);
+  Ok(ImportSummary {
+    mode,
+    exported_at: manifest.exported_at,
+    imported: result.imported,
+    files,
+  })
+}
+
+#[tauri::command]
+pub async fn export_data(app: AppHandle, path: String) -> Result<ExportSummary, String> {
+  export(&app, PathBuf::from(path)).await
+}
+
+#[tauri::command]
+pub async fn import_data(
+  app: AppHandle,
+  path: String,
+  mode: ImportMode,
+) -> Result<ImportSummary, String> {
+  import(&app, PathBuf::from(path), mode).await
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test_util::TempDir;
+  use serde_json::json;
+
+  fn tables() -> Tables {
+    let rows = |value: Value| serde_json::from_value::<Vec<Row>>(value).unwrap();
+    Tables::from([
+      (
+        "proofs".to_string(),
+        rows(json!([
+          { "id": 1, "userId": 3, "fileData": BASE64.encode(b"%PDF-1.4 proof") },
+          { "id": 2, "userId": 3, "fileData": "not base64!" },
+          { "id": 3, "userId": 3, "fileData": null },
+        ])),
+      ),
+      ("users".to_string(), rows(json!([{ "id": 3, "email": "sam@example.com" }]))),
+    ])
+  }
+
+  fn export(dir: &TempDir, sources: &[(&'static str, PathBuf)]) -> PathBuf {
+    let path = dir.path().join("data.forge");
+    write_export(&path, tables(), sources, "1.0.0").unwrap();
+    path
+  }
+
+  /// Copies the export at `path`, swapping in `replacements` by entry name
+  fn rewrite(path: &Path, replacements: &[(&str, &[u8])]) {
+    let mut archive = open_export(path).unwrap();
+    let mut entries = Vec::new();
+    for i in 0..archive.len() {
+      let mut entry = archive.by_index(i).unwrap();
+      let mut contents = Vec::new();
+      entry.read_to_end(&mut contents).unwrap();
+      entries.push((entry.name().to_string(), contents));
+    }
+    for (name, contents) in replacements {
+      match entries.iter_mut().find(|(n, _)| n == name) {
+        Some(entry) => entry.1 = contents.to_vec(),
+        None => entries.push((name.to_string(), contents.to_vec())),
+      }
+    }
+    let mut zip = ZipWriter::new(File::create(path).unwrap());
+    for (name, contents) in entries {
+      zip.start_file(name, SimpleFileOptions::default()).unwrap();
+      zip.write_all(&contents).unwrap();
+    }
+    zip.finish().unwrap();
+  }
+
+  fn manifest_of(path: &Path) -> ExportManifest {
+    serde_json::from_reader(open_export(path).unwrap().by_name(MANIFEST_NAME).unwrap()).unwrap()
+  }
+
+  fn read(path: &Path) -> String {
+    fs::read_to_string(path).unwrap()
+  }
+
+  fn ids(pairs: &[(&str, i64)]) -> BTreeMap<String, i64> {
+    pairs.iter().map(|(old, new)| (old.to_string(), *new)).collect()
+  }
+
+  #[test]
+  fn export_round_trips() {
+    let dir = TempDir::new();
+    dir.file("kb/user_3/notes.md", "# Notes");
+    let path = export(&dir, &[(KB_FILES_DIR, dir.path().join("kb"))]);
+
+    let (manifest, restored) = read_export(&path).unwrap();
+    assert_eq!(restored, tables());
+    assert_eq!(manifest.tables, BTreeMap::from([("proofs".into(), 3), ("users".into(), 1)]));
+    assert_eq!(manifest.files, 1);
+    // Only the valid base64 value moves out of the table
+    assert_eq!(manifest.attachments.len(), 1);
+    assert_eq!(manifest.attachments[0].path, "attachments/proofs/1-fileData");
+
+    let mut archive = open_export(&path).unwrap();
+    let mut attachment = Vec::new();
+    archive.by_name("attachments/proofs/1-fileData").unwrap().read_to_end(&mut attachment).unwrap();
+    assert_eq!(attachment, b"%PDF-1.4 proof");
+    let rows: Vec<Row> = serde_json::from_reader(archive.by_name("tables/proofs.json").unwrap())
+      .unwrap();
+    assert_eq!(rows[0]["fileData"], Value::Null);
+  }
+
+  #[test]
+  fn read_export_rejects_a_damaged_attachment() {
+    let dir = TempDir::new();
+    let path = export(&dir, &[]);
+    rewrite(&path, &[("attachments/proofs/1-fileData", b"%PDF-1.4 forged")]);
+    let err = read_export(&path).unwrap_err();
+    assert_eq!(err, "Attachment attachments/proofs/1-fileData is damaged");
+  }
+
+  #[test]
+  fn read_export_rejects_a_truncated_table() {
+    let dir = TempDir::new();
+    let path = export(&dir, &[]);
+    rewrite(&path, &[("tables/users.json", b"[]")]);
+    assert_eq!(read_export(&path).unwrap_err(), "Table users has 0 rows, expected 1");
+  }
+
+  #[test]
+  fn read_export_rejects_a_newer_version() {
+    let dir = TempDir::new();
+    let path = export(&dir, &[]);
+    let mut manifest = manifest_of(&path);
+    manifest.version = EXPORT_VERSION + 1;
+    manifest.app_version = "9.0.0".to_string();
+    rewrite(&path, &[(MANIFEST_NAME, &serde_json::to_vec(&manifest).unwrap())]);
+    let err = read_export(&path).unwrap_err();
+    assert!(err.contains("newer version of Forge (9.0.0)"), "{}", err);
+  }
+
+  #[test]
+  fn read_export_only_reads_attachments_from_the_archive() {
+    let dir = TempDir::new();
+    let outside = dir.file("secret.txt", "outside");
+    let path = export(&dir, &[]);
+    let mut manifest = manifest_of(&path);
+    manifest.attachments[0].path = format!("../{}", outside.file_name().unwrap().to_str().unwrap());
+    manifest.attachments[0].sha256 = backup::hex(Sha256::digest(b"outside"));
+    rewrite(&path, &[(MANIFEST_NAME, &serde_json::to_vec(&manifest).unwrap())]);
+    assert_eq!(read_export(&path).unwrap_err(), "Export is missing ../secret.txt");
+  }
+
+  #[test]
+  fn restore_files_refuses_paths_outside_the_target() {
+    let dir = TempDir::new();
+    let path = export(&dir, &[]);
+    for name in ["files/forge_kb/../../../escaped.md", "/files/forge_kb/absolute.md"] {
+      rewrite(&path, &[(name, b"x")]);
+      let kb = dir.path().join("kb");
+      let err = restore_files(&path, KB_FILES_DIR, &kb, ImportMode::Merge, &BTreeMap::new())
+        .unwrap_err();
+      assert!(err.contains("unsafe path"), "{}", err);
+      assert!(!dir.path().join("escaped.md").exists());
+    }
+  }
+
+  #[test]
+  fn remap_user_dir_renames_only_known_user_folders() {
+    let user_ids = ids(&[("3", 7)]);
+    let remap = |path: &str| remap_user_dir(Path::new(path), &user_ids);
+    assert_eq!(remap("user_3/notes/week1.md"), Path::new("user_7/notes/week1.md"));
+    assert_eq!(remap("user_4/notes.md"), Path::new("user_4/notes.md"));
+    assert_eq!(remap("shared/user_3/notes.md"), Path::new("shared/user_3/notes.md"));
+    assert_eq!(remap("user_3"), Path::new("user_7"));
+  }
+
+  #[test]
+  fn merge_adds_missing_files_under_the_new_user_ids() {
+    let dir = TempDir::new();
+    dir.file("exported/user_3/a.md", "exported a");
+    dir.file("exported/user_3/b.md", "exported b");
+    let path = export(&dir, &[(KB_FILES_DIR, dir.path().join("exported"))]);
+    dir.file("kb/user_7/a.md", "local a");
+    dir.file("kb/user_1/c.md", "local c");
+
+    let kb = dir.path().join("kb");
+    let files = restore_files(&path, KB_FILES_DIR, &kb, ImportMode::Merge, &ids(&[("3", 7)]))
+      .unwrap();
+    assert_eq!(files, 1);
+    assert_eq!(read(&kb.join("user_7/a.md")), "local a");
+    assert_eq!(read(&kb.join("user_7/b.md")), "exported b");
+    assert_eq!(read(&kb.join("user_1/c.md")), "local c");
+    assert!(!kb.join("user_3").exists());
+  }
+
+  #[test]
+  fn replace_swaps_the_whole_directory() {
+    let dir = TempDir::new();
+    dir.file("exported/user_3/a.md", "exported a");
+    let path = export(&dir, &[(KB_FILES_DIR, dir.path().join("exported"))]);
+    dir.file("kb/user_3/a.md", "local a");
+    dir.file("kb/user_1/c.md", "local c");
+
+    let kb = dir.path().join("kb");
+    let files = restore_files(&path, KB_FILES_DIR, &kb, ImportMode::Replace, &BTreeMap::new())
+      .unwrap();
+    assert_eq!(files, 1);
+    assert_eq!(read(&kb.join("user_3/a.md")), "exported a");
+    assert!(!kb.join("user_1").exists());
+    assert!(!dir.path().join("kb.import").exists());
+    assert!(!dir.path().join("kb.pre-import").exists());
+  }
+}
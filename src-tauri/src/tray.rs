@@ -0,0 +1,444 @@
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use chrono::{Local, NaiveTime};
+use serde::{Deserialize, Serialize};
+use tauri::menu::{Menu, MenuEvent, MenuItem, PredefinedMenuItem};
+use tauri::tray::{MouseButton, MouseButtonState, TrayIcon, TrayIconBuilder, TrayIconEvent};
+use tauri::{AppHandle, Emitter, Listener, Manager, Wry};
+
+use crate::backend::{self, BackendPhase, BackendState};
+use crate::focus::{self, FocusTarget};
+use crate::lifecycle;
+use crate::local_db;
+use crate::store::{read_json, write_json};
+
+const SETTINGS_FILE_NAME: &str = "tray-settings.json";
+const TIME_FORMAT: &str = "%H:%M";
+// Blocks start on the minute, so the menu never lags by more than this
+const REFRESH_INTERVAL: Duration = Duration::from_secs(30);
+pub const EVENT_START_FOCUS: &str = "tray://start-focus";
+
+const ITEM_OPEN: &str = "open";
+const ITEM_FOCUS: &str = "focus";
+const ITEM_BACKEND: &str = "backend";
+const ITEM_QUIT: &str = "quit";
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TraySettings {
+  // Closing the main window hides it and leaves the backend, reminders and
+  // scheduled work running. Off until the user asks for it, so closing the
+  // window quits as it does everywhere else.
+  pub close_to_tray: bool,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrayBlock {
+  pub title: String,
+  // `HH:MM`, as in the schedule's time blocks
+  pub start_time: String,
+  pub end_time: String,
+}
+
+/// Today's schedule as the webview last loaded it. The shell has no session
+/// of its own, so the signed-in page pushes this whenever it changes.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TraySummary {
+  // Signed-in user, so the tray can start a focus session for them
+  #[serde(default)]
+  pub user_id: Option<i64>,
+  // Local `YYYY-MM-DD`
+  pub date: String,
+  pub blocks: Vec<TrayBlock>,
+  pub missions_total: usize,
+  pub missions_completed: usize,
+}
+
+#[derive(Clone)]
+struct TrayItems {
+  now: MenuItem<Wry>,
+  next: MenuItem<Wry>,
+  missions: MenuItem<Wry>,
+  focus: MenuItem<Wry>,
+  backend: MenuItem<Wry>,
+}
+
+#[derive(Default)]
+pub struct TrayState {
+  summary: Mutex<Option<TraySummary>>,
+  items: Mutex<Option<TrayItems>>,
+  icon: Mutex<Option<TrayIcon<Wry>>>,
+}
+
+fn settings_path(app: &AppHandle) -> Result<PathBuf, String> {
+  app
+    .path()
+    .app_config_dir()
+    .map(|dir| dir.join(SETTINGS_FILE_NAME))
+    .map_err(|e| format!("Failed to resolve config directory: {}", e))
+}
+
+pub fn load_settings(app: &AppHandle) -> TraySettings {
+  settings_path(app).and_then(|path| read_json(&path)).unwrap_or_else(|e| {
+    log::warn!("{}", e);
+    TraySettings::default()
+  })
+}
+
+fn parse_time(value: &str) -> Option<NaiveTime> {
+  NaiveTime::parse_from_str(value.trim(), TIME_FORMAT).ok()
+}
+
+/// The "Now" and "Next" menu lines for `time`
+fn block_lines(
+  summary: Option<&TraySummary>,
+  today: &str,
+  time: NaiveTime,
+) -> (String, String) {
+  let Some(summary) = summary.filter(|s| s.date == today) else {
+    return ("Open Forge to load today's schedule".to_string(), String::new());
+  };
+  let mut blocks: Vec<(NaiveTime, NaiveTime, &str)> = summary
+    .blocks
+    .iter()
+    .filter_map(|b| {
+      Some((parse_time(&b.start_time)?, parse_time(&b.end_time)?, b.title.as_str()))
+    })
+    .collect();
+  if blocks.is_empty() {
+    return ("No schedule for today".to_string(), String::new());
+  }
+  blocks.sort_by_key(|(start, _, _)| *start);
+
+  let now = match blocks.iter().find(|(start, end, _)| *start <= time && time < *end) {
+    Some((_, end, title)) => format!("Now: {} (until {})", title, end.format(TIME_FORMAT)),
+    None => "Now: free time".to_string(),
+  };
+  let next = match blocks.iter().find(|(start, _, _)| *start > time) {
+    Some((start, _, title)) => format!("Next: {} at {}", title, start.format(TIME_FORMAT)),
+    None => "Next: nothing else today".to_string(),
+  };
+  (now, next)
+}
+
+/// A focus target for the block running at `time`
+fn current_block(summary: &TraySummary, time: NaiveTime) -> Option<FocusTarget> {
+  summary.blocks.iter().find_map(|b| {
+    let (start, end) = (parse_time(&b.start_time)?, parse_time(&b.end_time)?);
+    (start <= time && time < end).then(|| FocusTarget::Block {
+      schedule_date: summary.date.clone(),
+      start_time: b.start_time.clone(),
+      title: b.title.clone(),
+      planned_minutes: (end - start).num_minutes(),
+    })
+  })
+}
+
+fn missions_line(summary: Option<&TraySummary>, today: &str) -> String {
+  match summary.filter(|s| s.date == today) {
+    Some(s) => format!("Missions today: {} of {} done", s.missions_completed, s.missions_total),
+    None => "Missions today: -".to_string(),
+  }
+}
+
+fn backend_paused(app: &AppHandle) -> bool {
+  matches!(app.state::<BackendState>().phase(), BackendPhase::Stopped | BackendPhase::Crashed)
+}
+
+/// Brings the menu text up to date with the clock, the summary and the
+/// backend. Menu and tray handles are cloned out of their locks first:
+/// away from the main thread their setters wait on it, and the main thread
+/// calls this too.
+pub fn refresh(app: &AppHandle) {
+  let state = app.state::<TrayState>();
+  let Some(items) = state.items.lock().unwrap().clone() else {
+    return;
+  };
+  let summary = state.summary.lock().unwrap().clone();
+  let now = Local::now();
+  let today = now.format("%Y-%m-%d").to_string();
+
+  let (now_line, next_line) = block_lines(summary.as_ref(), &today, now.time());
+  let missions_line = missions_line(summary.as_ref(), &today);
+  let focusing = focus::status(app).is_some();
+  let paused = backend_paused(app);
+  let tooltip = if paused {
+    "Forge (paused)".to_string()
+  } else {
+    format!("Forge - {}", now_line)
+  };
+  let icon = state.icon.lock().unwrap().clone();
+
+  let _ = items.now.set_text(&now_line);
+  let _ = items.next.set_text(&next_line);
+  let _ = items.missions.set_text(missions_line);
+  let _ = items.focus.set_text(if focusing { "Stop focus session" } else { "Start focus session" });
+  let _ = items.backend.set_text(if paused { "Resume backend" } else { "Pause backend" });
+  if let Some(icon) = icon {
+    let _ = icon.set_tooltip(Some(tooltip));
+  }
+}
+
+fn toggle_backend(app: &AppHandle) {
+  let app = app.clone();
+  tauri::async_runtime::spawn(async move {
+    if backend_paused(&app) {
+      log::info!("Resuming backend from the tray");
+      if let Err(e) = backend::start_backend(app.clone()).await {
+        log::error!("Failed to resume backend: {}", e);
+      }
+    } else {
+      log::info!("Pausing backend from the tray");
+      backend::shutdown_backend(&app, backend::SHUTDOWN_GRACE).await;
+      local_db::stop(&app).await;
+    }
+    refresh(&app);
+  });
+}
+
+/// Stops the running focus session, or starts one for the current block.
+/// Outside any block the webview is asked to pick what to focus on.
+fn toggle_focus(app: &AppHandle) {
+  if focus::status(app).is_some() {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+      if let Err(e) = focus::stop(&app).await {
+        log::warn!("Failed to stop focus session: {}", e);
+      }
+    });
+    return;
+  }
+
+  let summary = app.state::<TrayState>().summary.lock().unwrap().clone();
+  let now = Local::now();
+  let today = now.format("%Y-%m-%d").to_string();
+  let block = summary.filter(|s| s.date == today).and_then(|s| {
+    let target = current_block(&s, now.time())?;
+    Some((s.user_id?, target))
+  });
+  match block {
+    Some((user_id, target)) => {
+      if let Err(e) = focus::start(app, user_id, target) {
+        log::warn!("Failed to start focus session: {}", e);
+      }
+    }
+    None => {
+      lifecycle::show_current_window(app);
+      let _ = app.emit(EVENT_START_FOCUS, ());
+    }
+  }
+}
+
+fn on_menu_event(app: &AppHandle, event: MenuEvent) {
+  match event.id().as_ref() {
+    ITEM_OPEN => lifecycle::show_current_window(app),
+    ITEM_FOCUS => toggle_focus(app),
+    ITEM_BACKEND => toggle_backend(app),
+    // `handle_run_event` shuts the backend down before the process exits
+    ITEM_QUIT => app.exit(0),
+    _ => {}
+  }
+}
+
+/// Builds the tray icon and keeps its menu current
+pub fn create(app: &AppHandle) -> tauri::Result<()> {
+  let info = |text: &str| MenuItem::new(app, text, false, None::<&str>);
+  let items = TrayItems {
+    now: info("")?,
+    next: info("")?,
+    missions: info("")?,
+    focus: MenuItem::with_id(app, ITEM_FOCUS, "Start focus session", true, None::<&str>)?,
+    backend: MenuItem::with_id(app, ITEM_BACKEND, "Pause backend", true, None::<&str>)?,
+  };
+  let menu = Menu::with_items(
+    app,
+    &[
+      &items.now,
+      &items.next,
+      &items.missions,
+      &PredefinedMenuItem::separator(app)?,
+      &MenuItem::with_id(app, ITEM_OPEN, "Open Forge", true, None::<&str>)?,
+      &items.focus,
+      &items.backend,
+      &PredefinedMenuItem::separator(app)?,
+      &MenuItem::with_id(app, ITEM_QUIT, "Quit", true, None::<&str>)?,
+    ],
+  )?;
+
+  let mut builder = TrayIconBuilder::with_id("main")
+    .menu(&menu)
+    .show_menu_on_left_click(false)
+    .on_menu_event(on_menu_event)
+    .on_tray_icon_event(|tray, event| {
+      if let TrayIconEvent::Click {
+        button: MouseButton::Left,
+        button_state: MouseButtonState::Up,
+        ..
+      } = event
+      {
+        lifecycle::show_current_window(tray.app_handle());
+      }
+    });
+  if let Some(icon) = app.default_window_icon() {
+    builder = builder.icon(icon.clone());
+  }
+  let icon = builder.build(app)?;
+
+  let state = app.state::<TrayState>();
+  *state.items.lock().unwrap() = Some(items);
+  *state.icon.lock().unwrap() = Some(icon);
+  refresh(app);
+
+  // Sessions started or stopped from the webview
+  let handle = app.clone();
+  app.listen(focus::EVENT_CHANGED, move |_| refresh(&handle));
+
+  let app = app.clone();
+  tauri::async_runtime::spawn(async move {
+    let mut interval = tokio::time::interval(REFRESH_INTERVAL);
+    loop {
+      interval.tick().await;
+      refresh(&app);
+    }
+  });
+  Ok(())
+}
+
+#[tauri::command]
+pub fn set_tray_summary(app: AppHandle, summary: TraySummary) {
+  *app.state::<TrayState>().summary.lock().unwrap() = Some(summary);
+  refresh(&app);
+}
+
+#[tauri::command]
+pub fn get_tray_settings(app: AppHandle) -> TraySettings {
+  load_settings(&app)
+}
+
+#[tauri::command]
+pub fn save_tray_settings(app: AppHandle, settings: TraySettings) -> Result<(), String> {
+  write_json(&settings_path(&app)?, &settings)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const TODAY: &str = "2026-10-15";
+
+  fn at(time: &str) -> NaiveTime {
+    parse_time(time).unwrap()
+  }
+
+  fn summary(date: &str, blocks: &[(&str, &str, &str)]) -> TraySummary {
+    TraySummary {
+      user_id: Some(1),
+      date: date.to_string(),
+      blocks: blocks
+        .iter()
+        .map(|(title, start, end)| TrayBlock {
+          title: title.to_string(),
+          start_time: start.to_string(),
+          end_time: end.to_string(),
+        })
+        .collect(),
+      missions_total: 5,
+      missions_completed: 2,
+    }
+  }
+
+  /// Out of order, with free time from 10:30 to 13:00
+  fn day() -> TraySummary {
+    let blocks = [
+      ("Lab report", "13:00", "14:30"),
+      ("Calculus", "09:00", "10:00"),
+      ("Reading", "10:00", "10:30"),
+    ];
+    summary(TODAY, &blocks)
+  }
+
+  fn lines(summary: &TraySummary, time: &str) -> (String, String) {
+    block_lines(Some(summary), TODAY, at(time))
+  }
+
+  fn pair(now: &str, next: &str) -> (String, String) {
+    (now.to_string(), next.to_string())
+  }
+
+  #[test]
+  fn block_lines_ask_for_a_schedule_until_today_is_loaded() {
+    let prompt = pair("Open Forge to load today's schedule", "");
+    assert_eq!(block_lines(None, TODAY, at("09:00")), prompt);
+    let yesterday = summary("2026-10-14", &[("Calculus", "09:00", "10:00")]);
+    assert_eq!(lines(&yesterday, "09:30"), prompt);
+  }
+
+  #[test]
+  fn block_lines_without_usable_blocks() {
+    let empty = pair("No schedule for today", "");
+    assert_eq!(lines(&summary(TODAY, &[]), "09:00"), empty);
+    assert_eq!(lines(&summary(TODAY, &[("Calculus", "9am", "10:00")]), "09:00"), empty);
+  }
+
+  #[test]
+  fn block_lines_follow_the_day_in_time_order() {
+    let day = day();
+    assert_eq!(lines(&day, "08:00"), pair("Now: free time", "Next: Calculus at 09:00"));
+    assert_eq!(
+      lines(&day, "09:15"),
+      pair("Now: Calculus (until 10:00)", "Next: Reading at 10:00")
+    );
+    // A block ends as the next one starts
+    assert_eq!(
+      lines(&day, "10:00"),
+      pair("Now: Reading (until 10:30)", "Next: Lab report at 13:00")
+    );
+    assert_eq!(lines(&day, "11:45"), pair("Now: free time", "Next: Lab report at 13:00"));
+  }
+
+  #[test]
+  fn block_lines_after_the_last_block_starts() {
+    let day = day();
+    assert_eq!(
+      lines(&day, "14:00"),
+      pair("Now: Lab report (until 14:30)", "Next: nothing else today")
+    );
+    assert_eq!(lines(&day, "14:30"), pair("Now: free time", "Next: nothing else today"));
+  }
+
+  #[test]
+  fn current_block_targets_the_running_block() {
+    let day = day();
+    let target = |time: &str| match current_block(&day, at(time)) {
+      Some(FocusTarget::Block { schedule_date, start_time, title, planned_minutes }) => {
+        assert_eq!(schedule_date, TODAY);
+        Some((start_time, title, planned_minutes))
+      }
+      Some(other) => panic!("unexpected target {:?}", other),
+      None => None,
+    };
+    assert_eq!(target("13:00"), Some(("13:00".to_string(), "Lab report".to_string(), 90)));
+    assert_eq!(target("10:00"), Some(("10:00".to_string(), "Reading".to_string(), 30)));
+    assert_eq!(target("08:59"), None);
+    assert_eq!(target("12:00"), None);
+    assert_eq!(target("14:30"), None);
+  }
+
+  #[test]
+  fn missions_line_counts_only_today() {
+    assert_eq!(missions_line(Some(&day()), TODAY), "Missions today: 2 of 5 done");
+    assert_eq!(missions_line(Some(&day()), "2026-10-16"), "Missions today: -");
+    assert_eq!(missions_line(None, TODAY), "Missions today: -");
+  }
+
+  #[test]
+  fn close_to_tray_is_off_by_default() {
+    assert!(!TraySettings::default().close_to_tray);
+    let saved: TraySettings = serde_json::from_str("{}").unwrap();
+    assert!(!saved.close_to_tray);
+  }
+}
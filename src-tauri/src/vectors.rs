@@ -0,0 +1,905 @@
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::net::{Ipv4Addr, TcpListener};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard, OnceLock};
+
+use axum::extract::{DefaultBodyLimit, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::routing::post;
+use axum::{Json, Router};
+use percent_encoding::{percent_decode_str, utf8_percent_encode, CONTROLS};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::{AppHandle, Manager};
+
+use crate::backend::{self, BackendState};
+use crate::chunker::{self, ChunkRequest};
+
+const VECTORS_DIR_NAME: &str = "vectors";
+const PARTITION_PREFIX: &str = "course_";
+const PARTITION_EXTENSION: &str = "fvec";
+const USER_PREFIX: &str = "user_";
+const MAGIC: &[u8; 4] = b"FVEC";
+const FORMAT: u32 = 1;
+const DEFAULT_TOP_K: usize = 4;
+// A few thousand chunks of 384-dimension embeddings as JSON
+const MAX_BODY_BYTES: usize = 64 * 1024 * 1024;
+
+pub type Metadata = serde_json::Map<String, Value>;
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VectorItem {
+  pub id: String,
+  pub vector: Vec<f32>,
+  #[serde(default)]
+  pub text: String,
+  #[serde(default)]
+  pub metadata: Metadata,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VectorMatch {
+  pub id: String,
+  pub course: String,
+  pub text: String,
+  pub metadata: Metadata,
+  // Cosine similarity, -1 to 1
+  pub score: f32,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueryResult {
+  pub matches: Vec<VectorMatch>,
+  // Entries in the searched partitions, so callers can tell an empty index
+  // from one that is missing data
+  pub total: usize,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InsertRequest {
+  pub user_id: i64,
+  pub course: String,
+  pub items: Vec<VectorItem>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueryRequest {
+  pub user_id: i64,
+  // Every course of the user when absent
+  pub course: Option<String>,
+  pub vector: Vec<f32>,
+  pub top_k: Option<usize>,
+  #[serde(default)]
+  pub filter: Metadata,
+  pub min_score: Option<f32>,
+}
+
+/// Removes the user's entries matching both `ids` and `filter`; with
+/// neither, the whole partition. A missing course widens the scope to every
+/// course of the user, never to other users.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeleteRequest {
+  pub user_id: i64,
+  pub course: Option<String>,
+  pub ids: Option<Vec<String>>,
+  #[serde(default)]
+  pub filter: Metadata,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct Entry {
+  id: String,
+  text: String,
+  metadata: Metadata,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Header {
+  dim: usize,
+  entries: Vec<Entry>,
+}
+
+/// One user's vectors for one course. Vectors are stored normalized and
+/// back to back, so a query is a straight run of dot products.
+#[derive(Default)]
+struct Partition {
+  dim: usize,
+  entries: Vec<Entry>,
+  vectors: Vec<f32>,
+}
+
+impl Partition {
+  fn vector(&self, i: usize) -> &[f32] {
+    &self.vectors[i * self.dim..(i + 1) * self.dim]
+  }
+
+  fn retain(&mut self, mut keep: impl FnMut(&Entry) -> bool) -> usize {
+    let before = self.entries.len();
+    let mut entries = Vec::with_capacity(before);
+    let mut vectors = Vec::with_capacity(self.vectors.len());
+    for (i, entry) in std::mem::take(&mut self.entries).into_iter().enumerate() {
+      if keep(&entry) {
+        vectors.extend_from_slice(&self.vectors[i * self.dim..(i + 1) * self.dim]);
+        entries.push(entry);
+      }
+    }
+    self.entries = entries;
+    self.vectors = vectors;
+    before - self.entries.len()
+  }
+}
+
+type PartitionKey = (i64, String);
+
+/// On-disk vector index for the knowledge base, partitioned by user and
+/// course. Partitions are loaded on first use and written back after every
+/// change.
+pub struct VectorIndex {
+  partitions: Mutex<HashMap<PartitionKey, Partition>>,
+  url: OnceLock<String>,
+}
+
+impl VectorIndex {
+  pub fn new() -> Self {
+    Self { partitions: Mutex::new(HashMap::new()), url: OnceLock::new() }
+  }
+
+  /// Base URL of the HTTP endpoint, once it is listening
+  pub fn url(&self) -> Option<String> {
+    self.url.get().cloned()
+  }
+
+  fn lock(&self) -> MutexGuard<'_, HashMap<PartitionKey, Partition>> {
+    self.partitions.lock().unwrap_or_else(|e| e.into_inner())
+  }
+}
+
+pub fn vectors_dir(app: &AppHandle) -> Result<PathBuf, String> {
+  app
+    .path()
+    .app_data_dir()
+    .map(|dir| dir.join(VECTORS_DIR_NAME))
+    .map_err(|e| format!("Could not determine the data directory: {}", e))
+}
+
+// `\s` in JavaScript, which unlike `char::is_whitespace` takes in U+FEFF
+// and leaves out U+0085
+fn is_js_whitespace(c: char) -> bool {
+  (c.is_whitespace() && c != '\u{85}') || c == '\u{feff}'
+}
+
+/// Same rules as `sanitizeCourseCode` in server/llm/retriever.ts, so both
+/// sides agree on partition names
+fn sanitize_course(course: &str) -> Result<String, String> {
+  let sanitized: String = course
+    .chars()
+    .filter(|&c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-') || is_js_whitespace(c))
+    .collect::<String>()
+    .trim_matches(is_js_whitespace)
+    .to_uppercase();
+  if sanitized.is_empty() {
+    return Err(format!("Invalid course code: {:?}", course));
+  }
+  Ok(sanitized)
+}
+
+fn user_dir(root: &Path, user_id: i64) -> PathBuf {
+  root.join(format!("{}{}", USER_PREFIX, user_id))
+}
+
+// The prefix keeps course codes like `CON` clear of reserved Windows names.
+// Tabs and line breaks, which file names cannot hold everywhere, are
+// percent-encoded; sanitized codes never contain `%` themselves.
+fn partition_path(root: &Path, (user_id, course): &PartitionKey) -> PathBuf {
+  let course = utf8_percent_encode(course, CONTROLS);
+  user_dir(root, *user_id).join(format!("{}{}.{}", PARTITION_PREFIX, course, PARTITION_EXTENSION))
+}
+
+fn list_courses(root: &Path, user_id: i64) -> Vec<String> {
+  let Ok(entries) = fs::read_dir(user_dir(root, user_id)) else {
+    return Vec::new();
+  };
+  let mut courses: Vec<String> = entries
+    .flatten()
+    .filter_map(|e| {
+      let path = e.path();
+      if path.extension()? != PARTITION_EXTENSION {
+        return None;
+      }
+      let course = path.file_stem()?.to_str()?.strip_prefix(PARTITION_PREFIX)?;
+      Some(percent_decode_str(course).decode_utf8().ok()?.into_owned())
+    })
+    .collect();
+  courses.sort();
+  courses
+}
+
+fn decode(bytes: &[u8]) -> Result<Partition, String> {
+  if bytes.len() < 16 || &bytes[..4] != MAGIC {
+    return Err("not a vector partition".to_string());
+  }
+  let format = u32::from_le_bytes(bytes[4..8].try_into().unwrap());
+  if format != FORMAT {
+    return Err(format!("unsupported format {}", format));
+  }
+  let header_len = u64::from_le_bytes(bytes[8..16].try_into().unwrap()) as usize;
+  let header_end = 16usize.checked_add(header_len).filter(|&end| end <= bytes.len());
+  let header_end = header_end.ok_or("truncated header")?;
+  let header: Header = serde_json::from_slice(&bytes[16..header_end]).map_err(|e| e.to_string())?;
+
+  let data = &bytes[header_end..];
+  if data.len() != header.dim * header.entries.len() * 4 {
+    return Err("vector data does not match the header".to_string());
+  }
+  let vectors = data.chunks_exact(4).map(|b| f32::from_le_bytes(b.try_into().unwrap())).collect();
+  Ok(Partition { dim: header.dim, entries: header.entries, vectors })
+}
+
+/// `FVEC`, the format version and the JSON header's length, then the header
+/// and the vectors as little-endian `f32`s
+fn encode(partition: &Partition) -> Result<Vec<u8>, String> {
+  let header = Header { dim: partition.dim, entries: partition.entries.clone() };
+  let header = serde_json::to_vec(&header).map_err(|e| e.to_string())?;
+  let mut bytes = Vec::with_capacity(16 + header.len() + partition.vectors.len() * 4);
+  bytes.extend_from_slice(MAGIC);
+  bytes.extend_from_slice(&FORMAT.to_le_bytes());
+  bytes.extend_from_slice(&(header.len() as u64).to_le_bytes());
+  bytes.extend_from_slice(&header);
+  for value in &partition.vectors {
+    bytes.extend_from_slice(&value.to_le_bytes());
+  }
+  Ok(bytes)
+}
+
+fn read_partition(path: &Path) -> Result<Partition, String> {
+  decode(&fs::read(path).map_err(|e| e.to_string())?)
+}
+
+fn write_partition(path: &Path, partition: &Partition) -> Result<(), String> {
+  let io_err = |e: std::io::Error| format!("Failed to save {}: {}", path.display(), e);
+  if partition.entries.is_empty() {
+    return match fs::remove_file(path) {
+      Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(io_err(e)),
+      _ => Ok(()),
+    };
+  }
+  let bytes = encode(partition)?;
+  if let Some(parent) = path.parent() {
+    fs::create_dir_all(parent).map_err(io_err)?;
+  }
+  let tmp = path.with_extension("tmp");
+  fs::write(&tmp, bytes).map_err(io_err)?;
+  fs::rename(&tmp, path).map_err(io_err)
+}
+
+/// The cached partition for `key`, loading it from disk the first time.
+/// An unreadable file is dropped rather than failing every query, since the
+/// backend rebuilds missing entries from the database.
+fn partition<'a>(
+  partitions: &'a mut HashMap<PartitionKey, Partition>,
+  root: &Path,
+  key: &PartitionKey,
+) -> &'a mut Partition {
+  partitions.entry(key.clone()).or_insert_with(|| {
+    let path = partition_path(root, key);
+    if !path.exists() {
+      return Partition::default();
+    }
+    read_partition(&path).unwrap_or_else(|e| {
+      log::warn!("Discarding unreadable vector partition {}: {}", path.display(), e);
+      Partition::default()
+    })
+  })
+}
+
+fn normalize(vector: &[f32]) -> Result<Vec<f32>, String> {
+  if vector.is_empty() || vector.iter().any(|v| !v.is_finite()) {
+    return Err("Vectors must be non-empty and finite".to_string());
+  }
+  let norm = vector.iter().map(|v| v * v).sum::<f32>().sqrt();
+  if norm == 0.0 {
+    return Err("Vectors must not be all zeros".to_string());
+  }
+  Ok(vector.iter().map(|v| v / norm).collect())
+}
+
+fn values_equal(a: &Value, b: &Value) -> bool {
+  match (a, b) {
+    // 3 and 3.0 are the same page
+    (Value::Number(a), Value::Number(b)) => a.as_f64() == b.as_f64(),
+    _ => a == b,
+  }
+}
+
+/// Every filter key must be present and equal; an array filter value
+/// matches any of its elements
+fn matches_filter(metadata: &Metadata, filter: &Metadata) -> bool {
+  filter.iter().all(|(key, expected)| {
+    let Some(actual) = metadata.get(key) else {
+      return false;
+    };
+    match expected {
+      Value::Array(options) => options.iter().any(|option| values_equal(actual, option)),
+      expected => values_equal(actual, expected),
+    }
+  })
+}
+
+impl VectorIndex {
+  /// Adds items to a partition, replacing any with the same id
+  pub fn insert(&self, root: &Path, request: InsertRequest) -> Result<usize, String> {
+    let key = (request.user_id, sanitize_course(&request.course)?);
+    let Some(dim) = request.items.first().map(|item| item.vector.len()) else {
+      return Ok(0);
+    };
+    let items = request
+      .items
+      .into_iter()
+      .map(|item| {
+        if item.vector.len() != dim {
+          let len = item.vector.len();
+          return Err(format!("Vector {} has {} dimensions, expected {}", item.id, len, dim));
+        }
+        let entry = Entry { id: item.id, text: item.text, metadata: item.metadata };
+        Ok((normalize(&item.vector)?, entry))
+      })
+      .collect::<Result<Vec<_>, String>>()?;
+
+    let mut partitions = self.lock();
+    let partition = partition(&mut partitions, root, &key);
+    if partition.entries.is_empty() {
+      partition.dim = dim;
+    } else if partition.dim != dim {
+      return Err(format!(
+        "Vectors for {} have {} dimensions, expected {}",
+        key.1, dim, partition.dim
+      ));
+    }
+
+    let mut positions: HashMap<String, usize> =
+      partition.entries.iter().enumerate().map(|(i, e)| (e.id.clone(), i)).collect();
+    let count = items.len();
+    for (vector, entry) in items {
+      match positions.get(&entry.id) {
+        Some(&i) => {
+          partition.vectors[i * dim..(i + 1) * dim].copy_from_slice(&vector);
+          partition.entries[i] = entry;
+        }
+        None => {
+          positions.insert(entry.id.clone(), partition.entries.len());
+          partition.vectors.extend_from_slice(&vector);
+          partition.entries.push(entry);
+        }
+      }
+    }
+    write_partition(&partition_path(root, &key), partition)?;
+    Ok(count)
+  }
+
+  /// Top-k entries by cosine similarity. Partitions whose dimensions differ
+  /// from the query, such as ones built by another embedding model, are
+  /// skipped.
+  pub fn query(&self, root: &Path, request: QueryRequest) -> Result<QueryResult, String> {
+    let query = normalize(&request.vector)?;
+    let courses = match &request.course {
+      Some(course) => vec![sanitize_course(course)?],
+      None => list_courses(root, request.user_id),
+    };
+    let top_k = request.top_k.unwrap_or(DEFAULT_TOP_K);
+
+    let mut partitions = self.lock();
+    let mut total = 0;
+    // (score, course, index) for a stable order among equal scores
+    let mut scored: Vec<(f32, &String, usize)> = Vec::new();
+    for course in &courses {
+      let key = (request.user_id, course.clone());
+      partition(&mut partitions, root, &key);
+    }
+    for course in &courses {
+      let Some(partition) = partitions.get(&(request.user_id, course.clone())) else {
+        continue;
+      };
+      total += partition.entries.len();
+      if partition.dim != query.len() {
+        continue;
+      }
+      for (i, entry) in partition.entries.iter().enumerate() {
+        if !matches_filter(&entry.metadata, &request.filter) {
+          continue;
+        }
+        let score: f32 = partition.vector(i).iter().zip(&query).map(|(a, b)| a * b).sum();
+        if request.min_score.is_some_and(|min| score < min) {
+          continue;
+        }
+        scored.push((score, course, i));
+      }
+    }
+    scored.sort_by(|a, b| {
+      b.0.partial_cmp(&a.0).unwrap_or(Ordering::Equal).then(a.1.cmp(b.1)).then(a.2.cmp(&b.2))
+    });
+    scored.truncate(top_k);
+
+    let matches = scored
+      .into_iter()
+      .map(|(score, course, i)| {
+        let entry = &partitions[&(request.user_id, course.clone())].entries[i];
+        VectorMatch {
+          id: entry.id.clone(),
+          course: course.clone(),
+          text: entry.text.clone(),
+          metadata: entry.metadata.clone(),
+          score,
+        }
+      })
+      .collect();
+    Ok(QueryResult { matches, total })
+  }
+
+  /// Returns the number of entries removed
+  pub fn delete(&self, root: &Path, request: DeleteRequest) -> Result<usize, String> {
+    let course = request.course.as_deref().map(sanitize_course).transpose()?;
+    let ids: Option<HashSet<String>> = request.ids.map(|ids| ids.into_iter().collect());
+    let courses = match course {
+      Some(course) => vec![course],
+      None => list_courses(root, request.user_id),
+    };
+
+    let mut partitions = self.lock();
+    let mut removed = 0;
+    for course in courses {
+      let key = (request.user_id, course);
+      let partition = partition(&mut partitions, root, &key);
+      let count = partition.retain(|entry| {
+        let selected = ids.as_ref().is_none_or(|ids| ids.contains(&entry.id));
+        !(selected && matches_filter(&entry.metadata, &request.filter))
+      });
+      if count > 0 {
+        write_partition(&partition_path(root, &key), partition)?;
+        removed += count;
+      }
+    }
+    Ok(removed)
+  }
+}
+
+/// Forgets every partition, on disk and in memory. Used when the database
+/// is swapped out from under the index; the backend refills it as courses
+/// are searched.
+pub fn reset(app: &AppHandle) {
+  let index = app.state::<VectorIndex>();
+  let mut partitions = index.lock();
+  partitions.clear();
+  if let Ok(root) = vectors_dir(app) {
+    if let Err(e) = fs::remove_dir_all(&root) {
+      if e.kind() != std::io::ErrorKind::NotFound {
+        log::warn!("Failed to clear the vector index: {}", e);
+      }
+    }
+  }
+}
+
+/// Runs an index operation off the async runtime
+async fn run<T: Send + 'static>(
+  app: &AppHandle,
+  op: impl FnOnce(&VectorIndex, &Path) -> Result<T, String> + Send + 'static,
+) -> Result<T, String> {
+  let root = vectors_dir(app)?;
+  let app = app.clone();
+  tauri::async_runtime::spawn_blocking(move || op(&app.state::<VectorIndex>(), &root))
+    .await
+    .map_err(|e| format!("Vector index task failed: {}", e))?
+}
+
+#[tauri::command]
+pub async fn vector_insert(
+  app: AppHandle,
+  user_id: i64,
+  course: String,
+  items: Vec<VectorItem>,
+) -> Result<usize, String> {
+  let request = InsertRequest { user_id, course, items };
+  run(&app, move |index, root| index.insert(root, request)).await
+}
+
+#[tauri::command]
+pub async fn vector_query(
+  app: AppHandle,
+  user_id: i64,
+  course: Option<String>,
+  vector: Vec<f32>,
+  top_k: Option<usize>,
+  filter: Option<Metadata>,
+  min_score: Option<f32>,
+) -> Result<QueryResult, String> {
+  let filter = filter.unwrap_or_default();
+  let request = QueryRequest { user_id, course, vector, top_k, filter, min_score };
+  run(&app, move |index, root| index.query(root, request)).await
+}
+
+#[tauri::command]
+pub async fn vector_delete(
+  app: AppHandle,
+  user_id: i64,
+  course: Option<String>,
+  ids: Option<Vec<String>>,
+  filter: Option<Metadata>,
+) -> Result<usize, String> {
+  let filter = filter.unwrap_or_default();
+  let request = DeleteRequest { user_id, course, ids, filter };
+  run(&app, move |index, root| index.delete(root, request)).await
+}
+
+type HttpResult = Result<Json<Value>, (StatusCode, Json<Value>)>;
+
+/// Same token as the backend's `/api/internal` routes; without it the
+/// routes do not exist
+fn authorize(app: &AppHandle, headers: &HeaderMap) -> Result<(), (StatusCode, Json<Value>)> {
+  let token = headers.get(backend::SHELL_TOKEN_HEADER).and_then(|v| v.to_str().ok());
+  if token != Some(app.state::<BackendState>().shell_token()) {
+    return Err((StatusCode::NOT_FOUND, Json(Value::Null)));
+  }
+  Ok(())
+}
+
+fn respond<T: Serialize>(result: Result<T, String>) -> HttpResult {
+  match result {
+    Ok(value) => Ok(Json(serde_json::to_value(value).unwrap_or_default())),
+    Err(message) => {
+      Err((StatusCode::BAD_REQUEST, Json(serde_json::json!({ "message": message }))))
+    }
+  }
+}
+
+async fn http_insert(
+  State(app): State<AppHandle>,
+  headers: HeaderMap,
+  Json(request): Json<InsertRequest>,
+) -> HttpResult {
+  authorize(&app, &headers)?;
+  let result = run(&app, move |index, root| index.insert(root, request)).await;
+  respond(result.map(|inserted| serde_json::json!({ "inserted": inserted })))
+}
+
+async fn http_query(
+  State(app): State<AppHandle>,
+  headers: HeaderMap,
+  Json(request): Json<QueryRequest>,
+) -> HttpResult {
+  authorize(&app, &headers)?;
+  respond(run(&app, move |index, root| index.query(root, request)).await)
+}
+
+async fn http_delete(
+  State(app): State<AppHandle>,
+  headers: HeaderMap,
+  Json(request): Json<DeleteRequest>,
+) -> HttpResult {
+  authorize(&app, &headers)?;
+  let result = run(&app, move |index, root| index.delete(root, request)).await;
+  respond(result.map(|deleted| serde_json::json!({ "deleted": deleted })))
+}
+
+async fn http_chunk(
+  State(app): State<AppHandle>,
+  headers: HeaderMap,
+  Json(request): Json<ChunkRequest>,
+) -> HttpResult {
+  authorize(&app, &headers)?;
+  respond(chunker::chunk(request).await)
+}
+
+/// Serves the index, and the chunker that feeds it, to the backend on a
+/// loopback port, handed to it as `FORGE_VECTOR_URL`. Bound before
+/// returning so the URL is known by the time the sidecar's environment is
+/// built.
+pub fn spawn_server(app: AppHandle) -> Result<(), String> {
+  let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))
+    .and_then(|listener| listener.set_nonblocking(true).map(|_| listener))
+    .map_err(|e| format!("Failed to start the vector index server: {}", e))?;
+  let port = listener.local_addr().map_err(|e| e.to_string())?.port();
+  let _ = app.state::<VectorIndex>().url.set(format!("http://127.0.0.1:{}", port));
+
+  let router = Router::new()
+    .route("/vectors/insert", post(http_insert))
+    .route("/vectors/query", post(http_query))
+    .route("/vectors/delete", post(http_delete))
+    .route("/chunks", post(http_chunk))
+    .layer(DefaultBodyLimit::max(MAX_BODY_BYTES))
+    .with_state(app);
+  tauri::async_runtime::spawn(async move {
+    let served = match tokio::net::TcpListener::from_std(listener) {
+      Ok(listener) => axum::serve(listener, router).await,
+      Err(e) => Err(e),
+    };
+    if let Err(e) = served {
+      log::error!("Vector index server stopped: {}", e);
+    }
+  });
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test_util::TempDir;
+
+  fn sample() -> Partition {
+    let entry = |id: &str, page: i64| Entry {
+      id: id.to_string(),
+      text: format!("chunk {}", id),
+      metadata: serde_json::json!({ "fileId": 3, "page": page }).as_object().unwrap().clone(),
+    };
+    Partition {
+      dim: 3,
+      entries: vec![entry("1", 1), entry("2", 4)],
+      vectors: vec![0.6, 0.8, 0.0, -1.0, 0.0, f32::MIN_POSITIVE],
+    }
+  }
+
+  #[test]
+  fn sanitizes_course_codes_like_the_backend() {
+    assert_eq!(sanitize_course(" cs-101 ").unwrap(), "CS-101");
+    assert_eq!(sanitize_course("intro_bio").unwrap(), "INTRO_BIO");
+    assert_eq!(sanitize_course("../phys 2.0").unwrap(), "PHYS 20");
+    // `\s` keeps tabs and non-breaking spaces, drops NEL and trims a BOM
+    assert_eq!(sanitize_course("Phys\t101").unwrap(), "PHYS\t101");
+    assert_eq!(sanitize_course("math\u{a0}2\u{85}").unwrap(), "MATH\u{a0}2");
+    assert_eq!(sanitize_course("\u{feff}chem\u{3000}").unwrap(), "CHEM");
+    assert!(sanitize_course("!!!").is_err());
+    assert!(sanitize_course(" \t ").is_err());
+  }
+
+  #[test]
+  fn names_partitions_by_user_and_course() {
+    let root = Path::new("index");
+    assert_eq!(
+      partition_path(root, &(7, "CS 101".to_string())),
+      root.join("user_7").join("course_CS 101.fvec")
+    );
+    assert_eq!(
+      partition_path(root, &(7, "PHYS\t101".to_string())),
+      root.join("user_7").join("course_PHYS%09101.fvec")
+    );
+  }
+
+  #[test]
+  fn lists_the_partitions_written() {
+    let dir = TempDir::new();
+    let courses = ["CS 101", "PHYS\t101", "CON"];
+    for course in courses {
+      write_partition(&partition_path(dir.path(), &(7, course.to_string())), &sample()).unwrap();
+    }
+    write_partition(&partition_path(dir.path(), &(12, "BIO".to_string())), &sample()).unwrap();
+    fs::write(user_dir(dir.path(), 7).join("notes.txt"), b"").unwrap();
+
+    assert_eq!(list_courses(dir.path(), 7), vec!["CON", "CS 101", "PHYS\t101"]);
+    assert!(list_courses(dir.path(), 99).is_empty());
+  }
+
+  #[test]
+  fn round_trips_partitions() {
+    let partition = sample();
+    let bytes = encode(&partition).unwrap();
+    assert_eq!(&bytes[..4], MAGIC);
+    assert_eq!(u32::from_le_bytes(bytes[4..8].try_into().unwrap()), FORMAT);
+
+    let decoded = decode(&bytes).unwrap();
+    assert_eq!(decoded.dim, partition.dim);
+    assert_eq!(decoded.vectors, partition.vectors);
+    assert_eq!(decoded.entries.len(), 2);
+    for (a, b) in decoded.entries.iter().zip(&partition.entries) {
+      assert_eq!((&a.id, &a.text, &a.metadata), (&b.id, &b.text, &b.metadata));
+    }
+    assert_eq!(decoded.vector(1), &[-1.0, 0.0, f32::MIN_POSITIVE]);
+  }
+
+  #[test]
+  fn rejects_damaged_partitions() {
+    let bytes = encode(&sample()).unwrap();
+
+    let mut magic = bytes.clone();
+    magic[0] = b'X';
+    assert_eq!(decode(&magic).err().unwrap(), "not a vector partition");
+
+    let mut format = bytes.clone();
+    format[4..8].copy_from_slice(&2u32.to_le_bytes());
+    assert_eq!(decode(&format).err().unwrap(), "unsupported format 2");
+
+    let mut header = bytes.clone();
+    header[8..16].copy_from_slice(&u64::MAX.to_le_bytes());
+    assert_eq!(decode(&header).err().unwrap(), "truncated header");
+
+    let short = &bytes[..bytes.len() - 1];
+    assert_eq!(decode(short).err().unwrap(), "vector data does not match the header");
+    assert!(decode(&bytes[..10]).is_err());
+  }
+
+  #[test]
+  fn writes_and_removes_partition_files() {
+    let dir = TempDir::new();
+    let path = partition_path(dir.path(), &(1, "CS".to_string()));
+    write_partition(&path, &sample()).unwrap();
+    assert_eq!(read_partition(&path).unwrap().entries.len(), 2);
+    assert!(!path.with_extension("tmp").exists());
+
+    write_partition(&path, &Partition::default()).unwrap();
+    assert!(!path.exists());
+    // Removing one that is already gone is fine
+    write_partition(&path, &Partition::default()).unwrap();
+  }
+
+  fn item(id: &str, vector: &[f32], metadata: Value) -> VectorItem {
+    VectorItem {
+      id: id.to_string(),
+      vector: vector.to_vec(),
+      text: format!("chunk {}", id),
+      metadata: metadata.as_object().unwrap().clone(),
+    }
+  }
+
+  fn insert(index: &VectorIndex, root: &Path, user_id: i64, course: &str, items: Vec<VectorItem>) {
+    let request = InsertRequest { user_id, course: course.to_string(), items };
+    index.insert(root, request).unwrap();
+  }
+
+  fn query(user_id: i64, vector: &[f32]) -> QueryRequest {
+    QueryRequest {
+      user_id,
+      course: None,
+      vector: vector.to_vec(),
+      top_k: None,
+      filter: Metadata::new(),
+      min_score: None,
+    }
+  }
+
+  fn delete(user_id: i64) -> DeleteRequest {
+    DeleteRequest { user_id, course: None, ids: None, filter: Metadata::new() }
+  }
+
+  fn ids(result: &QueryResult) -> Vec<&str> {
+    result.matches.iter().map(|m| m.id.as_str()).collect()
+  }
+
+  /// Four chunks for user 1 across two courses, at known angles from [1, 0]
+  fn seeded(dir: &TempDir) -> VectorIndex {
+    let index = VectorIndex::new();
+    let cs = vec![
+      item("a", &[1.0, 0.0], serde_json::json!({ "fileId": 1, "page": 1 })),
+      item("b", &[0.0, 2.0], serde_json::json!({ "fileId": 1, "page": 2 })),
+      item("c", &[3.0, 3.0], serde_json::json!({ "fileId": 2, "page": 1 })),
+    ];
+    insert(&index, dir.path(), 1, "CS 101", cs);
+    let bio = vec![item("d", &[-1.0, 0.5], serde_json::json!({ "fileId": 3, "page": 1.0 }))];
+    insert(&index, dir.path(), 1, "bio", bio);
+    index
+  }
+
+  #[test]
+  fn query_ranks_by_cosine_similarity() {
+    let dir = TempDir::new();
+    let index = seeded(&dir);
+
+    let result = index.query(dir.path(), query(1, &[2.0, 0.0])).unwrap();
+    assert_eq!(ids(&result), ["a", "c", "b", "d"]);
+    assert_eq!(result.total, 4);
+    assert!((result.matches[0].score - 1.0).abs() < 1e-6);
+    assert!((result.matches[1].score - std::f32::consts::FRAC_1_SQRT_2).abs() < 1e-6);
+    assert_eq!(result.matches[3].course, "BIO");
+
+    let top = index.query(dir.path(), QueryRequest { top_k: Some(2), ..query(1, &[0.0, 1.0]) });
+    assert_eq!(ids(&top.unwrap()), ["b", "c"]);
+
+    let course = Some("cs 101".to_string());
+    let result = index.query(dir.path(), QueryRequest { course, ..query(1, &[1.0, 0.0]) });
+    assert_eq!(result.unwrap().total, 3);
+  }
+
+  #[test]
+  fn query_drops_matches_below_min_score() {
+    let dir = TempDir::new();
+    let index = seeded(&dir);
+    let request = QueryRequest { min_score: Some(0.5), ..query(1, &[1.0, 0.0]) };
+    let result = index.query(dir.path(), request).unwrap();
+    assert_eq!(ids(&result), ["a", "c"]);
+    // The total still counts everything searched
+    assert_eq!(result.total, 4);
+  }
+
+  #[test]
+  fn query_applies_metadata_filters() {
+    let dir = TempDir::new();
+    let index = seeded(&dir);
+    let filtered = |filter: Value| {
+      let filter = filter.as_object().unwrap().clone();
+      let result = index.query(dir.path(), QueryRequest { filter, ..query(1, &[1.0, 0.0]) });
+      ids(&result.unwrap()).into_iter().map(String::from).collect::<Vec<_>>()
+    };
+    assert_eq!(filtered(serde_json::json!({ "fileId": 1 })), ["a", "b"]);
+    assert_eq!(filtered(serde_json::json!({ "fileId": 1, "page": 2 })), ["b"]);
+    assert_eq!(filtered(serde_json::json!({ "fileId": [2, 3] })), ["c", "d"]);
+    // 1 and 1.0 are the same page
+    assert_eq!(filtered(serde_json::json!({ "fileId": 3, "page": 1 })), ["d"]);
+    assert!(filtered(serde_json::json!({ "section": "intro" })).is_empty());
+  }
+
+  #[test]
+  fn rejects_mismatched_dimensions() {
+    let dir = TempDir::new();
+    let index = seeded(&dir);
+
+    let none = || serde_json::json!({});
+    let mixed = vec![item("e", &[1.0, 0.0], none()), item("f", &[1.0, 0.0, 0.0], none())];
+    let request = InsertRequest { user_id: 1, course: "CHEM".to_string(), items: mixed };
+    assert_eq!(
+      index.insert(dir.path(), request).unwrap_err(),
+      "Vector f has 3 dimensions, expected 2"
+    );
+    assert!(list_courses(dir.path(), 1).iter().all(|course| course != "CHEM"));
+
+    let wider = vec![item("g", &[1.0, 0.0, 0.0], none())];
+    let request = InsertRequest { user_id: 1, course: "cs 101".to_string(), items: wider };
+    assert_eq!(
+      index.insert(dir.path(), request).unwrap_err(),
+      "Vectors for CS 101 have 3 dimensions, expected 2"
+    );
+
+    // Partitions built for another size are skipped rather than failing
+    let result = index.query(dir.path(), query(1, &[1.0, 0.0, 0.0])).unwrap();
+    assert!(result.matches.is_empty());
+    assert_eq!(result.total, 4);
+    assert!(index.query(dir.path(), query(1, &[0.0, 0.0])).is_err());
+    assert!(index.query(dir.path(), query(1, &[])).is_err());
+  }
+
+  #[test]
+  fn insert_replaces_items_with_the_same_id() {
+    let dir = TempDir::new();
+    let index = seeded(&dir);
+    let replacement = vec![item("a", &[0.0, -1.0], serde_json::json!({ "fileId": 9 }))];
+    insert(&index, dir.path(), 1, "CS 101", replacement);
+
+    let result = index.query(dir.path(), query(1, &[0.0, -1.0])).unwrap();
+    assert_eq!(result.total, 4);
+    assert_eq!(result.matches[0].id, "a");
+    assert!((result.matches[0].score - 1.0).abs() < 1e-6);
+    assert_eq!(result.matches[0].metadata["fileId"], 9);
+
+    // And the replacement is what a fresh load sees
+    let reloaded = VectorIndex::new().query(dir.path(), query(1, &[0.0, -1.0])).unwrap();
+    assert_eq!(ids(&reloaded), ids(&result));
+  }
+
+  #[test]
+  fn delete_stays_within_the_user() {
+    let dir = TempDir::new();
+    let index = seeded(&dir);
+    let other = vec![item("a", &[1.0, 0.0], serde_json::json!({ "fileId": 1 }))];
+    insert(&index, dir.path(), 2, "CS 101", other);
+
+    let by_file = serde_json::json!({ "fileId": 1 }).as_object().unwrap().clone();
+    assert_eq!(index.delete(dir.path(), DeleteRequest { filter: by_file, ..delete(1) }), Ok(2));
+    assert_eq!(ids(&index.query(dir.path(), query(1, &[1.0, 0.0])).unwrap()), ["c", "d"]);
+    assert_eq!(index.query(dir.path(), query(2, &[1.0, 0.0])).unwrap().total, 1);
+
+    let by_id = Some(vec!["d".to_string(), "missing".to_string()]);
+    assert_eq!(index.delete(dir.path(), DeleteRequest { ids: by_id, ..delete(1) }), Ok(1));
+
+    let course = Some("bio".to_string());
+    assert_eq!(index.delete(dir.path(), DeleteRequest { course, ..delete(1) }), Ok(0));
+
+    // No course, ids or filter clears the user and no one else
+    assert_eq!(index.delete(dir.path(), delete(1)), Ok(1));
+    assert_eq!(index.query(dir.path(), query(1, &[1.0, 0.0])).unwrap().total, 0);
+    assert!(list_courses(dir.path(), 1).is_empty());
+    assert_eq!(list_courses(dir.path(), 2), ["CS 101"]);
+  }
+}